@@ -0,0 +1,439 @@
+//! Single-file backups of the app data directory.
+//!
+//! An archive is a zip of every file under the data directory plus a
+//! `backup-manifest.json` naming the schema version it was written with.
+//! SQLite databases are archived from a snapshot rather than their live
+//! files, so rows still in the WAL are included and the databases may live
+//! outside the data directory. Restores refuse archives from any other schema
+//! version.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Bump whenever the layout of the store or the stats DB changes in a way an
+/// older build could not read.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "backup-manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub schema_version: u32,
+    pub app_version: String,
+    pub created_at: i64,
+}
+
+/// A SQLite database the app keeps open.
+#[derive(Debug, Clone)]
+pub struct Database {
+    /// Name inside the archive.
+    pub entry: String,
+    pub path: PathBuf,
+}
+
+impl Database {
+    /// The database file and its WAL and shared-memory sidecars.
+    fn files(&self) -> [PathBuf; 3] {
+        ["", "-wal", "-shm"].map(|suffix| {
+            let mut file = self.path.clone().into_os_string();
+            file.push(suffix);
+            PathBuf::from(file)
+        })
+    }
+}
+
+/// Sidecars of databases we do not snapshot are rebuilt on open.
+fn is_transient(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with("-wal") || name.ends_with("-shm"))
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn entry_name(file: &Path, data_dir: &Path) -> Result<String, String> {
+    Ok(file
+        .strip_prefix(data_dir)
+        .map_err(|e| format!("Failed to name backup entry: {e}"))?
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// Zip `data_dir` and `databases` into `dest`. Returns the number of data
+/// files archived.
+pub fn write_archive(
+    data_dir: &Path,
+    dest: &Path,
+    manifest: &BackupManifest,
+    databases: &[Database],
+) -> Result<usize, String> {
+    let mut files = Vec::new();
+    if data_dir.exists() {
+        collect_files(data_dir, &mut files)
+            .map_err(|e| format!("Failed to read app data directory: {e}"))?;
+    }
+    let database_files = databases
+        .iter()
+        .flat_map(Database::files)
+        .collect::<Vec<_>>();
+    // The archive may itself be written inside the data directory.
+    files.retain(|file| file != dest && !is_transient(file) && !database_files.contains(file));
+
+    let archive = File::create(dest).map_err(|e| format!("Failed to create backup file: {e}"))?;
+    let mut writer = ZipWriter::new(archive);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    writer
+        .start_file(MANIFEST_NAME, options)
+        .map_err(|e| format!("Failed to write backup manifest: {e}"))?;
+    serde_json::to_writer_pretty(&mut writer, manifest)
+        .map_err(|e| format!("Failed to write backup manifest: {e}"))?;
+
+    let mut archived = 0;
+    for database in databases.iter().filter(|database| database.path.exists()) {
+        let snapshot =
+            std::env::temp_dir().join(format!("{}.{}.backup", database.entry, std::process::id()));
+        let _ = fs::remove_file(&snapshot);
+        let written = crate::db::snapshot(&database.path, &snapshot).and_then(|()| {
+            writer
+                .start_file(database.entry.as_str(), options)
+                .map_err(|e| format!("Failed to add {} to backup: {e}", database.entry))?;
+            let mut source = File::open(&snapshot)
+                .map_err(|e| format!("Failed to read {}: {e}", database.entry))?;
+            io::copy(&mut source, &mut writer)
+                .map_err(|e| format!("Failed to add {} to backup: {e}", database.entry))
+        });
+        let _ = fs::remove_file(&snapshot);
+        written?;
+        archived += 1;
+    }
+
+    for file in &files {
+        let name = entry_name(file, data_dir)?;
+        writer
+            .start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to add {name} to backup: {e}"))?;
+        let mut source = File::open(file).map_err(|e| format!("Failed to read {name}: {e}"))?;
+        io::copy(&mut source, &mut writer)
+            .map_err(|e| format!("Failed to add {name} to backup: {e}"))?;
+    }
+
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to finish backup file: {e}"))?;
+    Ok(archived + files.len())
+}
+
+fn open_archive(src: &Path) -> Result<ZipArchive<File>, String> {
+    let file = File::open(src).map_err(|e| format!("Failed to open backup file: {e}"))?;
+    ZipArchive::new(file).map_err(|e| format!("Not a valid backup archive: {e}"))
+}
+
+pub fn read_manifest(src: &Path) -> Result<BackupManifest, String> {
+    let mut archive = open_archive(src)?;
+    let manifest = archive
+        .by_name(MANIFEST_NAME)
+        .map_err(|_| "Backup archive has no manifest".to_string())?;
+    serde_json::from_reader(manifest).map_err(|e| format!("Backup manifest is invalid: {e}"))
+}
+
+/// Where a restore is unpacked before it replaces anything: a sibling of the
+/// data directory, so moving files into place is a rename.
+fn staging_dir(data_dir: &Path) -> PathBuf {
+    let mut name = data_dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".restore");
+    data_dir.with_file_name(name)
+}
+
+/// A backup unpacked and checked beside the files it replaces. Nothing the
+/// user has is touched until `apply`; dropping it discards the staging
+/// directory.
+#[derive(Debug)]
+pub struct StagedRestore {
+    root: PathBuf,
+    /// Staged file and where it goes.
+    files: Vec<(PathBuf, PathBuf)>,
+    /// Current files the restore replaces or drops.
+    existing: Vec<PathBuf>,
+}
+
+/// Unpack `src` into a staging directory next to `data_dir` and check every
+/// entry and database in it. Entry paths are checked before anything is
+/// written, so an archive that tries to escape the directory writes nothing.
+pub fn stage_archive(
+    src: &Path,
+    data_dir: &Path,
+    databases: &[Database],
+) -> Result<StagedRestore, String> {
+    let mut archive = open_archive(src)?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read backup entry: {e}"))?;
+        if entry.is_dir() || entry.name() == MANIFEST_NAME {
+            continue;
+        }
+        if let Some(database) = databases
+            .iter()
+            .find(|database| database.entry == entry.name())
+        {
+            entries.push((index, database.path.clone(), true));
+            continue;
+        }
+        let Some(relative) = entry.enclosed_name() else {
+            return Err(format!(
+                "Backup entry '{}' escapes the data directory",
+                entry.name()
+            ));
+        };
+        entries.push((index, data_dir.join(relative), false));
+    }
+
+    let root = staging_dir(data_dir);
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("new"))
+        .map_err(|e| format!("Failed to create {}: {e}", root.display()))?;
+    let mut staged = StagedRestore {
+        root,
+        files: Vec::with_capacity(entries.len()),
+        existing: Vec::new(),
+    };
+    for (index, target, is_database) in entries {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read backup entry: {e}"))?;
+        let file = staged.root.join("new").join(index.to_string());
+        let mut output = File::create(&file)
+            .map_err(|e| format!("Failed to stage {}: {e}", target.display()))?;
+        // The zip reader checks each entry's CRC as it reaches the end.
+        io::copy(&mut entry, &mut output)
+            .map_err(|e| format!("Failed to restore {}: {e}", target.display()))?;
+        drop(output);
+        if is_database {
+            crate::db::quick_check(&file)?;
+        }
+        staged.files.push((file, target));
+    }
+
+    if data_dir.exists() {
+        collect_files(data_dir, &mut staged.existing)
+            .map_err(|e| format!("Failed to read app data directory: {e}"))?;
+    }
+    staged
+        .existing
+        .extend(databases.iter().flat_map(Database::files));
+    // The archive may itself sit in the data directory.
+    staged.existing.retain(|file| file != src);
+    Ok(staged)
+}
+
+impl StagedRestore {
+    /// Swap the staged files in for the current ones; files the archive
+    /// lacks do not survive. The databases must not be open. A failed swap
+    /// moves everything back first. Returns the number of files restored.
+    pub fn apply(self) -> Result<usize, String> {
+        let mut moved = Vec::new();
+        if let Err(e) = self.swap(&mut moved) {
+            for (from, to) in moved.iter().rev() {
+                if let Err(undo) = fs::rename(to, from) {
+                    log::error!(
+                        "Failed to move {} back to {}: {undo}",
+                        to.display(),
+                        from.display()
+                    );
+                }
+            }
+            return Err(e);
+        }
+        Ok(self.files.len())
+    }
+
+    /// Move each file, recording it in `moved` as it goes.
+    fn swap(&self, moved: &mut Vec<(PathBuf, PathBuf)>) -> Result<(), String> {
+        let aside = self.root.join("old");
+        fs::create_dir_all(&aside)
+            .map_err(|e| format!("Failed to create {}: {e}", aside.display()))?;
+        for (index, file) in self.existing.iter().enumerate() {
+            if !file.exists() {
+                continue;
+            }
+            let old = aside.join(index.to_string());
+            fs::rename(file, &old)
+                .map_err(|e| format!("Failed to replace {}: {e}", file.display()))?;
+            moved.push((file.clone(), old));
+        }
+        for (file, target) in &self.files {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+            }
+            fs::rename(file, target)
+                .map_err(|e| format!("Failed to restore {}: {e}", target.display()))?;
+            moved.push((file.clone(), target.clone()));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StagedRestore {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn restore(src: &Path, data_dir: &Path, databases: &[Database]) -> Result<usize, String> {
+        stage_archive(src, data_dir, databases)?.apply()
+    }
+
+    #[test]
+    fn round_trip_includes_wal_rows_and_drops_extra_files() {
+        let base = std::env::temp_dir().join(format!("backup-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let data_dir = base.join("data");
+        fs::create_dir_all(data_dir.join("sub")).unwrap();
+        fs::write(data_dir.join("settings.json"), "{}").unwrap();
+        fs::write(data_dir.join("sub/notes.txt"), "kept").unwrap();
+        let databases = [Database {
+            entry: "history.db".to_string(),
+            path: base.join("config/history.db"),
+        }];
+        fs::create_dir_all(base.join("config")).unwrap();
+
+        // Leave a row in the WAL: the connection stays open, uncheckpointed.
+        let live = Connection::open(&databases[0].path).unwrap();
+        live.execute_batch(
+            "PRAGMA journal_mode = WAL; PRAGMA wal_autocheckpoint = 0;
+             CREATE TABLE t (v TEXT); INSERT INTO t VALUES ('in the wal');",
+        )
+        .unwrap();
+
+        let manifest = BackupManifest {
+            schema_version: SCHEMA_VERSION,
+            app_version: "1.0.0".to_string(),
+            created_at: 0,
+        };
+        let archive = base.join("backup.zip");
+        assert_eq!(
+            write_archive(&data_dir, &archive, &manifest, &databases).unwrap(),
+            3
+        );
+        drop(live);
+
+        fs::write(data_dir.join("stray.txt"), "not in the backup").unwrap();
+        fs::write(data_dir.join("settings.json"), "{\"changed\":true}").unwrap();
+        assert_eq!(restore(&archive, &data_dir, &databases).unwrap(), 3);
+        assert!(!data_dir.join("stray.txt").exists());
+        assert_eq!(
+            fs::read_to_string(data_dir.join("settings.json")).unwrap(),
+            "{}"
+        );
+        assert_eq!(
+            fs::read_to_string(data_dir.join("sub/notes.txt")).unwrap(),
+            "kept"
+        );
+        let restored = Connection::open(&databases[0].path).unwrap();
+        let value: String = restored
+            .query_row("SELECT v FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, "in the wal");
+        drop(restored);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn damaged_archive_leaves_current_files_in_place() {
+        let base = std::env::temp_dir().join(format!("backup-damaged-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let data_dir = base.join("data");
+        fs::create_dir_all(&data_dir).unwrap();
+        // Incompressible, so most of the archive is this entry's data.
+        let mut seed = 1u32;
+        let noise = (0..64 * 1024)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 24) as u8
+            })
+            .collect::<Vec<_>>();
+        fs::write(data_dir.join("noise.bin"), &noise).unwrap();
+        fs::write(data_dir.join("settings.json"), "{}").unwrap();
+        let databases = [Database {
+            entry: "history.db".to_string(),
+            path: base.join("history.db"),
+        }];
+        Connection::open(&databases[0].path)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (v TEXT); INSERT INTO t VALUES ('backed up');")
+            .unwrap();
+        let manifest = BackupManifest {
+            schema_version: SCHEMA_VERSION,
+            app_version: "1.0.0".to_string(),
+            created_at: 0,
+        };
+        let archive = base.join("backup.zip");
+        write_archive(&data_dir, &archive, &manifest, &databases).unwrap();
+        let bytes = fs::read(&archive).unwrap();
+
+        fs::write(data_dir.join("settings.json"), "{\"current\":true}").unwrap();
+        fs::write(data_dir.join("noise.bin"), "current").unwrap();
+        let assert_untouched = || {
+            assert_eq!(
+                fs::read_to_string(data_dir.join("settings.json")).unwrap(),
+                "{\"current\":true}"
+            );
+            assert_eq!(
+                fs::read_to_string(data_dir.join("noise.bin")).unwrap(),
+                "current"
+            );
+            let value: String = Connection::open(&databases[0].path)
+                .unwrap()
+                .query_row("SELECT v FROM t", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(value, "backed up");
+            assert!(!staging_dir(&data_dir).exists());
+        };
+
+        let truncated = base.join("truncated.zip");
+        fs::write(&truncated, &bytes[..bytes.len() / 2]).unwrap();
+        assert!(restore(&truncated, &data_dir, &databases).is_err());
+        assert_untouched();
+
+        // Intact headers, damaged data: fails partway through extraction.
+        let mut corrupt = bytes.clone();
+        let middle = corrupt.len() / 2;
+        for byte in &mut corrupt[middle..middle + 16] {
+            *byte ^= 0xff;
+        }
+        let damaged = base.join("damaged.zip");
+        fs::write(&damaged, &corrupt).unwrap();
+        assert!(restore(&damaged, &data_dir, &databases).is_err());
+        assert_untouched();
+
+        assert_eq!(restore(&archive, &data_dir, &databases).unwrap(), 3);
+        assert_eq!(fs::read(data_dir.join("noise.bin")).unwrap(), noise);
+        assert!(!staging_dir(&data_dir).exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,373 @@
+//! Rust-owned SQLite storage for conversion history and message snippets.
+
+use rusqlite::{params, Connection, ErrorCode, OpenFlags, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+
+/// Only the newest conversions are kept.
+pub const CONVERSION_HISTORY_LIMIT: u32 = 100;
+/// Daily tallies older than this many days are dropped.
+const DAILY_COUNT_RETENTION_DAYS: i64 = 7;
+pub const SNIPPET_LIMIT: u32 = 50;
+pub const SNIPPET_NAME_MAX_CHARS: usize = 64;
+pub const SNIPPET_TEMPLATE_MAX_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conversion {
+    pub id: i64,
+    pub input: String,
+    pub unix: i64,
+    pub format: String,
+    pub created_at: i64,
+}
+
+/// A reusable message; `{{time}}` markers are filled in by `render_template`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub name: String,
+    pub template: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+pub fn open(path: &Path) -> Result<Connection, String> {
+    let connection =
+        Connection::open(path).map_err(|e| format!("Failed to open stats database: {e}"))?;
+    connection
+        .pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| format!("Failed to enable WAL for stats database: {e}"))?;
+    migrate(&connection)?;
+    Ok(connection)
+}
+
+fn migrate(connection: &Connection) -> Result<(), String> {
+    connection
+        .execute_batch(
+            "CREATE TABLE IF NOT EXISTS conversions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                input TEXT NOT NULL,
+                unix INTEGER NOT NULL,
+                format TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS conversions_created_at
+                ON conversions (created_at DESC, id DESC);
+            CREATE TABLE IF NOT EXISTS snippets (
+                name TEXT PRIMARY KEY COLLATE NOCASE,
+                template TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS daily_conversion_counts (
+                day_start INTEGER PRIMARY KEY,
+                count INTEGER NOT NULL
+            );",
+        )
+        .map_err(|e| format!("Failed to migrate stats database: {e}"))
+}
+
+pub fn add_conversion(
+    connection: &Connection,
+    input: &str,
+    unix: i64,
+    format: &str,
+    created_at: i64,
+) -> Result<Conversion, String> {
+    connection
+        .execute(
+            "INSERT INTO conversions (input, unix, format, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![input, unix, format, created_at],
+        )
+        .map_err(|e| format!("Failed to record conversion: {e}"))?;
+    let id = connection.last_insert_rowid();
+
+    connection
+        .execute(
+            "DELETE FROM conversions WHERE id NOT IN (
+                SELECT id FROM conversions ORDER BY created_at DESC, id DESC LIMIT ?1
+            )",
+            params![CONVERSION_HISTORY_LIMIT],
+        )
+        .map_err(|e| format!("Failed to prune conversion history: {e}"))?;
+
+    Ok(Conversion {
+        id,
+        input: input.to_string(),
+        unix,
+        format: format.to_string(),
+        created_at,
+    })
+}
+
+/// Newest first.
+pub fn recent_conversions(connection: &Connection, limit: u32) -> Result<Vec<Conversion>, String> {
+    let mut statement = connection
+        .prepare(
+            "SELECT id, input, unix, format, created_at FROM conversions
+             ORDER BY created_at DESC, id DESC LIMIT ?1",
+        )
+        .map_err(|e| format!("Failed to query conversion history: {e}"))?;
+    let rows = statement
+        .query_map(params![limit.min(CONVERSION_HISTORY_LIMIT)], |row| {
+            Ok(Conversion {
+                id: row.get(0)?,
+                input: row.get(1)?,
+                unix: row.get(2)?,
+                format: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query conversion history: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read conversion history: {e}"))
+}
+
+pub fn count_conversions_since(connection: &Connection, since: i64) -> Result<u32, String> {
+    connection
+        .query_row(
+            "SELECT COUNT(*) FROM conversions WHERE created_at >= ?1",
+            params![since],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count conversions: {e}"))
+}
+
+/// Count one conversion towards the local day starting at `day_start`.
+/// History is pruned to `CONVERSION_HISTORY_LIMIT`, so per-day totals are
+/// tallied separately.
+pub fn count_conversion_on_day(connection: &Connection, day_start: i64) -> Result<(), String> {
+    connection
+        .execute(
+            "INSERT INTO daily_conversion_counts (day_start, count) VALUES (?1, 1)
+             ON CONFLICT (day_start) DO UPDATE SET count = count + 1",
+            params![day_start],
+        )
+        .map_err(|e| format!("Failed to count conversion: {e}"))?;
+    connection
+        .execute(
+            "DELETE FROM daily_conversion_counts WHERE day_start < ?1",
+            params![day_start - DAILY_COUNT_RETENTION_DAYS * 86_400],
+        )
+        .map(|_| ())
+        .map_err(|e| format!("Failed to prune daily conversion counts: {e}"))
+}
+
+pub fn conversions_on_day(connection: &Connection, day_start: i64) -> Result<u32, String> {
+    connection
+        .query_row(
+            "SELECT count FROM daily_conversion_counts WHERE day_start = ?1",
+            params![day_start],
+            |row| row.get(0),
+        )
+        .optional()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| format!("Failed to count conversions: {e}"))
+}
+
+/// Also resets the daily tallies, so cleared history reads as none today.
+pub fn clear_conversions(connection: &Connection) -> Result<(), String> {
+    connection
+        .execute_batch("DELETE FROM conversions; DELETE FROM daily_conversion_counts;")
+        .map_err(|e| format!("Failed to clear conversion history: {e}"))
+}
+
+pub fn validate_snippet(name: &str, template: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Snippet name must not be empty".to_string());
+    }
+    if name.trim().chars().count() > SNIPPET_NAME_MAX_CHARS {
+        return Err(format!(
+            "Snippet name must be at most {SNIPPET_NAME_MAX_CHARS} characters"
+        ));
+    }
+    if template.trim().is_empty() {
+        return Err("Snippet template must not be empty".to_string());
+    }
+    if template.chars().count() > SNIPPET_TEMPLATE_MAX_CHARS {
+        return Err(format!(
+            "Snippet template must be at most {SNIPPET_TEMPLATE_MAX_CHARS} characters"
+        ));
+    }
+    Ok(())
+}
+
+/// Insert a snippet, or replace the one already saved under `name`; names
+/// match ignoring ASCII case. Callers enforce `SNIPPET_LIMIT`.
+pub fn save_snippet(
+    connection: &Connection,
+    name: &str,
+    template: &str,
+    now: i64,
+) -> Result<Snippet, String> {
+    let name = name.trim();
+    let existing = connection
+        .query_row(
+            "SELECT name, created_at FROM snippets WHERE name = ?1",
+            params![name],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to look up snippet: {e}"))?;
+    let created_at = match existing {
+        Some((old_name, created_at)) => {
+            connection
+                .execute(
+                    "UPDATE snippets SET name = ?1, template = ?2, updated_at = ?3
+                     WHERE name = ?4",
+                    params![name, template, now, old_name],
+                )
+                .map_err(|e| format!("Failed to update snippet: {e}"))?;
+            created_at
+        }
+        None => {
+            connection
+                .execute(
+                    "INSERT INTO snippets (name, template, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?3)",
+                    params![name, template, now],
+                )
+                .map_err(|e| format!("Failed to save snippet: {e}"))?;
+            now
+        }
+    };
+    Ok(Snippet {
+        name: name.to_string(),
+        template: template.to_string(),
+        created_at,
+        updated_at: now,
+    })
+}
+
+/// Sorted by name, ignoring ASCII case.
+pub fn list_snippets(connection: &Connection) -> Result<Vec<Snippet>, String> {
+    let mut statement = connection
+        .prepare("SELECT name, template, created_at, updated_at FROM snippets ORDER BY name")
+        .map_err(|e| format!("Failed to query snippets: {e}"))?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok(Snippet {
+                name: row.get(0)?,
+                template: row.get(1)?,
+                created_at: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query snippets: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read snippets: {e}"))
+}
+
+/// Whether a snippet by that name existed.
+pub fn delete_snippet(connection: &Connection, name: &str) -> Result<bool, String> {
+    connection
+        .execute("DELETE FROM snippets WHERE name = ?1", params![name.trim()])
+        .map(|deleted| deleted > 0)
+        .map_err(|e| format!("Failed to delete snippet: {e}"))
+}
+
+/// Fold the WAL back into the main database file.
+pub fn checkpoint(connection: &Connection) -> Result<(), String> {
+    connection
+        .execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+        .map_err(|e| format!("Failed to checkpoint stats database: {e}"))
+}
+
+/// Write a consistent copy of the database at `path` to `dest`, including
+/// rows still in its WAL. Works while other connections have it open.
+pub fn snapshot(path: &Path, dest: &Path) -> Result<(), String> {
+    let connection = Connection::open(path)
+        .map_err(|e| format!("Failed to open {} for backup: {e}", path.display()))?;
+    connection
+        .execute("VACUUM INTO ?1", params![dest.to_string_lossy()])
+        .map(|_| ())
+        .map_err(|e| format!("Failed to snapshot {}: {e}", path.display()))
+}
+
+fn on_disk_size(path: &Path) -> u64 {
+    let wal = path.with_extension(match path.extension() {
+        Some(extension) => format!("{}-wal", extension.to_string_lossy()),
+        None => "wal".to_string(),
+    });
+    [path, wal.as_path()]
+        .iter()
+        .filter_map(|file| std::fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Checkpoint the WAL and `VACUUM` the database at `path`. Returns the bytes
+/// reclaimed on disk, counting the WAL file.
+pub fn compact(connection: &Connection, path: &Path) -> Result<u64, String> {
+    let before = on_disk_size(path);
+    connection
+        .execute_batch("PRAGMA wal_checkpoint(TRUNCATE); VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")
+        .map_err(|e| match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => {
+                "Stats database is busy; try again once nothing else is using it".to_string()
+            }
+            _ => format!("Failed to compact stats database: {e}"),
+        })?;
+    Ok(before.saturating_sub(on_disk_size(path)))
+}
+
+/// Run SQLite's quick consistency check on the database file at `path`.
+pub fn quick_check(path: &Path) -> Result<(), String> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let result: String = connection
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to check {}: {e}", path.display()))?;
+    if result == "ok" {
+        Ok(())
+    } else {
+        Err(format!("{} is damaged: {result}", path.display()))
+    }
+}
+
+/// Cheap round-trip used by diagnostics to prove the database answers.
+pub fn ping(connection: &Connection) -> Result<(), String> {
+    connection
+        .query_row("SELECT 1", [], |row| row.get::<_, i64>(0))
+        .map(|_| ())
+        .map_err(|e| format!("Stats database did not respond: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_db() -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+        migrate(&connection).unwrap();
+        connection
+    }
+
+    #[test]
+    fn daily_count_outlives_history_pruning() {
+        let connection = memory_db();
+        let day = 1_710_460_800;
+        for i in 0..(CONVERSION_HISTORY_LIMIT as i64 + 20) {
+            add_conversion(&connection, "in 1 hour", i, "F", day + i).unwrap();
+            count_conversion_on_day(&connection, day).unwrap();
+        }
+        assert_eq!(
+            count_conversions_since(&connection, day).unwrap(),
+            CONVERSION_HISTORY_LIMIT
+        );
+        assert_eq!(
+            conversions_on_day(&connection, day).unwrap(),
+            CONVERSION_HISTORY_LIMIT + 20
+        );
+        assert_eq!(conversions_on_day(&connection, day + 86_400).unwrap(), 0);
+
+        count_conversion_on_day(&connection, day + 30 * 86_400).unwrap();
+        assert_eq!(conversions_on_day(&connection, day).unwrap(), 0);
+        clear_conversions(&connection).unwrap();
+        assert_eq!(
+            conversions_on_day(&connection, day + 30 * 86_400).unwrap(),
+            0
+        );
+    }
+}
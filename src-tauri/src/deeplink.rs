@@ -0,0 +1,166 @@
+//! `hammeroverlay://` links.
+//!
+//! `hammeroverlay://parse?text=next%20friday` asks the app to parse `text`;
+//! `hammeroverlay://show?unix=1710511200&fmt=F` is a permalink that opens
+//! the app on a fixed timestamp. The deep-link plugin registers the scheme
+//! and delivers links; on Windows and Linux a link opened while the app runs
+//! starts a second process, which passes it on (see `instance`).
+
+use crate::discord;
+use serde::Serialize;
+use url::Url;
+
+pub const SCHEME: &str = "hammeroverlay";
+
+/// Longest `text` a link may carry; matches what fits in the input box.
+const MAX_TEXT_LEN: usize = 500;
+
+/// The first argument that looks like one of our links.
+pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<String> {
+    let prefix = format!("{SCHEME}://");
+    args.into_iter().find(|arg| {
+        arg.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(&prefix))
+    })
+}
+
+/// What a link asks the app to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+    Parse(String),
+    Show(Permalink),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Permalink {
+    pub unix: i64,
+    pub format: String,
+}
+
+/// Build the `hammeroverlay://show` link for `unix` in `format`.
+pub fn permalink(unix: i64, format: &str) -> Result<String, String> {
+    discord::validate_format(format)?;
+    // The reader guesses seconds vs milliseconds by size, so only accept
+    // values it reads back unchanged.
+    if discord::parse_epoch(&unix.to_string()) != Ok(unix) {
+        return Err(format!("Timestamp {unix} cannot be carried in a permalink"));
+    }
+    Ok(format!("{SCHEME}://show?unix={unix}&fmt={format}"))
+}
+
+/// Decode a `parse` or `show` link.
+pub fn parse_link(link: &str) -> Result<Link, String> {
+    let url = Url::parse(link).map_err(|e| format!("Invalid link: {e}"))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("Unsupported link scheme '{}'", url.scheme()));
+    }
+    // `hammeroverlay://parse` puts the action in the host; accept a path too.
+    let action = url
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| url.path().trim_matches('/'))
+        .to_ascii_lowercase();
+    match action.as_str() {
+        "parse" => parse_text(&url).map(Link::Parse),
+        "show" => parse_permalink(&url).map(Link::Show),
+        _ => Err(format!("Unsupported link action '{action}'")),
+    }
+}
+
+fn query_param(url: &Url, name: &str) -> Result<String, String> {
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.trim().to_string())
+        .ok_or_else(|| format!("Link is missing the '{name}' parameter"))
+}
+
+fn parse_permalink(url: &Url) -> Result<Permalink, String> {
+    let unix = discord::parse_epoch(&query_param(url, "unix")?)?;
+    let format = query_param(url, "fmt")?;
+    discord::validate_format(&format)?;
+    Ok(Permalink { unix, format })
+}
+
+/// Decode the text to parse from a `hammeroverlay://parse?text=...` link.
+fn parse_text(url: &Url) -> Result<String, String> {
+    let text = query_param(url, "text")?;
+    if text.is_empty() {
+        return Err("Link 'text' parameter is empty".to_string());
+    }
+    if text.chars().count() > MAX_TEXT_LEN {
+        return Err(format!(
+            "Link 'text' parameter is longer than {MAX_TEXT_LEN} characters"
+        ));
+    }
+    if text.chars().any(char::is_control) {
+        return Err("Link 'text' parameter contains control characters".to_string());
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_links_decode_their_text() {
+        assert_eq!(
+            parse_link("hammeroverlay://parse?text=next%20friday%203pm"),
+            Ok(Link::Parse("next friday 3pm".to_string()))
+        );
+        assert_eq!(
+            parse_link("hammeroverlay://parse?text=tomorrow+at+5%26ish"),
+            Ok(Link::Parse("tomorrow at 5&ish".to_string()))
+        );
+        assert_eq!(
+            parse_link("HammerOverlay://PARSE?text=%20noon%20"),
+            Ok(Link::Parse("noon".to_string()))
+        );
+        assert_eq!(
+            parse_link("hammeroverlay:///parse?text=noon"),
+            Ok(Link::Parse("noon".to_string()))
+        );
+    }
+
+    #[test]
+    fn other_schemes_and_actions_are_rejected() {
+        assert!(parse_link("https://parse?text=noon").is_err());
+        assert!(parse_link("hammeroverlay://open?text=noon").is_err());
+        assert!(parse_link("not a link").is_err());
+    }
+
+    #[test]
+    fn parse_links_need_usable_text() {
+        assert!(parse_link("hammeroverlay://parse").is_err());
+        assert!(parse_link("hammeroverlay://parse?txt=noon").is_err());
+        assert!(parse_link("hammeroverlay://parse?text=").is_err());
+        assert!(parse_link("hammeroverlay://parse?text=%20%20").is_err());
+        assert!(parse_link("hammeroverlay://parse?text=noon%0Atomorrow").is_err());
+
+        let longest = "a".repeat(MAX_TEXT_LEN);
+        assert_eq!(
+            parse_link(&format!("hammeroverlay://parse?text={longest}")),
+            Ok(Link::Parse(longest.clone()))
+        );
+        assert!(parse_link(&format!("hammeroverlay://parse?text={longest}a")).is_err());
+    }
+
+    #[test]
+    fn permalinks_round_trip() {
+        for (unix, format) in [(1_710_511_200, "F"), (0, "t"), (4_102_444_800, "R")] {
+            let link = permalink(unix, format).unwrap();
+            assert_eq!(
+                parse_link(&link),
+                Ok(Link::Show(Permalink {
+                    unix,
+                    format: format.to_string(),
+                }))
+            );
+        }
+        assert!(permalink(1_710_511_200, "X").is_err());
+        // Would read back as milliseconds.
+        assert!(permalink(1_710_511_200_000, "F").is_err());
+        assert!(parse_link("hammeroverlay://show?unix=1710511200").is_err());
+        assert!(parse_link("hammeroverlay://show?unix=soon&fmt=F").is_err());
+    }
+}
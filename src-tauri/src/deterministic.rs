@@ -0,0 +1,1834 @@
+//! Deterministic grammar for the Rust-side `parse_time` path.
+//!
+//! Everything here is pure: callers pass the reference instant explicitly so
+//! results are reproducible. Input the grammar does not understand is an
+//! error, which lets callers defer to the LLM path instead of guessing.
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, LocalResult, Months, NaiveDate, NaiveDateTime,
+    NaiveTime, Offset, TimeZone, Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+use crate::locales::{self, ParseLocale};
+
+const AVERAGE_MONTH_SECONDS: f64 = 2_629_746.0;
+const AVERAGE_YEAR_SECONDS: f64 = 31_556_952.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RelativeUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl RelativeUnit {
+    fn from_word(word: &str) -> Option<Self> {
+        match word {
+            "s" | "sec" | "secs" | "second" | "seconds" => Some(Self::Second),
+            "m" | "min" | "mins" | "minute" | "minutes" => Some(Self::Minute),
+            "h" | "hr" | "hrs" | "hour" | "hours" => Some(Self::Hour),
+            "d" | "day" | "days" => Some(Self::Day),
+            "w" | "wk" | "wks" | "week" | "weeks" => Some(Self::Week),
+            "mo" | "mos" | "month" | "months" => Some(Self::Month),
+            "y" | "yr" | "yrs" | "year" | "years" => Some(Self::Year),
+            _ => None,
+        }
+    }
+
+    /// Units with a fixed length. Months and years are calendar-aware instead.
+    fn fixed_seconds(self) -> Option<f64> {
+        match self {
+            Self::Second => Some(1.0),
+            Self::Minute => Some(60.0),
+            Self::Hour => Some(3_600.0),
+            Self::Day => Some(86_400.0),
+            Self::Week => Some(604_800.0),
+            Self::Month | Self::Year => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Future,
+    Past,
+}
+
+/// Lowercase, drop trailing punctuation, and collapse whitespace.
+pub fn normalize_input(input: &str) -> String {
+    input
+        .trim()
+        .trim_end_matches(['.', '!', '?'])
+        .replace(',', " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn parse_amount(token: &str) -> Result<f64, String> {
+    if matches!(token, "a" | "an" | "one") {
+        return Ok(1.0);
+    }
+    if token.starts_with('-') || token.starts_with('+') {
+        return Err(format!(
+            "Signed amounts like '{token}' are ambiguous; use \"in ...\" or \"... ago\""
+        ));
+    }
+    let amount = token
+        .parse::<f64>()
+        .map_err(|_| format!("Expected a number but found '{token}'"))?;
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(format!("Relative amount must be positive: '{token}'"));
+    }
+    Ok(amount)
+}
+
+fn parse_relative_parts(
+    tokens: &[String],
+) -> Result<(Direction, Vec<(f64, RelativeUnit)>), String> {
+    let words = tokens.iter().map(String::as_str).collect::<Vec<_>>();
+    let (direction, body) = match words.as_slice() {
+        ["in", rest @ ..] => (Direction::Future, rest),
+        [rest @ .., "ago"] => (Direction::Past, rest),
+        [rest @ .., "from", "now"] => (Direction::Future, rest),
+        [rest @ .., "later"] => (Direction::Future, rest),
+        _ => return Err("Not a relative time phrase".to_string()),
+    };
+    if body.contains(&"ago") || body.first() == Some(&"in") {
+        return Err("Relative phrase mixes past and future directions".to_string());
+    }
+
+    let mut parts = Vec::new();
+    let mut index = 0;
+    while index < body.len() {
+        let word = body[index];
+        if word == "and" {
+            index += 1;
+            continue;
+        }
+
+        let amount = if word == "half" {
+            if matches!(body.get(index + 1), Some(&"a") | Some(&"an")) {
+                index += 1;
+            }
+            0.5
+        } else {
+            parse_amount(word)?
+        };
+
+        let unit_word = body
+            .get(index + 1)
+            .ok_or_else(|| format!("Relative phrase is missing a unit after '{word}'"))?;
+        let unit = RelativeUnit::from_word(unit_word)
+            .ok_or_else(|| format!("Unknown time unit: '{unit_word}'"))?;
+        parts.push((amount, unit));
+        index += 2;
+    }
+
+    if parts.is_empty() {
+        return Err("Relative phrase is missing an amount and unit".to_string());
+    }
+    Ok((direction, parts))
+}
+
+fn shift_months<Z: TimeZone>(
+    moment: DateTime<Z>,
+    months: u32,
+    direction: Direction,
+) -> Result<DateTime<Z>, String> {
+    let shifted = match direction {
+        Direction::Future => moment.checked_add_months(Months::new(months)),
+        Direction::Past => moment.checked_sub_months(Months::new(months)),
+    };
+    shifted.ok_or_else(|| "Relative offset is out of range".to_string())
+}
+
+fn apply_relative<Z: TimeZone>(
+    now: &DateTime<Z>,
+    direction: Direction,
+    parts: &[(f64, RelativeUnit)],
+) -> Result<DateTime<Z>, String> {
+    let mut moment = now.clone();
+    let mut seconds = 0.0_f64;
+
+    for &(amount, unit) in parts {
+        if let Some(unit_seconds) = unit.fixed_seconds() {
+            seconds += amount * unit_seconds;
+            continue;
+        }
+
+        // Whole months/years follow the calendar; any fraction uses the
+        // average length so "in 1.5 months" still lands somewhere sensible.
+        let (months_per_unit, average_seconds) = match unit {
+            RelativeUnit::Year => (12.0, AVERAGE_YEAR_SECONDS),
+            _ => (1.0, AVERAGE_MONTH_SECONDS),
+        };
+        let whole = amount.trunc();
+        let months = whole * months_per_unit;
+        if months > f64::from(u32::MAX) {
+            return Err("Relative offset is out of range".to_string());
+        }
+        moment = shift_months(moment, months as u32, direction)?;
+        seconds += (amount - whole) * average_seconds;
+    }
+
+    let millis = (seconds * 1000.0).round();
+    if !millis.is_finite() || millis.abs() > i64::MAX as f64 {
+        return Err("Relative offset is out of range".to_string());
+    }
+    let offset = Duration::try_milliseconds(millis as i64)
+        .ok_or_else(|| "Relative offset is out of range".to_string())?;
+    let shifted = match direction {
+        Direction::Future => moment.checked_add_signed(offset),
+        Direction::Past => moment.checked_sub_signed(offset),
+    };
+    shifted.ok_or_else(|| "Relative offset is out of range".to_string())
+}
+
+/// Resolve a relative phrase against a zoned reference instant.
+pub fn relative_from<Z: TimeZone>(input: &str, now: &DateTime<Z>) -> Result<DateTime<Z>, String> {
+    let tokens = tokenize(&normalize_input(input));
+    let (direction, parts) = parse_relative_parts(&tokens)?;
+    apply_relative(now, direction, &parts)
+}
+
+/// Resolve phrases like "in 90 minutes", "in 1.5 hours", or "2 days ago"
+/// against `reference_now` (Unix seconds). Calendar units are applied in UTC.
+pub fn parse_relative(input: &str, reference_now: i64) -> Result<i64, String> {
+    let now = DateTime::<Utc>::from_timestamp(reference_now, 0)
+        .ok_or_else(|| format!("Reference time is out of range: {reference_now}"))?;
+    relative_from(input, &now).map(|moment| moment.timestamp())
+}
+
+const FILLER_WORDS: &[&str] = &["at", "@", "on", "around", "about", "by"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WeekdayModifier {
+    Bare,
+    This,
+    Next,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateSpec {
+    DayOffset(i64),
+    Weekday(Weekday, WeekdayModifier),
+    Weekend(WeekdayModifier),
+    MonthDay {
+        month: u32,
+        day: u32,
+        year: Option<i32>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Meridiem {
+    Am,
+    Pm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TimeSpec {
+    hour: u32,
+    minute: u32,
+    meridiem: Option<Meridiem>,
+    /// Written so it can only be a 24-hour clock reading ("05:00", "17:30").
+    twenty_four_hour: bool,
+}
+
+/// How a time with no date and a meridiem-less hour ("at 5") resolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BareTimePolicy {
+    /// The soonest upcoming reading: today if still ahead, else tomorrow.
+    #[default]
+    Next,
+    /// Stay on today's date, preferring a reading that is still ahead.
+    Today,
+    /// Offer every reading and let the user choose.
+    Ask,
+}
+
+impl BareTimePolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "next" => Some(Self::Next),
+            "today" => Some(Self::Today),
+            "ask" => Some(Self::Ask),
+            _ => None,
+        }
+    }
+}
+
+/// User preferences the grammar consults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseRules {
+    /// First day of a week; "next friday" means the Friday of the week after
+    /// the one containing today.
+    pub starts_on: Weekday,
+    /// Whether "this friday" said on a Friday means today or a week out.
+    pub this_includes_today: bool,
+    pub bare_time: BareTimePolicy,
+    /// Applied to a date with no time, like "march 15"; "tonight" still
+    /// means 20:00. Type "00:00" for midnight.
+    pub date_only_time: NaiveTime,
+}
+
+impl Default for ParseRules {
+    fn default() -> Self {
+        Self {
+            starts_on: Weekday::Mon,
+            this_includes_today: true,
+            bare_time: BareTimePolicy::default(),
+            date_only_time: NaiveTime::from_hms_opt(9, 0, 0).expect("09:00 is a valid time"),
+        }
+    }
+}
+
+/// The result of a successful deterministic parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub epoch: i64,
+    pub had_explicit_time: bool,
+    /// "now" or an offset from it, such as "in 2 hours".
+    pub relative: bool,
+}
+
+pub fn weekday_from_word(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thur" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn month_from_word(word: &str) -> Option<u32> {
+    match word {
+        "january" | "jan" => Some(1),
+        "february" | "feb" => Some(2),
+        "march" | "mar" => Some(3),
+        "april" | "apr" => Some(4),
+        "may" => Some(5),
+        "june" | "jun" => Some(6),
+        "july" | "jul" => Some(7),
+        "august" | "aug" => Some(8),
+        "september" | "sep" | "sept" => Some(9),
+        "october" | "oct" => Some(10),
+        "november" | "nov" => Some(11),
+        "december" | "dec" => Some(12),
+        _ => None,
+    }
+}
+
+fn meridiem_from_word(word: &str) -> Option<Meridiem> {
+    match word {
+        "am" | "a.m" | "a.m." => Some(Meridiem::Am),
+        "pm" | "p.m" | "p.m." => Some(Meridiem::Pm),
+        _ => None,
+    }
+}
+
+/// Split glued tokens such as `90min`, `1.5h`, `3pm`, `3:30pm`, or `15th`.
+fn tokenize(normalized: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in normalized.split_whitespace() {
+        let split_at = word
+            .char_indices()
+            .find(|(_, c)| !(c.is_ascii_digit() || *c == '.' || *c == ':'))
+            .map(|(index, _)| index);
+        match split_at {
+            Some(index) if index > 0 && word[..index].chars().any(|c| c.is_ascii_digit()) => {
+                tokens.push(word[..index].to_string());
+                tokens.push(word[index..].to_string());
+            }
+            _ => tokens.push(word.to_string()),
+        }
+    }
+    tokens
+}
+
+fn parse_day_number(token: &str) -> Option<u32> {
+    if token.is_empty() || token.len() > 2 || !token.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    token
+        .parse::<u32>()
+        .ok()
+        .filter(|day| (1..=31).contains(day))
+}
+
+fn parse_year_number(token: &str) -> Option<i32> {
+    if token.len() != 4 || !token.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    token
+        .parse::<i32>()
+        .ok()
+        .filter(|year| (1970..=9999).contains(year))
+}
+
+fn is_ordinal_suffix(token: &str) -> bool {
+    matches!(token, "st" | "nd" | "rd" | "th")
+}
+
+/// Fixed-date holidays the grammar knows by name, as (phrase, month, day).
+/// This is a convenience for common phrases, not a holiday calendar: movable
+/// holidays (Easter, Thanksgiving) are left out, and regional entries carry
+/// that region's date. Multi-word phrases must precede their prefixes.
+/// Locale tables map their own names onto these phrases.
+const HOLIDAYS: &[(&str, u32, u32)] = &[
+    ("new year's eve", 12, 31),
+    ("new years eve", 12, 31),
+    ("nye", 12, 31),
+    ("new year's day", 1, 1),
+    ("new years day", 1, 1),
+    ("new year's", 1, 1),
+    ("new years", 1, 1),
+    ("new year", 1, 1),
+    ("valentine's day", 2, 14),
+    ("valentines day", 2, 14),
+    ("valentines", 2, 14),
+    ("halloween", 10, 31),
+    ("christmas eve", 12, 24),
+    ("christmas day", 12, 25),
+    ("christmas", 12, 25),
+    ("xmas", 12, 25),
+    // US
+    ("independence day", 7, 4),
+    // UK, Canada, Australia
+    ("boxing day", 12, 26),
+    // Ireland
+    ("st patrick's day", 3, 17),
+    ("st patricks day", 3, 17),
+];
+
+/// "christmas", "new year's eve 2025": the next occurrence unless a year
+/// follows.
+fn match_holiday(tokens: &[&str]) -> Option<(DateSpec, usize)> {
+    let (month, day, mut used) = HOLIDAYS.iter().find_map(|(phrase, month, day)| {
+        let words = phrase.split_whitespace().collect::<Vec<_>>();
+        tokens
+            .starts_with(&words)
+            .then_some((*month, *day, words.len()))
+    })?;
+    let year = tokens.get(used).and_then(|token| parse_year_number(token));
+    if year.is_some() {
+        used += 1;
+    }
+    Some((DateSpec::MonthDay { month, day, year }, used))
+}
+
+/// Match a date phrase at the start of `tokens`, returning it and the number
+/// of tokens consumed.
+fn match_date(tokens: &[&str]) -> Option<(DateSpec, usize)> {
+    let first = *tokens.first()?;
+    match first {
+        "today" | "tonight" => return Some((DateSpec::DayOffset(0), 1)),
+        "tomorrow" | "tmrw" | "tmr" => return Some((DateSpec::DayOffset(1), 1)),
+        "yesterday" => return Some((DateSpec::DayOffset(-1), 1)),
+        _ => {}
+    }
+    if let Some(holiday) = match_holiday(tokens) {
+        return Some(holiday);
+    }
+
+    let modifier = match first {
+        "this" | "coming" => Some(WeekdayModifier::This),
+        "next" => Some(WeekdayModifier::Next),
+        _ => None,
+    };
+    if first == "weekend" {
+        return Some((DateSpec::Weekend(WeekdayModifier::Bare), 1));
+    }
+    if let Some(modifier) = modifier {
+        if tokens.get(1) == Some(&"weekend") {
+            return Some((DateSpec::Weekend(modifier), 2));
+        }
+        let weekday = weekday_from_word(tokens.get(1)?)?;
+        return Some((DateSpec::Weekday(weekday, modifier), 2));
+    }
+    if let Some(weekday) = weekday_from_word(first) {
+        return Some((DateSpec::Weekday(weekday, WeekdayModifier::Bare), 1));
+    }
+
+    // "march 15", "mar 15th", "march 15 2025"
+    if let Some(month) = month_from_word(first) {
+        let day = parse_day_number(tokens.get(1)?)?;
+        let mut used = 2;
+        if tokens
+            .get(used)
+            .is_some_and(|token| is_ordinal_suffix(token))
+        {
+            used += 1;
+        }
+        let year = tokens.get(used).and_then(|token| parse_year_number(token));
+        if year.is_some() {
+            used += 1;
+        }
+        return Some((DateSpec::MonthDay { month, day, year }, used));
+    }
+
+    // "15 march", "15th of march 2025"
+    let day = parse_day_number(first)?;
+    let mut used = 1;
+    if tokens
+        .get(used)
+        .is_some_and(|token| is_ordinal_suffix(token))
+    {
+        used += 1;
+    }
+    if tokens.get(used) == Some(&"of") {
+        used += 1;
+    }
+    let month = month_from_word(tokens.get(used)?)?;
+    used += 1;
+    let year = tokens.get(used).and_then(|token| parse_year_number(token));
+    if year.is_some() {
+        used += 1;
+    }
+    Some((DateSpec::MonthDay { month, day, year }, used))
+}
+
+/// "noon" and "midnight" as 24-hour readings, so no meridiem default moves
+/// them.
+fn keyword_time(token: &str) -> Option<TimeSpec> {
+    let hour = match token {
+        "noon" | "midday" => 12,
+        "midnight" => 0,
+        _ => return None,
+    };
+    Some(TimeSpec {
+        hour,
+        minute: 0,
+        meridiem: None,
+        twenty_four_hour: true,
+    })
+}
+
+/// Match a clock time at the start of `tokens`. A bare number only counts as
+/// an hour when it follows "at" or is followed by "o'clock".
+fn match_time(tokens: &[&str], after_at: bool) -> Result<Option<(TimeSpec, usize)>, String> {
+    let Some(first) = tokens.first() else {
+        return Ok(None);
+    };
+    if let Some(spec) = keyword_time(first) {
+        return Ok(Some((spec, 1)));
+    }
+    if !first.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+
+    let (hour_text, minute_text) = match first.split_once(':') {
+        Some((hour, minute)) => (hour, Some(minute)),
+        None => (*first, None),
+    };
+    if hour_text.is_empty() || hour_text.len() > 2 || !hour_text.chars().all(|c| c.is_ascii_digit())
+    {
+        return Ok(None);
+    }
+    let hour = hour_text
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid hour: '{first}'"))?;
+    let minute = match minute_text {
+        Some(minute) if minute.len() == 2 && minute.chars().all(|c| c.is_ascii_digit()) => minute
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid minute: '{first}'"))?,
+        Some(_) => return Err(format!("Invalid time: '{first}'")),
+        None => 0,
+    };
+
+    let mut used = 1;
+    let meridiem = tokens.get(1).and_then(|token| meridiem_from_word(token));
+    let has_suffix =
+        meridiem.is_some() || matches!(tokens.get(1), Some(&"o'clock") | Some(&"oclock"));
+    if has_suffix {
+        used += 1;
+    } else if minute_text.is_none() && !after_at {
+        return Ok(None);
+    }
+
+    if minute > 59 {
+        return Err(format!("Invalid minute in '{first}'"));
+    }
+    match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => {
+            return Err(format!("Hour must be 1-12 with am/pm: '{first}'"));
+        }
+        None if hour > 23 => return Err(format!("Invalid hour: '{first}'")),
+        _ => {}
+    }
+
+    let twenty_four_hour =
+        meridiem.is_none() && (hour == 0 || hour > 12 || hour_text.starts_with('0'));
+    Ok(Some((
+        TimeSpec {
+            hour,
+            minute,
+            meridiem,
+            twenty_four_hour,
+        },
+        used,
+    )))
+}
+
+fn local_datetime(
+    tz: &Tz,
+    date: NaiveDate,
+    hour: u32,
+    minute: u32,
+) -> Result<DateTime<Tz>, String> {
+    let naive = date
+        .and_hms_opt(hour, minute, 0)
+        .ok_or_else(|| format!("Invalid time {hour:02}:{minute:02}"))?;
+    resolve_local(tz, naive)
+}
+
+fn resolve_local(tz: &Tz, naive: NaiveDateTime) -> Result<DateTime<Tz>, String> {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(moment) => Ok(moment),
+        LocalResult::Ambiguous(earliest, _) => Ok(earliest),
+        // Wall-clock time skipped by a DST jump: move past the gap.
+        LocalResult::None => tz
+            .from_local_datetime(&(naive + Duration::hours(1)))
+            .earliest()
+            .ok_or_else(|| format!("{naive} does not exist in {}", tz.name())),
+    }
+}
+
+/// ISO-8601 datetimes that carry their own offset ("Z", "+05:30").
+const ISO_OFFSET_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M%:z", "%Y-%m-%d %H:%M%:z"];
+/// Offset-less ISO-8601 datetimes, read in the caller's timezone.
+const ISO_LOCAL_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Recognize ISO-8601 / RFC 3339 strings as copied from logs. An embedded
+/// offset wins; a bare local datetime or date uses `tz`. `None` means the
+/// input is not ISO-shaped at all.
+fn parse_iso(
+    input: &str,
+    tz: &Tz,
+    date_only_time: NaiveTime,
+) -> Option<Result<Resolution, String>> {
+    let trimmed = input.trim();
+    if !trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let fixed = |moment: DateTime<FixedOffset>| {
+        Some(Ok(Resolution {
+            epoch: moment.timestamp(),
+            had_explicit_time: true,
+            relative: false,
+        }))
+    };
+    if let Ok(moment) = DateTime::parse_from_rfc3339(trimmed) {
+        return fixed(moment);
+    }
+    let zulu = trimmed
+        .strip_suffix(['Z', 'z'])
+        .map(|rest| format!("{rest}+00:00"));
+    let offset_input = zulu.as_deref().unwrap_or(trimmed);
+    for format in ISO_OFFSET_FORMATS {
+        if let Ok(moment) = DateTime::parse_from_str(offset_input, format) {
+            return fixed(moment);
+        }
+    }
+    for format in ISO_LOCAL_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(trimmed, format) {
+            return Some(resolve_local(tz, naive).map(|moment| Resolution {
+                epoch: moment.timestamp(),
+                had_explicit_time: true,
+                relative: false,
+            }));
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(trimmed, "%Y-%m-%d") {
+        let (hour, minute) = (date_only_time.hour(), date_only_time.minute());
+        return Some(
+            local_datetime(tz, date, hour, minute).map(|moment| Resolution {
+                epoch: moment.timestamp(),
+                had_explicit_time: false,
+                relative: false,
+            }),
+        );
+    }
+    None
+}
+
+fn add_days(date: NaiveDate, days: i64) -> Result<NaiveDate, String> {
+    date.checked_add_signed(Duration::days(days))
+        .ok_or_else(|| "Date is out of range".to_string())
+}
+
+fn days_until(today: NaiveDate, weekday: Weekday) -> i64 {
+    (i64::from(weekday.num_days_from_monday()) - i64::from(today.weekday().num_days_from_monday()))
+        .rem_euclid(7)
+}
+
+/// Days from the start of the week to `weekday`.
+fn week_offset(weekday: Weekday, starts_on: Weekday) -> i64 {
+    (i64::from(weekday.num_days_from_monday()) - i64::from(starts_on.num_days_from_monday()))
+        .rem_euclid(7)
+}
+
+/// "next <weekday>" is always in the following week. "this <weekday>" is the
+/// day in the current week, or a week later once that day has passed (or is
+/// today and `this_includes_today` is off). A bare weekday is the next
+/// occurrence, today included.
+fn weekday_days_ahead(
+    today: NaiveDate,
+    weekday: Weekday,
+    modifier: WeekdayModifier,
+    rules: &ParseRules,
+) -> i64 {
+    let into_week = week_offset(today.weekday(), rules.starts_on);
+    let this_week = week_offset(weekday, rules.starts_on) - into_week;
+    match modifier {
+        WeekdayModifier::Bare => days_until(today, weekday),
+        WeekdayModifier::Next => this_week + 7,
+        WeekdayModifier::This if this_week > 0 => this_week,
+        WeekdayModifier::This if this_week == 0 && rules.this_includes_today => 0,
+        WeekdayModifier::This => this_week + 7,
+    }
+}
+
+/// Calendar dates a date phrase can refer to, default first.
+fn resolve_dates(
+    spec: DateSpec,
+    today: NaiveDate,
+    rules: &ParseRules,
+) -> Result<Vec<NaiveDate>, String> {
+    match spec {
+        DateSpec::DayOffset(days) => Ok(vec![add_days(today, days)?]),
+        DateSpec::Weekday(weekday, modifier) => Ok(vec![add_days(
+            today,
+            weekday_days_ahead(today, weekday, modifier, rules),
+        )?]),
+        DateSpec::Weekend(modifier) => {
+            // On Sunday the current weekend is today; otherwise it starts on
+            // the coming (or current) Saturday.
+            let (coming, following) = if today.weekday() == Weekday::Sun {
+                (today, add_days(today, 6)?)
+            } else {
+                let saturday = add_days(today, days_until(today, Weekday::Sat))?;
+                (saturday, add_days(saturday, 7)?)
+            };
+            let in_weekend = matches!(today.weekday(), Weekday::Sat | Weekday::Sun);
+            match modifier {
+                WeekdayModifier::Next if in_weekend => Ok(vec![following]),
+                WeekdayModifier::Next => Ok(vec![coming, following]),
+                _ => Ok(vec![coming]),
+            }
+        }
+        DateSpec::MonthDay { month, day, year } => {
+            let invalid = || format!("Invalid date: month {month}, day {day}");
+            if let Some(year) = year {
+                return Ok(vec![
+                    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(invalid)?
+                ]);
+            }
+            // Without a year, pick the next occurrence (today counts).
+            let date = match NaiveDate::from_ymd_opt(today.year(), month, day) {
+                Some(date) if date >= today => date,
+                _ => NaiveDate::from_ymd_opt(today.year() + 1, month, day).ok_or_else(invalid)?,
+            };
+            Ok(vec![date])
+        }
+    }
+}
+
+/// Whether a meridiem-less hour could mean either half of the day.
+fn is_ambiguous_hour(time: TimeSpec, evening: bool) -> bool {
+    time.meridiem.is_none() && !time.twenty_four_hour && !evening && (1..=12).contains(&time.hour)
+}
+
+/// 24-hour readings of a time attached to an explicit date, default first.
+/// Without am/pm, 1-6 default to afternoon, 7-11 to morning, and "tonight"
+/// forces pm.
+fn dated_hours(time: TimeSpec, evening: bool) -> Vec<u32> {
+    let default = match time.meridiem {
+        Some(Meridiem::Am) => time.hour % 12,
+        Some(Meridiem::Pm) => time.hour % 12 + 12,
+        None if (1..=11).contains(&time.hour) && (evening || time.hour <= 6) => time.hour + 12,
+        None => time.hour,
+    };
+    if is_ambiguous_hour(time, evening) {
+        vec![default, (default + 12) % 24]
+    } else {
+        vec![default]
+    }
+}
+
+/// Readings of a lone time under `policy`, soonest first. `Next` and `Ask`
+/// roll each reading to tomorrow once it has passed; `Ask` keeps both halves
+/// of an ambiguous hour while the others settle on one.
+fn upcoming_times(
+    time: TimeSpec,
+    evening: bool,
+    now: &DateTime<Tz>,
+    policy: BareTimePolicy,
+) -> Result<Vec<DateTime<Tz>>, String> {
+    let tz = now.timezone();
+    let today = now.date_naive();
+    let hours = dated_hours(time, evening);
+
+    if policy == BareTimePolicy::Today {
+        let mut readings = hours
+            .iter()
+            .map(|&hour| local_datetime(&tz, today, hour, time.minute))
+            .collect::<Result<Vec<_>, _>>()?;
+        let default = readings[0];
+        readings.retain(|moment| moment > now);
+        readings.sort();
+        return Ok(vec![readings.first().copied().unwrap_or(default)]);
+    }
+
+    let mut moments = Vec::new();
+    for hour in hours {
+        let mut next = None;
+        for day_offset in 0..=1 {
+            let candidate = local_datetime(&tz, add_days(today, day_offset)?, hour, time.minute)?;
+            if candidate > *now {
+                next = Some(candidate);
+                break;
+            }
+        }
+        moments.push(next.ok_or_else(|| "Could not find an upcoming time".to_string())?);
+    }
+    moments.sort();
+    if policy == BareTimePolicy::Next {
+        moments.truncate(1);
+    }
+    Ok(moments)
+}
+
+/// Every plausible reading of an input, default first. More than one
+/// candidate means the grammar could not choose without asking.
+///
+/// Under the `Ask` bare-time policy, ambiguity is flagged for a
+/// meridiem-less hour from 1 to 12 ("at 5", "friday 5:30") unless "tonight"
+/// pins it to the evening or it is written 24-hour style ("05:00", "17:00").
+/// "next weekend" said on a weekday (the coming weekend or the one after) is
+/// always flagged. Every other phrase applies its default silently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interpretation {
+    pub candidates: Vec<Resolution>,
+}
+
+impl Interpretation {
+    fn single(resolution: Resolution) -> Self {
+        Self {
+            candidates: vec![resolution],
+        }
+    }
+
+    pub fn best(&self) -> Resolution {
+        self.candidates[0]
+    }
+
+    pub fn needs_clarification(&self) -> bool {
+        self.candidates.len() > 1
+    }
+}
+
+/// Parse absolute phrases such as "tomorrow 3pm", "next friday at 14:30",
+/// "march 15", "christmas 6pm", "friday noon", or just "5:30pm", ISO-8601
+/// strings like "2024-03-15T14:30:00+05:30", plus everything
+/// `parse_relative` accepts. "midnight" is the start of its day, except that
+/// "midnight tonight" ends today. `rules` decides which week "this"/"next"
+/// weekdays land in and how a meridiem-less hour resolves.
+pub fn parse(
+    input: &str,
+    now: &DateTime<Tz>,
+    rules: &ParseRules,
+) -> Result<Interpretation, String> {
+    if let Some(resolution) = parse_iso(input, &now.timezone(), rules.date_only_time) {
+        return resolution.map(Interpretation::single);
+    }
+    let normalized = normalize_input(input);
+    if normalized.is_empty() {
+        return Err("Input is empty".to_string());
+    }
+    if matches!(normalized.as_str(), "now" | "right now") {
+        return Ok(Interpretation::single(Resolution {
+            epoch: now.timestamp(),
+            had_explicit_time: true,
+            relative: true,
+        }));
+    }
+    if let Ok(moment) = relative_from(&normalized, now) {
+        return Ok(Interpretation::single(Resolution {
+            epoch: moment.timestamp(),
+            had_explicit_time: true,
+            relative: true,
+        }));
+    }
+
+    let tokens = tokenize(&normalized);
+    let tokens = tokens.iter().map(String::as_str).collect::<Vec<_>>();
+    let evening = tokens.contains(&"tonight");
+
+    let mut date = None;
+    let mut time = None;
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = tokens[index];
+        if FILLER_WORDS.contains(&token) {
+            index += 1;
+            continue;
+        }
+        if let Some((spec, used)) = match_date(&tokens[index..]) {
+            if date.replace(spec).is_some() {
+                return Err("Input contains more than one date".to_string());
+            }
+            index += used;
+            continue;
+        }
+        let after_at = index > 0 && matches!(tokens[index - 1], "at" | "@");
+        if let Some((spec, used)) = match_time(&tokens[index..], after_at)? {
+            if time.replace(spec).is_some() {
+                return Err("Input contains more than one time".to_string());
+            }
+            index += used;
+            continue;
+        }
+        return Err(format!("Unrecognized word: '{token}'"));
+    }
+
+    let tz = now.timezone();
+    let today = now.date_naive();
+    let mut candidates = Vec::new();
+    let mut push = |moment: DateTime<Tz>, had_explicit_time: bool| {
+        let resolution = Resolution {
+            epoch: moment.timestamp(),
+            had_explicit_time,
+            relative: false,
+        };
+        if !candidates.contains(&resolution) {
+            candidates.push(resolution);
+        }
+    };
+
+    match (date, time) {
+        (Some(spec), Some(time)) => {
+            let mut hours = dated_hours(time, evening);
+            if rules.bare_time != BareTimePolicy::Ask {
+                hours.truncate(1);
+            }
+            for day in resolve_dates(spec, today, rules)? {
+                for &hour in &hours {
+                    // Midnight starts a day, so "midnight tonight" is the one
+                    // that ends today.
+                    let day = if evening && hour == 0 {
+                        add_days(day, 1)?
+                    } else {
+                        day
+                    };
+                    push(local_datetime(&tz, day, hour, time.minute)?, true);
+                }
+            }
+        }
+        (Some(spec), None) => {
+            let (hour, minute) = if evening {
+                (20, 0)
+            } else {
+                (rules.date_only_time.hour(), rules.date_only_time.minute())
+            };
+            for day in resolve_dates(spec, today, rules)? {
+                push(local_datetime(&tz, day, hour, minute)?, false);
+            }
+        }
+        (None, Some(time)) => {
+            for moment in upcoming_times(time, evening, now, rules.bare_time)? {
+                push(moment, true);
+            }
+        }
+        (None, None) => return Err("No date or time found in input".to_string()),
+    }
+
+    Ok(Interpretation { candidates })
+}
+
+/// Round `epoch` to the nearest multiple of `minutes` on `tz`'s wall clock,
+/// so 30-minute steps land on :00 and :30 even in a +05:45 zone. Halfway
+/// rounds up; `minutes == 0` leaves the epoch alone.
+pub fn round_epoch(epoch: i64, tz: &Tz, minutes: u32) -> i64 {
+    if minutes == 0 {
+        return epoch;
+    }
+    let Some(moment) = DateTime::from_timestamp(epoch, 0) else {
+        return epoch;
+    };
+    let offset = i64::from(moment.with_timezone(tz).offset().fix().local_minus_utc());
+    let step = i64::from(minutes) * 60;
+    let local = epoch + offset;
+    (local + step / 2).div_euclid(step) * step - offset
+}
+
+/// Most suggestions `suggest_completions` returns.
+pub const SUGGESTION_LIMIT: usize = 8;
+
+/// Whole phrases the grammar accepts, most useful first.
+const SUGGESTION_PHRASES: &[&str] = &[
+    "tomorrow",
+    "today",
+    "tonight",
+    "now",
+    "in 30 minutes",
+    "in 1 hour",
+    "in 2 hours",
+    "in 2 days",
+    "in a week",
+    "this weekend",
+    "next weekend",
+    "yesterday",
+];
+
+/// Words offered to finish the last word of longer input, by what may
+/// follow the word before it.
+const UNIT_WORDS: &[&str] = &["minutes", "hours", "days", "weeks", "months", "years"];
+/// `UNIT_WORDS` after a count of one.
+const SINGULAR_UNIT_WORDS: &[&str] = &["minute", "hour", "day", "week", "month", "year"];
+const DATE_WORDS: &[&str] = &[
+    "tomorrow",
+    "today",
+    "tonight",
+    "yesterday",
+    "weekend",
+    "noon",
+    "midnight",
+    "ago",
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const WEEKDAY_NAMES: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// Completions for what the user has typed so far, from the grammar's own
+/// vocabulary. Whole phrases that extend `partial` rank first, then inputs
+/// with just the last word finished ("in 2 h" -> "in 2 hours"). Purely
+/// lexical; nothing is parsed.
+pub fn suggest_completions(partial: &str) -> Vec<String> {
+    let typed = normalize_input(partial);
+    if typed.is_empty() {
+        return Vec::new();
+    }
+    let weekday_phrases = ["", "this ", "next "].iter().flat_map(|modifier| {
+        WEEKDAY_NAMES
+            .iter()
+            .map(move |weekday| format!("{modifier}{weekday}"))
+    });
+    let phrases = SUGGESTION_PHRASES
+        .iter()
+        .map(|phrase| phrase.to_string())
+        .chain(weekday_phrases)
+        .chain(HOLIDAYS.iter().map(|(phrase, _, _)| phrase.to_string()));
+
+    let (head, last) = match typed.rsplit_once(' ') {
+        Some((head, last)) => (Some(head), last),
+        None => (None, typed.as_str()),
+    };
+    let previous = head.and_then(|head| head.rsplit(' ').next());
+    let vocabulary: Vec<&str> = match previous {
+        Some("this" | "next" | "coming") => {
+            WEEKDAY_NAMES.iter().chain(&["weekend"]).copied().collect()
+        }
+        Some("a" | "an" | "one" | "1") => SINGULAR_UNIT_WORDS.to_vec(),
+        Some(word) if word.parse::<f64>().is_ok() => UNIT_WORDS.to_vec(),
+        _ => DATE_WORDS
+            .iter()
+            .chain(&WEEKDAY_NAMES)
+            .chain(UNIT_WORDS)
+            .copied()
+            .collect(),
+    };
+    let words = vocabulary
+        .into_iter()
+        .filter(|word| word.starts_with(last) && *word != last)
+        .map(|word| match head {
+            Some(head) => format!("{head} {word}"),
+            None => word.to_string(),
+        });
+
+    let mut suggestions = Vec::new();
+    for suggestion in phrases
+        .filter(|phrase| phrase.starts_with(&typed) && *phrase != typed)
+        .chain(words)
+    {
+        if !suggestions.contains(&suggestion) {
+            suggestions.push(suggestion);
+        }
+        if suggestions.len() == SUGGESTION_LIMIT {
+            break;
+        }
+    }
+    suggestions
+}
+
+/// Inputs longer than this are not searched word span by word span.
+const LENIENT_MAX_WORDS: usize = 16;
+
+/// The longest run of words that `parse_with_locale` accepts, so "lunch with
+/// sam tomorrow 3pm" still yields "tomorrow 3pm". A last resort when the
+/// whole input fails and nothing better is available.
+pub fn parse_longest_span(
+    input: &str,
+    now: &DateTime<Tz>,
+    locale: &ParseLocale,
+    rules: &ParseRules,
+) -> Result<Interpretation, String> {
+    let normalized = normalize_input(input);
+    let words = normalized.split_whitespace().collect::<Vec<_>>();
+    if words.len() > LENIENT_MAX_WORDS {
+        return Err(format!(
+            "Input is longer than {LENIENT_MAX_WORDS} words for a partial parse"
+        ));
+    }
+    for length in (1..=words.len()).rev() {
+        for window in words.windows(length) {
+            if let Ok(interpretation) = parse_with_locale(&window.join(" "), now, locale, rules) {
+                return Ok(interpretation);
+            }
+        }
+    }
+    Err("No date or time found in input".to_string())
+}
+
+/// Weekdays a recurrence phrase such as "weekdays" or "mondays" repeats on.
+fn recurrence_days(word: &str) -> Option<Vec<Weekday>> {
+    use Weekday::*;
+    match word {
+        "day" | "days" | "daily" | "everyday" => Some(vec![Mon, Tue, Wed, Thu, Fri, Sat, Sun]),
+        "weekday" | "weekdays" => Some(vec![Mon, Tue, Wed, Thu, Fri]),
+        "weekend" | "weekends" => Some(vec![Sat, Sun]),
+        _ => weekday_from_word(word)
+            .or_else(|| word.strip_suffix('s').and_then(weekday_from_word))
+            .map(|weekday| vec![weekday]),
+    }
+}
+
+/// The next moment after `now` matched by a simple recurrence such as "every
+/// monday", "weekdays at 9" or "daily 18:00". Only the first hit is found;
+/// without a time the match is the start of the next matching day after
+/// today, since today's start has already passed.
+pub fn next_occurrence(input: &str, now: &DateTime<Tz>) -> Result<i64, String> {
+    let tokens = tokenize(&normalize_input(input));
+    let mut tokens = tokens.iter().map(String::as_str).collect::<Vec<_>>();
+    if matches!(tokens.first(), Some(&("every" | "each"))) {
+        tokens.remove(0);
+    }
+    let Some(days) = tokens.first().and_then(|word| recurrence_days(word)) else {
+        return Err("Expected a recurrence such as 'every monday' or 'weekdays'".to_string());
+    };
+
+    let mut time = None;
+    let mut index = 1;
+    while index < tokens.len() {
+        let token = tokens[index];
+        if FILLER_WORDS.contains(&token) {
+            index += 1;
+            continue;
+        }
+        let after_at = matches!(tokens[index - 1], "at" | "@");
+        match match_time(&tokens[index..], after_at)? {
+            Some((spec, used)) if time.is_none() => {
+                time = Some(spec);
+                index += used;
+            }
+            Some(_) => return Err("Input contains more than one time".to_string()),
+            None => return Err(format!("Unrecognized word: '{token}'")),
+        }
+    }
+
+    let tz = now.timezone();
+    let today = now.date_naive();
+    // A week and a day covers every pattern even when today's slot has passed.
+    for offset in 0..=7 {
+        let day = add_days(today, offset)?;
+        if !days.contains(&day.weekday()) {
+            continue;
+        }
+        let moment = match time {
+            Some(time) => local_datetime(&tz, day, dated_hours(time, false)[0], time.minute)?,
+            None => local_datetime(&tz, day, 0, 0)?,
+        };
+        if moment <= *now {
+            continue;
+        }
+        return Ok(moment.timestamp());
+    }
+    Err("No upcoming occurrence found".to_string())
+}
+
+/// `parse`, trying `locale`'s keywords first and falling back to English so
+/// mixed input like "morgen 3pm" or plain English still works.
+pub fn parse_with_locale(
+    input: &str,
+    now: &DateTime<Tz>,
+    locale: &ParseLocale,
+    rules: &ParseRules,
+) -> Result<Interpretation, String> {
+    if let Some(translated) = locales::translate(&normalize_input(input), locale) {
+        if let Ok(interpretation) = parse(&translated, now, rules) {
+            return Ok(interpretation);
+        }
+    }
+    parse(input, now, rules)
+}
+
+/// What joins the two ends of a range, tried in order. A plain hyphen needs
+/// spaces around it so ISO dates stay whole.
+const RANGE_SEPARATORS: [&str; 6] = [" until ", " till ", " to ", "–", "—", " - "];
+
+/// Both ends of a range such as "3pm to 5pm".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeInterpretation {
+    pub start: Interpretation,
+    pub end: Interpretation,
+    /// The end read earlier than the start and was moved a day later.
+    pub rolled_over: bool,
+}
+
+/// Split "X to Y", "from X until Y" or "X–Y" into its two ends.
+fn split_range(input: &str) -> Option<(&str, &str)> {
+    let text = input.trim();
+    // ASCII lowercasing keeps byte offsets valid for slicing `text`.
+    let lower = text.to_ascii_lowercase();
+    let skip = if lower.starts_with("from ") { 5 } else { 0 };
+    RANGE_SEPARATORS.iter().find_map(|separator| {
+        let at = lower[skip..].find(separator)? + skip;
+        let start = text[skip..at].trim();
+        let end = text[at + separator.len()..].trim();
+        (!start.is_empty() && !end.is_empty()).then_some((start, end))
+    })
+}
+
+/// The same wall-clock time one day after `epoch`.
+fn next_day_epoch(epoch: i64, tz: &Tz) -> Result<i64, String> {
+    let local = DateTime::from_timestamp(epoch, 0)
+        .ok_or_else(|| "Time is out of range".to_string())?
+        .with_timezone(tz)
+        .naive_local();
+    let next = local
+        .checked_add_signed(Duration::days(1))
+        .ok_or_else(|| "Date is out of range".to_string())?;
+    Ok(resolve_local(tz, next)?.timestamp())
+}
+
+/// One end of a range; a bare hour such as the "5" in "9 to 5" reads as
+/// "at 5".
+fn parse_range_end(
+    text: &str,
+    now: &DateTime<Tz>,
+    locale: &ParseLocale,
+    rules: &ParseRules,
+) -> Result<Interpretation, String> {
+    parse_with_locale(text, now, locale, rules)
+        .or_else(|e| parse_with_locale(&format!("at {text}"), now, locale, rules).map_err(|_| e))
+}
+
+/// Parse a range of two `parse_with_locale` inputs. The end is read on the
+/// start's day, so "tomorrow 3pm to 5pm" ends tomorrow. An end that still
+/// lands before the start, as in "10pm to 2am", moves a day later when
+/// `roll_over` allows it and is an error otherwise.
+pub fn parse_range(
+    input: &str,
+    now: &DateTime<Tz>,
+    locale: &ParseLocale,
+    rules: &ParseRules,
+    roll_over: bool,
+) -> Result<RangeInterpretation, String> {
+    let (start_text, end_text) =
+        split_range(input).ok_or_else(|| "Expected a range such as '3pm to 5pm'".to_string())?;
+    let start =
+        parse_range_end(start_text, now, locale, rules).map_err(|e| format!("Range start: {e}"))?;
+    let tz = now.timezone();
+    let start_epoch = start.best().epoch;
+    let start_moment = DateTime::from_timestamp(start_epoch, 0)
+        .ok_or_else(|| "Time is out of range".to_string())?
+        .with_timezone(&tz);
+    let end_rules = ParseRules {
+        bare_time: BareTimePolicy::Today,
+        ..*rules
+    };
+    let mut end = parse_range_end(end_text, &start_moment, locale, &end_rules)
+        .map_err(|e| format!("Range end: {e}"))?;
+
+    let rolled_over = end.best().epoch < start_epoch;
+    if rolled_over {
+        if !roll_over {
+            return Err("Range ends before it starts".to_string());
+        }
+        for resolution in &mut end.candidates {
+            resolution.epoch = next_day_epoch(resolution.epoch, &tz)?;
+        }
+        if end.best().epoch < start_epoch {
+            return Err("Range ends before it starts".to_string());
+        }
+    }
+    Ok(RangeInterpretation {
+        start,
+        end,
+        rolled_over,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Friday 2024-03-15 14:20 UTC.
+    const NOW: i64 = 1_710_512_400;
+
+    #[test]
+    fn relative_covers_every_unit() {
+        assert_eq!(parse_relative("in 1 second", NOW), Ok(NOW + 1));
+        assert_eq!(parse_relative("in 30 secs", NOW), Ok(NOW + 30));
+        assert_eq!(parse_relative("in 90 minutes", NOW), Ok(NOW + 90 * 60));
+        assert_eq!(parse_relative("in an hour", NOW), Ok(NOW + 3_600));
+        assert_eq!(parse_relative("in 2 days", NOW), Ok(NOW + 2 * 86_400));
+        assert_eq!(parse_relative("in 3 weeks", NOW), Ok(NOW + 3 * 604_800));
+        // 2024-04-15 14:20 UTC
+        assert_eq!(parse_relative("in 1 month", NOW), Ok(1_713_190_800));
+        // 2025-03-15 14:20 UTC
+        assert_eq!(parse_relative("in a year", NOW), Ok(1_742_048_400));
+    }
+
+    #[test]
+    fn relative_accepts_fractions_and_compounds() {
+        assert_eq!(parse_relative("in 1.5 hours", NOW), Ok(NOW + 5_400));
+        assert_eq!(parse_relative("in 0.5 days", NOW), Ok(NOW + 43_200));
+        assert_eq!(parse_relative("in 90min", NOW), Ok(NOW + 5_400));
+        assert_eq!(
+            parse_relative("in 1 hour and 30 minutes", NOW),
+            Ok(NOW + 5_400)
+        );
+        assert_eq!(parse_relative("3 hours from now", NOW), Ok(NOW + 10_800));
+    }
+
+    #[test]
+    fn relative_reads_past_phrases() {
+        assert_eq!(parse_relative("2 days ago", NOW), Ok(NOW - 2 * 86_400));
+        assert_eq!(parse_relative("half an hour ago", NOW), Ok(NOW - 1_800));
+        assert_eq!(parse_relative("1.5 hours ago", NOW), Ok(NOW - 5_400));
+        // 2023-03-15 14:20 UTC
+        assert_eq!(parse_relative("1 year ago", NOW), Ok(1_678_890_000));
+    }
+
+    #[test]
+    fn relative_rejects_unclear_input() {
+        assert!(parse_relative("in -3 days", NOW).is_err());
+        assert!(parse_relative("in 2 days ago", NOW).is_err());
+        assert!(parse_relative("in 5", NOW).is_err());
+        assert!(parse_relative("in 3 fortnights", NOW).is_err());
+        assert!(parse_relative("next friday", NOW).is_err());
+    }
+
+    fn tokyo(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Tz> {
+        Tz::Asia__Tokyo
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn candidates(input: &str, now: &DateTime<Tz>, rules: &ParseRules) -> Vec<DateTime<Tz>> {
+        parse(input, now, rules)
+            .unwrap_or_else(|e| panic!("{input}: {e}"))
+            .candidates
+            .iter()
+            .map(|resolution| {
+                DateTime::from_timestamp(resolution.epoch, 0)
+                    .unwrap()
+                    .with_timezone(&now.timezone())
+            })
+            .collect()
+    }
+
+    const ASK: ParseRules = ParseRules {
+        starts_on: Weekday::Mon,
+        this_includes_today: true,
+        bare_time: BareTimePolicy::Ask,
+        date_only_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+    };
+
+    #[test]
+    fn bare_hour_is_ambiguous() {
+        // Friday 14:20: 5pm is still ahead today, 5am is tomorrow.
+        let now = tokyo(2024, 3, 15, 14, 20);
+        let at_five = parse("at 5", &now, &ASK).unwrap();
+        assert!(at_five.needs_clarification());
+        assert_eq!(
+            candidates("at 5", &now, &ASK),
+            [tokyo(2024, 3, 15, 17, 0), tokyo(2024, 3, 16, 5, 0)]
+        );
+        assert!(!parse("at 5", &now, &ParseRules::default())
+            .unwrap()
+            .needs_clarification());
+        for explicit in ["at 5pm", "17:00", "05:00", "tonight at 8"] {
+            assert!(
+                !parse(explicit, &now, &ASK).unwrap().needs_clarification(),
+                "{explicit}"
+            );
+        }
+    }
+
+    #[test]
+    fn next_weekend_is_ambiguous_before_the_weekend() {
+        let friday = tokyo(2024, 3, 15, 14, 20);
+        assert!(parse("next weekend", &friday, &ASK)
+            .unwrap()
+            .needs_clarification());
+        assert_eq!(
+            candidates("next weekend", &friday, &ASK),
+            [tokyo(2024, 3, 16, 9, 0), tokyo(2024, 3, 23, 9, 0)]
+        );
+        assert_eq!(
+            candidates("this weekend", &friday, &ASK),
+            [tokyo(2024, 3, 16, 9, 0)]
+        );
+        // During the weekend "next" can only mean the following one.
+        let saturday = tokyo(2024, 3, 16, 10, 0);
+        assert_eq!(
+            candidates("next weekend", &saturday, &ASK),
+            [tokyo(2024, 3, 23, 9, 0)]
+        );
+    }
+
+    #[test]
+    fn iso_honors_embedded_offsets() {
+        let now = tokyo(2024, 3, 15, 10, 0);
+        // 2024-03-15T14:30:00Z
+        let utc = 1_710_513_000;
+        let epoch =
+            |input: &str| parse(input, &now, &ParseRules::default()).map(|i| i.best().epoch);
+        assert_eq!(epoch("2024-03-15T14:30:00Z"), Ok(utc));
+        assert_eq!(epoch("2024-03-15T14:30Z"), Ok(utc));
+        assert_eq!(epoch("2024-03-15T14:30:00+05:30"), Ok(utc - 19_800));
+        assert_eq!(epoch("2024-03-15T14:30:00-04:00"), Ok(utc + 14_400));
+    }
+
+    #[test]
+    fn iso_without_offset_uses_the_local_zone() {
+        let now = tokyo(2024, 3, 15, 10, 0);
+        let rules = ParseRules::default();
+        assert_eq!(
+            candidates("2024-03-15T14:30:00", &now, &rules),
+            [tokyo(2024, 3, 15, 14, 30)]
+        );
+        assert_eq!(
+            candidates("2024-03-15 14:30", &now, &rules),
+            [tokyo(2024, 3, 15, 14, 30)]
+        );
+        let date_only = parse("2024-03-15", &now, &rules).unwrap().best();
+        assert_eq!(date_only.epoch, tokyo(2024, 3, 15, 9, 0).timestamp());
+        assert!(!date_only.had_explicit_time);
+    }
+
+    fn march_2024(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn friday(modifier: WeekdayModifier, today: NaiveDate, rules: &ParseRules) -> NaiveDate {
+        let dates = resolve_dates(DateSpec::Weekday(Weekday::Fri, modifier), today, rules);
+        assert_eq!(dates.as_ref().map(Vec::len), Ok(1));
+        dates.unwrap()[0]
+    }
+
+    #[test]
+    fn this_and_next_friday_through_a_monday_week() {
+        let rules = ParseRules::default();
+        // (today, this friday, next friday) for Mon 2024-03-11 to Sun 03-17.
+        let expected = [
+            (11, 15, 22),
+            (12, 15, 22),
+            (13, 15, 22),
+            (14, 15, 22),
+            (15, 15, 22),
+            (16, 22, 22),
+            (17, 22, 22),
+        ];
+        for (today, this, next) in expected {
+            let today = march_2024(today);
+            assert_eq!(
+                friday(WeekdayModifier::This, today, &rules),
+                march_2024(this)
+            );
+            assert_eq!(
+                friday(WeekdayModifier::Next, today, &rules),
+                march_2024(next)
+            );
+        }
+    }
+
+    #[test]
+    fn this_and_next_friday_through_a_sunday_week_without_today() {
+        let rules = ParseRules {
+            starts_on: Weekday::Sun,
+            this_includes_today: false,
+            ..ParseRules::default()
+        };
+        let expected = [
+            (11, 15, 22),
+            (12, 15, 22),
+            (13, 15, 22),
+            (14, 15, 22),
+            (15, 22, 22),
+            (16, 22, 22),
+            // A new week starts on Sunday.
+            (17, 22, 29),
+        ];
+        for (today, this, next) in expected {
+            let today = march_2024(today);
+            assert_eq!(
+                friday(WeekdayModifier::This, today, &rules),
+                march_2024(this)
+            );
+            assert_eq!(
+                friday(WeekdayModifier::Next, today, &rules),
+                march_2024(next)
+            );
+        }
+    }
+
+    #[test]
+    fn this_weekday_on_that_day_follows_this_includes_today() {
+        let monday = march_2024(11);
+        let this_monday = DateSpec::Weekday(Weekday::Mon, WeekdayModifier::This);
+        let includes = ParseRules::default();
+        let excludes = ParseRules {
+            this_includes_today: false,
+            ..ParseRules::default()
+        };
+        assert_eq!(
+            resolve_dates(this_monday, monday, &includes),
+            Ok(vec![monday])
+        );
+        assert_eq!(
+            resolve_dates(this_monday, monday, &excludes),
+            Ok(vec![march_2024(18)])
+        );
+    }
+
+    #[test]
+    fn passed_holidays_roll_to_next_year() {
+        let rules = ParseRules::default();
+        let boxing_day = tokyo(2024, 12, 26, 10, 0);
+        assert_eq!(
+            candidates("christmas", &boxing_day, &rules),
+            vec![tokyo(2025, 12, 25, 9, 0)]
+        );
+        assert_eq!(
+            candidates("christmas 6pm", &boxing_day, &rules),
+            vec![tokyo(2025, 12, 25, 18, 0)]
+        );
+        assert_eq!(
+            candidates("halloween 2030", &boxing_day, &rules),
+            vec![tokyo(2030, 10, 31, 9, 0)]
+        );
+    }
+
+    fn next(input: &str, now: &DateTime<Tz>) -> DateTime<Tz> {
+        let unix = next_occurrence(input, now).unwrap_or_else(|e| panic!("{input}: {e}"));
+        DateTime::from_timestamp(unix, 0)
+            .unwrap()
+            .with_timezone(&now.timezone())
+    }
+
+    #[test]
+    fn weekdays_skip_the_weekend() {
+        let friday_evening = tokyo(2024, 3, 15, 18, 0);
+        assert_eq!(
+            next("weekdays at 9", &friday_evening),
+            tokyo(2024, 3, 18, 9, 0)
+        );
+        assert_eq!(
+            next("weekdays at 9", &tokyo(2024, 3, 16, 12, 0)),
+            tokyo(2024, 3, 18, 9, 0)
+        );
+        assert_eq!(
+            next("weekdays at 9", &tokyo(2024, 3, 17, 23, 0)),
+            tokyo(2024, 3, 18, 9, 0)
+        );
+        assert_eq!(
+            next("every weekday 9am", &tokyo(2024, 3, 15, 8, 0)),
+            tokyo(2024, 3, 15, 9, 0)
+        );
+    }
+
+    #[test]
+    fn recurrence_without_a_time_starts_after_now() {
+        let friday = tokyo(2024, 3, 15, 10, 0);
+        assert_eq!(next("every monday", &friday), tokyo(2024, 3, 18, 0, 0));
+        assert_eq!(next("every friday", &friday), tokyo(2024, 3, 22, 0, 0));
+        assert_eq!(next("weekdays", &friday), tokyo(2024, 3, 18, 0, 0));
+        assert_eq!(next("daily", &friday), tokyo(2024, 3, 16, 0, 0));
+        let midnight = tokyo(2024, 3, 15, 0, 0);
+        assert_eq!(next("every friday", &midnight), tokyo(2024, 3, 22, 0, 0));
+    }
+
+    fn with_bare_time(bare_time: BareTimePolicy) -> ParseRules {
+        ParseRules {
+            bare_time,
+            ..ParseRules::default()
+        }
+    }
+
+    #[test]
+    fn bare_time_just_before_the_hour_stays_today() {
+        let before = tokyo(2024, 3, 15, 16, 59);
+        let five_pm = vec![tokyo(2024, 3, 15, 17, 0)];
+        for policy in [BareTimePolicy::Next, BareTimePolicy::Today] {
+            assert_eq!(
+                candidates("at 5", &before, &with_bare_time(policy)),
+                five_pm
+            );
+        }
+        assert_eq!(
+            candidates("at 5", &before, &with_bare_time(BareTimePolicy::Ask)),
+            vec![tokyo(2024, 3, 15, 17, 0), tokyo(2024, 3, 16, 5, 0)]
+        );
+    }
+
+    #[test]
+    fn bare_time_at_the_hour_counts_as_passed() {
+        let at = tokyo(2024, 3, 15, 17, 0);
+        assert_eq!(
+            candidates("at 5", &at, &with_bare_time(BareTimePolicy::Next)),
+            vec![tokyo(2024, 3, 16, 5, 0)]
+        );
+        // Today never leaves the date, so it keeps the later reading.
+        assert_eq!(
+            candidates("at 5", &at, &with_bare_time(BareTimePolicy::Today)),
+            vec![tokyo(2024, 3, 15, 17, 0)]
+        );
+        assert_eq!(
+            candidates("at 5", &at, &with_bare_time(BareTimePolicy::Ask)),
+            vec![tokyo(2024, 3, 16, 5, 0), tokyo(2024, 3, 16, 17, 0)]
+        );
+    }
+
+    #[test]
+    fn bare_time_before_the_early_reading_takes_it() {
+        let early = tokyo(2024, 3, 15, 4, 30);
+        for policy in [BareTimePolicy::Next, BareTimePolicy::Today] {
+            assert_eq!(
+                candidates("at 5", &early, &with_bare_time(policy)),
+                vec![tokyo(2024, 3, 15, 5, 0)]
+            );
+        }
+    }
+
+    fn utc(hour: u32, minute: u32) -> i64 {
+        Tz::UTC
+            .with_ymd_and_hms(2024, 3, 15, hour, minute, 0)
+            .unwrap()
+            .timestamp()
+    }
+
+    #[test]
+    fn round_epoch_goes_to_the_nearest_step() {
+        assert_eq!(round_epoch(utc(14, 7), &Tz::UTC, 15), utc(14, 0));
+        assert_eq!(round_epoch(utc(14, 8), &Tz::UTC, 15), utc(14, 15));
+        assert_eq!(round_epoch(utc(14, 52), &Tz::UTC, 15), utc(14, 45));
+        assert_eq!(
+            round_epoch(utc(23, 53), &Tz::UTC, 15),
+            utc(23, 45) + 15 * 60
+        );
+    }
+
+    #[test]
+    fn round_epoch_keeps_exact_steps_and_rounds_halfway_up() {
+        assert_eq!(round_epoch(utc(14, 30), &Tz::UTC, 15), utc(14, 30));
+        assert_eq!(round_epoch(utc(14, 15), &Tz::UTC, 30), utc(14, 30));
+        assert_eq!(round_epoch(utc(14, 15) - 1, &Tz::UTC, 30), utc(14, 0));
+        assert_eq!(round_epoch(utc(14, 7), &Tz::UTC, 0), utc(14, 7));
+    }
+
+    #[test]
+    fn round_epoch_uses_the_local_wall_clock() {
+        // 08:20 UTC is 14:05 in Kathmandu (+05:45), which rounds to 14:00
+        // local, 08:15 UTC.
+        assert_eq!(
+            round_epoch(utc(8, 20), &Tz::Asia__Kathmandu, 30),
+            utc(8, 15)
+        );
+    }
+
+    #[test]
+    fn date_without_a_time_gets_the_default_time() {
+        let now = tokyo(2024, 3, 10, 14, 20);
+        let rules = ParseRules::default();
+        let best = |input, rules: &ParseRules| parse(input, &now, rules).unwrap().best();
+
+        let dated = best("march 15", &rules);
+        assert_eq!(dated.epoch, tokyo(2024, 3, 15, 9, 0).timestamp());
+        assert!(!dated.had_explicit_time);
+        let midnight = best("march 15 00:00", &rules);
+        assert_eq!(midnight.epoch, tokyo(2024, 3, 15, 0, 0).timestamp());
+        assert!(midnight.had_explicit_time);
+
+        let evening = ParseRules {
+            date_only_time: NaiveTime::from_hms_opt(17, 30, 0).unwrap(),
+            ..rules
+        };
+        assert_eq!(
+            best("march 15", &evening).epoch,
+            tokyo(2024, 3, 15, 17, 30).timestamp()
+        );
+    }
+
+    #[test]
+    fn completions_extend_the_typed_prefix() {
+        assert_eq!(suggest_completions("to"), ["tomorrow", "today", "tonight"]);
+        assert_eq!(suggest_completions("next f"), ["next friday"]);
+        assert_eq!(suggest_completions("in 5 mi"), ["in 5 minutes"]);
+        assert_eq!(
+            suggest_completions("Christ"),
+            ["christmas eve", "christmas day", "christmas"]
+        );
+        assert!(suggest_completions("").is_empty());
+        assert!(suggest_completions("zzz").is_empty());
+        assert!(!suggest_completions("tomorrow").contains(&"tomorrow".to_string()));
+    }
+
+    #[test]
+    fn completions_rank_whole_phrases_before_finished_words() {
+        assert_eq!(suggest_completions("in a w"), ["in a week"]);
+        assert_eq!(suggest_completions("in an h"), ["in an hour"]);
+        assert_eq!(suggest_completions("in 1 d"), ["in 1 day"]);
+        assert_eq!(
+            suggest_completions("in one m"),
+            ["in one minute", "in one month"]
+        );
+        assert_eq!(suggest_completions("in 2 h"), ["in 2 hours"]);
+        let short = suggest_completions("t");
+        assert_eq!(short.len(), SUGGESTION_LIMIT);
+        assert_eq!(short[..4], ["tomorrow", "today", "tonight", "this weekend"]);
+    }
+
+    #[test]
+    fn completions_parse() {
+        let now = tokyo(2024, 3, 15, 14, 20);
+        for input in ["next", "in", "th", "this w", "in 3 d"] {
+            for completion in suggest_completions(input) {
+                assert!(
+                    parse(&completion, &now, &ParseRules::default()).is_ok(),
+                    "{input} -> {completion}"
+                );
+            }
+        }
+    }
+
+    fn new_york(day: u32, hour: u32, minute: u32) -> i64 {
+        Tz::America__New_York
+            .with_ymd_and_hms(2024, 3, day, hour, minute, 0)
+            .unwrap()
+            .timestamp()
+    }
+
+    #[test]
+    fn range_within_a_day() {
+        // Monday 2024-03-11 10:00 in New York.
+        let now = Tz::America__New_York
+            .with_ymd_and_hms(2024, 3, 11, 10, 0, 0)
+            .unwrap();
+        let rules = ParseRules::default();
+        let range = parse_range("3pm to 5pm", &now, &locales::ENGLISH, &rules, false).unwrap();
+        assert_eq!(range.start.best().epoch, new_york(11, 15, 0));
+        assert_eq!(range.end.best().epoch, new_york(11, 17, 0));
+        assert!(!range.rolled_over);
+
+        let range = parse_range(
+            "tomorrow 3pm until 5:30pm",
+            &now,
+            &locales::ENGLISH,
+            &rules,
+            false,
+        )
+        .unwrap();
+        assert_eq!(range.start.best().epoch, new_york(12, 15, 0));
+        assert_eq!(range.end.best().epoch, new_york(12, 17, 30));
+    }
+
+    #[test]
+    fn range_past_midnight_needs_roll_over() {
+        let now = Tz::America__New_York
+            .with_ymd_and_hms(2024, 3, 11, 10, 0, 0)
+            .unwrap();
+        let rules = ParseRules::default();
+        let range = parse_range("10pm to 2am", &now, &locales::ENGLISH, &rules, true).unwrap();
+        assert_eq!(range.start.best().epoch, new_york(11, 22, 0));
+        assert_eq!(range.end.best().epoch, new_york(12, 2, 0));
+        assert!(range.rolled_over);
+
+        assert!(parse_range("10pm to 2am", &now, &locales::ENGLISH, &rules, false).is_err());
+    }
+
+    #[test]
+    fn noon_and_midnight_name_exact_times() {
+        let berlin = |day: u32, hour: u32| {
+            Tz::Europe__Berlin
+                .with_ymd_and_hms(2024, 3, day, hour, 0, 0)
+                .unwrap()
+        };
+        // Wednesday 2024-03-13 10:00 in Berlin.
+        let now = berlin(13, 10);
+        assert_eq!(
+            candidates("noon", &now, &ASK),
+            vec![berlin(13, 12)],
+            "noon has no meridiem to ask about"
+        );
+        assert_eq!(
+            candidates("tomorrow noon", &now, &ASK),
+            vec![berlin(14, 12)]
+        );
+        assert_eq!(
+            candidates("friday at noon", &now, &ASK),
+            vec![berlin(15, 12)]
+        );
+        // Midnight "tonight" is the one that ends today.
+        assert_eq!(
+            candidates("midnight tonight", &now, &ASK),
+            vec![berlin(14, 0)]
+        );
+        assert_eq!(
+            candidates("midnight friday", &now, &ASK),
+            vec![berlin(15, 0)]
+        );
+        assert_eq!(
+            candidates("friday midnight", &now, &ASK),
+            vec![berlin(15, 0)]
+        );
+        assert!(parse("noon midnight", &now, &ASK).is_err());
+    }
+}
@@ -0,0 +1,482 @@
+//! Discord `<t:UNIX:X>` timestamp formats.
+
+use crate::timezone;
+use chrono_tz::Tz;
+use serde::Serialize;
+
+/// The seven format letters, in the order the overlay lists them.
+pub const FORMAT_LETTERS: [&str; 7] = ["d", "D", "t", "T", "f", "F", "R"];
+
+/// Human names matching the overlay's format list.
+pub fn format_description(format: &str) -> Option<&'static str> {
+    match format {
+        "d" => Some("Short Date"),
+        "D" => Some("Long Date"),
+        "t" => Some("Short Time"),
+        "T" => Some("Long Time"),
+        "f" => Some("Short Date/Time"),
+        "F" => Some("Long Date/Time"),
+        "R" => Some("Relative Time"),
+        _ => None,
+    }
+}
+
+pub fn validate_format(format: &str) -> Result<(), String> {
+    if FORMAT_LETTERS.contains(&format) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid Discord timestamp format '{format}'; expected one of {}",
+            FORMAT_LETTERS.join(", ")
+        ))
+    }
+}
+
+/// A non-empty list of distinct format letters.
+pub fn validate_enabled_formats(enabled: &[String]) -> Result<(), String> {
+    if enabled.is_empty() {
+        return Err("Enable at least one timestamp format".to_string());
+    }
+    for (index, format) in enabled.iter().enumerate() {
+        validate_format(format)?;
+        if enabled[..index].contains(format) {
+            return Err(format!("Timestamp format '{format}' is listed twice"));
+        }
+    }
+    Ok(())
+}
+
+/// Step one place through the `enabled` formats in `FORMAT_LETTERS` order,
+/// wrapping at both ends. `current` itself need not be enabled.
+fn step_format(current: &str, step: isize, enabled: &[String]) -> Result<&'static str, String> {
+    validate_format(current)?;
+    let index = FORMAT_LETTERS
+        .iter()
+        .position(|format| *format == current)
+        .unwrap_or_default();
+    let len = FORMAT_LETTERS.len() as isize;
+    (1..=len)
+        .map(|offset| FORMAT_LETTERS[(index as isize + step * offset).rem_euclid(len) as usize])
+        .find(|format| enabled.iter().any(|enabled| enabled == format))
+        .ok_or_else(|| "No timestamp formats are enabled".to_string())
+}
+
+pub fn next_format(current: &str, enabled: &[String]) -> Result<&'static str, String> {
+    step_format(current, 1, enabled)
+}
+
+pub fn prev_format(current: &str, enabled: &[String]) -> Result<&'static str, String> {
+    step_format(current, -1, enabled)
+}
+
+pub fn timestamp(unix: i64, format: &str) -> String {
+    format!("<t:{unix}:{format}>")
+}
+
+/// A `<t:UNIX:X>` tag found in free text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedTimestamp {
+    pub unix: i64,
+    pub format: String,
+    /// The tag exactly as it appeared in the input.
+    pub text: String,
+}
+
+/// Discord renders a tag without a letter as short date/time.
+const IMPLICIT_FORMAT: &str = "f";
+
+fn parse_tag(inner: &str) -> Option<(i64, &str)> {
+    let (unix, format) = match inner.split_once(':') {
+        Some((unix, format)) => (unix, format),
+        None => (inner, IMPLICIT_FORMAT),
+    };
+    let digits = unix.strip_prefix('-').unwrap_or(unix);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    validate_format(format).ok()?;
+    Some((unix.parse().ok()?, format))
+}
+
+/// Find every well-formed `<t:UNIX>` / `<t:UNIX:X>` tag in `input`, in order.
+/// Malformed tags are skipped rather than failing the whole scan.
+pub fn extract_timestamps(input: &str) -> Vec<ParsedTimestamp> {
+    let mut found = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find("<t:") {
+        let after_open = &rest[start + 3..];
+        let Some(end) = after_open.find('>') else {
+            break;
+        };
+        let inner = &after_open[..end];
+        // A nested "<t:" means this opener was malformed; rescan from there.
+        if let Some(nested) = inner.find("<t:") {
+            rest = &after_open[nested..];
+            continue;
+        }
+        if let Some((unix, format)) = parse_tag(inner) {
+            found.push(ParsedTimestamp {
+                unix,
+                format: format.to_string(),
+                text: rest[start..start + 3 + end + 1].to_string(),
+            });
+        }
+        rest = &after_open[end + 1..];
+    }
+    found
+}
+
+/// Re-emit the first timestamp tag in `input` with `to_format`.
+pub fn reformat_timestamp(input: &str, to_format: &str) -> Result<String, String> {
+    validate_format(to_format)?;
+    extract_timestamps(input)
+        .first()
+        .map(|found| timestamp(found.unix, to_format))
+        .ok_or_else(|| "No Discord timestamp found in input".to_string())
+}
+
+/// Epoch values at or above this are read as milliseconds; in seconds they
+/// would land past the year 5000.
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+/// 9999-12-31T23:59:59Z, the last instant Discord clients render sensibly.
+const EPOCH_SECONDS_MAX: i64 = 253_402_300_799;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattedTimestamp {
+    pub format: String,
+    pub timestamp: String,
+}
+
+/// Every format for one instant, in `FORMAT_LETTERS` order.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordFormats {
+    pub unix: i64,
+    pub formats: Vec<FormattedTimestamp>,
+}
+
+impl DiscordFormats {
+    /// Drop the formats not in `enabled`.
+    pub fn only(mut self, enabled: &[String]) -> Self {
+        self.formats
+            .retain(|formatted| enabled.contains(&formatted.format));
+        self
+    }
+}
+
+pub fn all_formats(unix: i64) -> DiscordFormats {
+    DiscordFormats {
+        unix,
+        formats: FORMAT_LETTERS
+            .iter()
+            .map(|format| FormattedTimestamp {
+                format: format.to_string(),
+                timestamp: timestamp(unix, format),
+            })
+            .collect(),
+    }
+}
+
+/// One labeled line per format in `enabled`, in `FORMAT_LETTERS` order,
+/// e.g. "Long Date (D): <t:1710513000:D>".
+pub fn labeled_formats(unix: i64, enabled: &[String]) -> String {
+    FORMAT_LETTERS
+        .iter()
+        .filter(|format| enabled.iter().any(|enabled| enabled == *format))
+        .map(|format| {
+            format!(
+                "{} ({format}): {}",
+                format_description(format).unwrap_or(format),
+                timestamp(unix, format)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A fenced code block with the raw tag for each of `formats`, one per line
+/// in the order given, so the syntax shows instead of rendering.
+pub fn code_block(unix: i64, formats: &[String]) -> Result<String, String> {
+    if formats.is_empty() {
+        return Err("Choose at least one format".to_string());
+    }
+    let lines = formats
+        .iter()
+        .map(|format| validate_format(format).map(|()| timestamp(unix, format)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(format!("```\n{}\n```", lines.join("\n")))
+}
+
+/// Read a pasted Unix timestamp in seconds or milliseconds, picking the unit
+/// by magnitude.
+pub fn parse_epoch(input: &str) -> Result<i64, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err("Enter a Unix timestamp".to_string());
+    }
+    if trimmed.starts_with('-') {
+        return Err(format!(
+            "'{trimmed}' is before 1970; negative timestamps are not supported"
+        ));
+    }
+    if !trimmed.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!(
+            "'{trimmed}' is not a Unix timestamp; expected whole seconds or milliseconds"
+        ));
+    }
+    let value: i64 = trimmed
+        .parse()
+        .map_err(|_| format!("'{trimmed}' is too large to be a Unix timestamp"))?;
+    let seconds = if value >= EPOCH_MILLIS_THRESHOLD {
+        value / 1000
+    } else {
+        value
+    };
+    if seconds > EPOCH_SECONDS_MAX {
+        return Err(format!(
+            "'{trimmed}' is past the year 9999 even when read as milliseconds"
+        ));
+    }
+    Ok(seconds)
+}
+
+const TEMPLATE_PREFIX: &str = "<t:UNIX:";
+
+/// Expand every `<t:UNIX:X>` placeholder in `template` into a timestamp for
+/// `unix`. Text outside the placeholders is copied through unchanged.
+pub fn format_template(unix: i64, template: &str) -> Result<String, String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(TEMPLATE_PREFIX) {
+        output.push_str(&rest[..start]);
+        let after_prefix = &rest[start + TEMPLATE_PREFIX.len()..];
+        let Some(end) = after_prefix.find('>') else {
+            return Err(format!(
+                "Unterminated placeholder '{}' in template",
+                &rest[start..]
+            ));
+        };
+        let format = &after_prefix[..end];
+        validate_format(format).map_err(|e| format!("Bad placeholder in template: {e}"))?;
+        output.push_str(&timestamp(unix, format));
+        rest = &after_prefix[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+const TIME_MARKER: &str = "{{time}}";
+
+/// Replace every `{{time}}` in `text` with a `format` timestamp for `unix`.
+/// Text without a marker comes back unchanged.
+pub fn render_template(text: &str, unix: i64, format: &str) -> Result<String, String> {
+    validate_format(format)?;
+    Ok(text.replace(TIME_MARKER, &timestamp(unix, format)))
+}
+
+/// Formats for `seconds` after `reference` (before it when negative).
+pub fn formats_in(reference: i64, seconds: i64) -> Result<DiscordFormats, String> {
+    let unix = reference
+        .checked_add(seconds)
+        .filter(|unix| (0..=EPOCH_SECONDS_MAX).contains(unix))
+        .ok_or_else(|| format!("An offset of {seconds} seconds is out of range"))?;
+    Ok(all_formats(unix))
+}
+
+/// What an `R` tag for `unix` reads as when viewed at `viewed_at`. Discord
+/// follows moment.js's `fromNow` thresholds ("a few seconds ago", "in a
+/// minute", "in 3 days"); months and years are approximated from days.
+pub fn relative_phrase(unix: i64, viewed_at: i64) -> String {
+    let delta = unix.saturating_sub(viewed_at);
+    let seconds = delta.unsigned_abs() as f64;
+    let minutes = (seconds / 60.0).round();
+    let hours = (seconds / 3_600.0).round();
+    let days = (seconds / 86_400.0).round();
+    let months = (seconds / 2_629_746.0).round();
+    let years = (seconds / 31_556_952.0).round();
+
+    let amount = if seconds < 45.0 {
+        "a few seconds".to_string()
+    } else if seconds < 90.0 {
+        "a minute".to_string()
+    } else if minutes < 45.0 {
+        format!("{minutes} minutes")
+    } else if minutes < 90.0 {
+        "an hour".to_string()
+    } else if hours < 22.0 {
+        format!("{hours} hours")
+    } else if hours < 36.0 {
+        "a day".to_string()
+    } else if days < 26.0 {
+        format!("{days} days")
+    } else if days < 45.0 {
+        "a month".to_string()
+    } else if days < 320.0 {
+        format!("{} months", months.max(2.0))
+    } else if days < 548.0 {
+        "a year".to_string()
+    } else {
+        format!("{} years", years.max(2.0))
+    };
+
+    if delta >= 0 {
+        format!("in {amount}")
+    } else {
+        format!("{amount} ago")
+    }
+}
+
+/// One format letter as the settings and help screens describe it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatDescription {
+    pub format: String,
+    pub label: String,
+    pub timestamp: String,
+    pub sample: String,
+}
+
+/// Roughly what Discord's English client shows for `format`, rendered in `tz`
+/// with the user's 12/24-hour preference. `viewed_at` anchors `R`.
+pub fn sample_rendering(
+    unix: i64,
+    format: &str,
+    tz: Tz,
+    use_24h: bool,
+    viewed_at: i64,
+) -> Result<String, String> {
+    validate_format(format)?;
+    if format == "R" {
+        return Ok(relative_phrase(unix, viewed_at));
+    }
+    let local = timezone::datetime_from_unix(unix)?.with_timezone(&tz);
+    let (short_time, long_time) = if use_24h {
+        ("%H:%M", "%H:%M:%S")
+    } else {
+        ("%-I:%M %p", "%-I:%M:%S %p")
+    };
+    let pattern = match format {
+        "d" => "%m/%d/%Y".to_string(),
+        "D" => "%B %-d, %Y".to_string(),
+        "t" => short_time.to_string(),
+        "T" => long_time.to_string(),
+        "f" => format!("%B %-d, %Y {short_time}"),
+        _ => format!("%A, %B %-d, %Y {short_time}"),
+    };
+    Ok(local.format(&pattern).to_string())
+}
+
+/// Each format in `enabled` for `unix`, in `FORMAT_LETTERS` order.
+pub fn describe_formats(
+    unix: i64,
+    tz: Tz,
+    use_24h: bool,
+    viewed_at: i64,
+    enabled: &[String],
+) -> Result<Vec<FormatDescription>, String> {
+    FORMAT_LETTERS
+        .iter()
+        .filter(|format| enabled.iter().any(|enabled| enabled == *format))
+        .map(|format| {
+            Ok(FormatDescription {
+                format: format.to_string(),
+                label: format_description(format).unwrap_or(format).to_string(),
+                timestamp: timestamp(unix, format),
+                sample: sample_rendering(unix, format, tz, use_24h, viewed_at)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letters(formats: &[&str]) -> Vec<String> {
+        formats.iter().map(|format| format.to_string()).collect()
+    }
+
+    #[test]
+    fn format_cycling_wraps_at_both_ends() {
+        let all = letters(&FORMAT_LETTERS);
+        assert_eq!(next_format("d", &all), Ok("D"));
+        assert_eq!(next_format("R", &all), Ok("d"));
+        assert_eq!(prev_format("D", &all), Ok("d"));
+        assert_eq!(prev_format("d", &all), Ok("R"));
+        assert!(next_format("x", &all).is_err());
+    }
+
+    #[test]
+    fn format_cycling_skips_disabled_formats() {
+        let some = letters(&["D", "F", "R"]);
+        assert_eq!(next_format("D", &some), Ok("F"));
+        assert_eq!(next_format("R", &some), Ok("D"));
+        assert_eq!(prev_format("D", &some), Ok("R"));
+        // `current` need not be enabled itself.
+        assert_eq!(next_format("d", &some), Ok("D"));
+        assert_eq!(prev_format("d", &some), Ok("R"));
+        assert_eq!(next_format("F", &letters(&["F"])), Ok("F"));
+        assert!(next_format("F", &[]).is_err());
+    }
+
+    #[test]
+    fn extract_finds_every_tag_and_skips_malformed_ones() {
+        let found = extract_timestamps(
+            "standup <t:1710512400:F>, retro <t:1710516000:R> <t:1:x> <t:abc> \
+             <t:<t:5:t> ends <t:1710519600>",
+        );
+        let tags = found
+            .iter()
+            .map(|tag| (tag.unix, tag.format.as_str(), tag.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            [
+                (1_710_512_400, "F", "<t:1710512400:F>"),
+                (1_710_516_000, "R", "<t:1710516000:R>"),
+                (5, "t", "<t:5:t>"),
+                (1_710_519_600, "f", "<t:1710519600>"),
+            ]
+        );
+        assert!(extract_timestamps("no tags <t:12").is_empty());
+    }
+
+    // Friday 2024-03-15 14:20 UTC.
+    const VIEWED_AT: i64 = 1_710_512_400;
+
+    #[test]
+    fn relative_phrase_reads_future_times() {
+        let phrase = |offset| relative_phrase(VIEWED_AT + offset, VIEWED_AT);
+        assert_eq!(phrase(60), "in a minute");
+        assert_eq!(phrase(10 * 60), "in 10 minutes");
+        assert_eq!(phrase(3 * 86_400), "in 3 days");
+        assert_eq!(phrase(30 * 86_400), "in a month");
+        assert_eq!(phrase(400 * 86_400), "in a year");
+    }
+
+    #[test]
+    fn relative_phrase_reads_past_times() {
+        let phrase = |offset| relative_phrase(VIEWED_AT - offset, VIEWED_AT);
+        assert_eq!(phrase(10), "a few seconds ago");
+        assert_eq!(phrase(2 * 3_600), "2 hours ago");
+        assert_eq!(phrase(100 * 86_400), "3 months ago");
+        assert_eq!(phrase(3 * 365 * 86_400), "3 years ago");
+    }
+
+    #[test]
+    fn relative_phrase_treats_now_as_the_future() {
+        assert_eq!(relative_phrase(VIEWED_AT, VIEWED_AT), "in a few seconds");
+        assert_eq!(
+            relative_phrase(VIEWED_AT - 1, VIEWED_AT),
+            "a few seconds ago"
+        );
+        // Still "a few seconds" up to the 45 s threshold.
+        assert_eq!(
+            relative_phrase(VIEWED_AT + 44, VIEWED_AT),
+            "in a few seconds"
+        );
+        assert_eq!(relative_phrase(VIEWED_AT + 45, VIEWED_AT), "in a minute");
+    }
+}
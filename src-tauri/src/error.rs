@@ -0,0 +1,149 @@
+//! Structured errors returned from Tauri commands.
+//!
+//! Commands serialize an `AppError` as `{ "code": "...", "message": "..." }`
+//! so the frontend can branch on `code` instead of matching message text.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AppError {
+    /// Reading or writing the settings store failed.
+    StoreIo(String),
+    /// A value could not be serialized or deserialized.
+    Serde(String),
+    /// The updater plugin was unavailable or an update step failed.
+    Updater(String),
+    /// Registering or unregistering a global shortcut failed.
+    Hotkey(String),
+    /// Reading or writing the clipboard failed.
+    Clipboard(String),
+    /// The stats database could not be opened or queried.
+    Db(String),
+    /// A requested resource (window, update, entry) does not exist.
+    NotFound(String),
+    /// The caller passed an input the command rejects.
+    Invalid(String),
+    /// Anything else; usually a helper that still reports a plain string.
+    Internal(String),
+}
+
+impl AppError {
+    /// Stable identifier for the frontend; never change an existing code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::StoreIo(_) => "store_io",
+            AppError::Serde(_) => "serde",
+            AppError::Updater(_) => "updater",
+            AppError::Hotkey(_) => "hotkey",
+            AppError::Clipboard(_) => "clipboard",
+            AppError::Db(_) => "db",
+            AppError::NotFound(_) => "not_found",
+            AppError::Invalid(_) => "invalid",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::StoreIo(message)
+            | AppError::Serde(message)
+            | AppError::Updater(message)
+            | AppError::Hotkey(message)
+            | AppError::Clipboard(message)
+            | AppError::Db(message)
+            | AppError::NotFound(message)
+            | AppError::Invalid(message)
+            | AppError::Internal(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal(message)
+    }
+}
+
+/// Lets helpers that still report plain strings call `?` on typed results.
+impl From<AppError> for String {
+    fn from(e: AppError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Serde(e.to_string())
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+/// Oldest entries are dropped past this many.
+pub const RECENT_ERRORS_LIMIT: usize = 50;
+/// The same error again within this many seconds bumps `repeats` instead of
+/// taking a new slot, so one failure loop can't flush everything else out.
+const REPEAT_WINDOW_SECONDS: i64 = 5;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorEntry {
+    pub code: &'static str,
+    pub message: String,
+    /// Unix seconds of the latest occurrence.
+    pub at: i64,
+    pub repeats: u32,
+}
+
+/// Bounded history of recent errors for the diagnostics panel.
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    entries: VecDeque<ErrorEntry>,
+}
+
+impl ErrorLog {
+    pub fn record(&mut self, error: &AppError, at: i64) {
+        if let Some(last) = self.entries.back_mut() {
+            if last.code == error.code()
+                && last.message == error.message()
+                && at - last.at < REPEAT_WINDOW_SECONDS
+            {
+                last.repeats += 1;
+                last.at = at;
+                return;
+            }
+        }
+        if self.entries.len() == RECENT_ERRORS_LIMIT {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ErrorEntry {
+            code: error.code(),
+            message: error.message().to_string(),
+            at,
+            repeats: 0,
+        });
+    }
+
+    /// Newest first.
+    pub fn recent(&self, limit: usize) -> Vec<ErrorEntry> {
+        self.entries.iter().rev().take(limit).cloned().collect()
+    }
+}
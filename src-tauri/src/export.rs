@@ -0,0 +1,45 @@
+//! Conversion history as Markdown for pasting into notes.
+
+use crate::{db::Conversion, discord, timezone};
+use chrono_tz::Tz;
+
+/// Escape the characters Markdown would otherwise treat as formatting.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+        ) {
+            escaped.push('\\');
+        }
+        // Keep each conversion on its own bullet.
+        escaped.push(if c.is_control() { ' ' } else { c });
+    }
+    escaped
+}
+
+/// A dated heading and one bullet per conversion, in the order given:
+/// the typed text, then the Discord tag it produced.
+pub fn history_markdown(
+    conversions: &[Conversion],
+    exported_at: i64,
+    tz: Tz,
+    use_24h: bool,
+) -> Result<String, String> {
+    let mut markdown = format!(
+        "# HammerOverlay history\n\nExported {}.\n\n",
+        timezone::canonical_text(exported_at, tz, use_24h, true)?
+    );
+    if conversions.is_empty() {
+        markdown.push_str("_No conversions yet._\n");
+    }
+    for conversion in conversions {
+        markdown.push_str(&format!(
+            "- {} → `{}`\n",
+            escape_markdown(conversion.input.trim()),
+            discord::timestamp(conversion.unix, &conversion.format)
+        ));
+    }
+    Ok(markdown)
+}
@@ -0,0 +1,368 @@
+//! Single-instance guard.
+//!
+//! The OS lock normally dies with its owner, but a hard crash can leave it
+//! held (a zombie, a lingering lock file). Each holder also records its PID
+//! and process identity next to the lock, and a refused launch checks both
+//! before giving up, so a recycled PID does not keep the app from starting.
+//!
+//! A launch that finds a live holder hands its deep link (or just a request
+//! to show the window) to that instance over a loopback socket, whose port
+//! and a per-run token sit next to the lock, and then exits.
+
+use single_instance::SingleInstance;
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    time::Duration,
+};
+
+#[cfg(windows)]
+use crate::CREATE_NO_WINDOW;
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+/// Lock name scoped to bundle identifier and build channel, so dev, beta and
+/// stable builds can run side by side.
+pub fn lock_name(identifier: &str) -> String {
+    let channel = option_env!("HAMMER_OVERLAY_CHANNEL").unwrap_or(if cfg!(debug_assertions) {
+        "dev"
+    } else {
+        "stable"
+    });
+    format!("{identifier}.{channel}")
+}
+
+/// macOS locks a file at the given path rather than a named object.
+fn lock_path(lock_name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{lock_name}.lock"))
+}
+
+fn lock_target(lock_name: &str) -> String {
+    if cfg!(target_os = "macos") {
+        lock_path(lock_name).display().to_string()
+    } else {
+        lock_name.to_string()
+    }
+}
+
+fn pid_file_path(lock_name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{lock_name}.pid"))
+}
+
+fn launch_file_path(lock_name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{lock_name}.port"))
+}
+
+/// How long a second launch waits on the running instance.
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(2);
+/// Token line plus the longest link worth reading.
+const MAX_HANDOFF_BYTES: u64 = 4096;
+
+/// The running instance's end of the launch hand-off.
+pub struct Launches {
+    listener: TcpListener,
+    token: String,
+}
+
+impl Launches {
+    fn bind(lock_name: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .map_err(|e| format!("Failed to listen for later launches: {e}"))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read the launch listener address: {e}"))?
+            .port();
+        let mut bytes = [0_u8; 16];
+        getrandom::fill(&mut bytes)
+            .map_err(|e| format!("Failed to generate the launch token: {e}"))?;
+        let token: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        fs::write(launch_file_path(lock_name), format!("{port}\n{token}"))
+            .map_err(|e| format!("Failed to record the launch listener: {e}"))?;
+        Ok(Self { listener, token })
+    }
+
+    /// Call `on_launch` with each later launch's deep link, or `None` for a
+    /// plain relaunch.
+    pub fn spawn(self, on_launch: impl Fn(Option<String>) + Send + 'static) {
+        std::thread::spawn(move || {
+            for stream in self.listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        log::warn!("Failed to accept a later launch: {e}");
+                        continue;
+                    }
+                };
+                match read_handoff(stream, &self.token) {
+                    Ok(link) => on_launch(link),
+                    Err(e) => log::warn!("Ignoring a later launch: {e}"),
+                }
+            }
+        });
+    }
+}
+
+fn read_handoff(stream: TcpStream, token: &str) -> Result<Option<String>, String> {
+    stream
+        .set_read_timeout(Some(HANDOFF_TIMEOUT))
+        .map_err(|e| format!("Failed to set a read timeout: {e}"))?;
+    let mut lines = BufReader::new(stream.take(MAX_HANDOFF_BYTES)).lines();
+    let mut next_line = || {
+        lines
+            .next()
+            .transpose()
+            .map_err(|e| format!("Failed to read the launch: {e}"))
+    };
+    if next_line()?.as_deref() != Some(token) {
+        return Err("wrong token".to_string());
+    }
+    Ok(next_line()?.filter(|link| !link.is_empty()))
+}
+
+/// Pass `link` to the running instance, which also shows its window.
+fn hand_off(lock_name: &str, link: Option<&str>) -> Result<(), String> {
+    let record = fs::read_to_string(launch_file_path(lock_name))
+        .map_err(|e| format!("Failed to read the launch listener record: {e}"))?;
+    let (port, token) = record
+        .split_once('\n')
+        .and_then(|(port, token)| Some((port.trim().parse::<u16>().ok()?, token.trim())))
+        .ok_or_else(|| "The launch listener record is malformed".to_string())?;
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&address, HANDOFF_TIMEOUT)
+        .map_err(|e| format!("Failed to reach the running instance: {e}"))?;
+    stream
+        .set_write_timeout(Some(HANDOFF_TIMEOUT))
+        .and_then(|()| write!(stream, "{token}\n{}\n", link.unwrap_or_default()))
+        .map_err(|e| format!("Failed to send the launch to the running instance: {e}"))
+}
+
+fn record_holder(lock_name: &str) {
+    let pid = std::process::id();
+    let record = match process_identity(pid) {
+        Ok(Some(identity)) => format!("{pid}\n{identity}"),
+        _ => pid.to_string(),
+    };
+    if let Err(e) = fs::write(pid_file_path(lock_name), record) {
+        log::warn!("Failed to record single-instance holder PID: {e}");
+    }
+}
+
+/// The recorded PID and, when it was captured, that process's identity.
+fn recorded_holder(lock_name: &str) -> Option<(u32, Option<String>)> {
+    let record = fs::read_to_string(pid_file_path(lock_name)).ok()?;
+    let mut lines = record.lines();
+    let pid = lines.next()?.trim().parse().ok()?;
+    let identity = lines
+        .next()
+        .map(str::trim)
+        .filter(|identity| !identity.is_empty())
+        .map(str::to_string);
+    Some((pid, identity))
+}
+
+/// Something that changes when a PID is reused: the start time in clock
+/// ticks since boot. `Ok(None)` means no such process; zombies count as gone.
+#[cfg(target_os = "linux")]
+fn process_identity(pid: u32) -> Result<Option<String>, String> {
+    let Ok(stat) = fs::read_to_string(format!("/proc/{pid}/stat")) else {
+        return Ok(None);
+    };
+    // Fields after the parenthesized name start at the state (field 3);
+    // the start time is field 22.
+    let fields = stat
+        .rsplit(')')
+        .next()
+        .map(|rest| rest.split_whitespace().collect::<Vec<_>>())
+        .unwrap_or_default();
+    match fields.first() {
+        Some(&"Z") | None => Ok(None),
+        Some(_) => Ok(Some(
+            fields.get(19).copied().unwrap_or_default().to_string(),
+        )),
+    }
+}
+
+/// The start time as `ps` prints it.
+#[cfg(target_os = "macos")]
+fn process_identity(pid: u32) -> Result<Option<String>, String> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "lstart=", "-p", &pid.to_string()])
+        .output()
+        .map_err(|e| format!("Failed to run ps: {e}"))?;
+    let started = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((output.status.success() && !started.is_empty()).then_some(started))
+}
+
+/// The image name; `tasklist` does not report start times.
+#[cfg(windows)]
+fn process_identity(pid: u32) -> Result<Option<String>, String> {
+    let output = std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH", "/FO", "CSV"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run tasklist: {e}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .map(|line| line.split("\",\"").collect::<Vec<_>>())
+        .find(|columns| columns.get(1) == Some(&pid.to_string().as_str()))
+        .and_then(|columns| {
+            columns
+                .first()
+                .map(|image| image.trim_matches('"').to_string())
+        }))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn process_identity(_pid: u32) -> Result<Option<String>, String> {
+    Err("Process lookup is not supported on this platform".to_string())
+}
+
+/// Whether the recorded holder is still the process that took the lock. A
+/// PID now owned by something else counts as gone; if we cannot tell, err on
+/// the side of the running instance.
+fn holder_is_alive(pid: u32, recorded_identity: Option<&str>) -> bool {
+    match process_identity(pid) {
+        Ok(Some(identity)) => recorded_identity.is_none_or(|recorded| recorded == identity),
+        Ok(None) => false,
+        Err(e) => {
+            log::warn!("Could not check single-instance holder PID {pid}: {e}");
+            true
+        }
+    }
+}
+
+fn exit_already_running(lock_name: &str, holder: Option<u32>, link: Option<&str>) -> ! {
+    match holder {
+        Some(pid) => {
+            log::warn!("Another instance of HammerOverlay is already running (PID {pid})")
+        }
+        None => log::warn!("Another instance of HammerOverlay is already running"),
+    }
+    match hand_off(lock_name, link) {
+        Ok(()) => {
+            log::info!("Handed the launch to the running instance");
+            std::process::exit(0);
+        }
+        Err(e) => {
+            // This runs before the app (and its logger and error log) exists,
+            // so `report_error` is out of reach; stderr is what the user sees.
+            log::warn!("{e}");
+            eprintln!("HammerOverlay is already running!");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The held lock and, when it could be set up, the listener for later
+/// launches. Must be kept alive for the lifetime of the app.
+pub struct Instance {
+    _lock: SingleInstance,
+    launches: Option<Launches>,
+}
+
+impl Instance {
+    fn hold(lock: SingleInstance, lock_name: &str) -> Self {
+        record_holder(lock_name);
+        let launches = Launches::bind(lock_name)
+            .inspect_err(|e| log::warn!("{e}; later launches will just exit"))
+            .ok();
+        Self {
+            _lock: lock,
+            launches,
+        }
+    }
+
+    pub fn take_launches(&mut self) -> Option<Launches> {
+        self.launches.take()
+    }
+}
+
+/// Take the single-instance lock. If a live instance holds it, hand `link`
+/// to that instance and exit. `None` means the lock could not be created and
+/// the app is running without it.
+pub fn acquire(lock_name: &str, link: Option<&str>) -> Option<Instance> {
+    let instance = match SingleInstance::new(&lock_target(lock_name)) {
+        Ok(instance) => instance,
+        Err(e) => {
+            log::warn!("Failed to acquire single-instance lock {lock_name}; continuing: {e}");
+            return None;
+        }
+    };
+
+    if instance.is_single() {
+        log::info!("Single instance check passed ({lock_name})");
+        return Some(Instance::hold(instance, lock_name));
+    }
+
+    match recorded_holder(lock_name) {
+        Some((pid, identity)) if !holder_is_alive(pid, identity.as_deref()) => {
+            log::warn!(
+                "Single-instance lock {lock_name} is stale (PID {pid} is gone); breaking it"
+            );
+            drop(instance);
+            if cfg!(target_os = "macos") {
+                let _ = fs::remove_file(lock_path(lock_name));
+            }
+            match SingleInstance::new(&lock_target(lock_name))
+                .ok()
+                .filter(SingleInstance::is_single)
+            {
+                Some(retry) => Some(Instance::hold(retry, lock_name)),
+                // Someone else took the lock in the meantime.
+                None => exit_already_running(lock_name, None, link),
+            }
+        }
+        holder => exit_already_running(lock_name, holder.map(|(pid, _)| pid), link),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn later_launches_reach_the_listener() {
+        let lock_name = format!("instance-test-{}", std::process::id());
+        let (sent, received) = mpsc::channel();
+        Launches::bind(&lock_name).unwrap().spawn(move |link| {
+            let _ = sent.send(link);
+        });
+
+        hand_off(&lock_name, Some("hammeroverlay://parse?text=noon")).unwrap();
+        hand_off(&lock_name, None).unwrap();
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            received.recv_timeout(timeout).unwrap().as_deref(),
+            Some("hammeroverlay://parse?text=noon")
+        );
+        assert_eq!(received.recv_timeout(timeout).unwrap(), None);
+
+        let _ = fs::remove_file(launch_file_path(&lock_name));
+    }
+
+    #[test]
+    fn handoff_with_the_wrong_token_is_ignored() {
+        let lock_name = format!("instance-token-test-{}", std::process::id());
+        let (sent, received) = mpsc::channel();
+        Launches::bind(&lock_name).unwrap().spawn(move |link| {
+            let _ = sent.send(link);
+        });
+        let record = fs::read_to_string(launch_file_path(&lock_name)).unwrap();
+        let (port, _) = record.split_once('\n').unwrap();
+        fs::write(
+            launch_file_path(&lock_name),
+            format!("{port}\nnot-the-token"),
+        )
+        .unwrap();
+
+        // The listener may hang up mid-write, so the send itself can fail.
+        let _ = hand_off(&lock_name, Some("hammeroverlay://parse?text=noon"));
+        assert!(received.recv_timeout(Duration::from_millis(500)).is_err());
+
+        let _ = fs::remove_file(launch_file_path(&lock_name));
+    }
+}
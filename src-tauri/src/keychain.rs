@@ -0,0 +1,237 @@
+//! Secrets in the OS credential store, never in `settings.json`.
+//!
+//! - Windows: Credential Manager, through the WinRT `PasswordVault`.
+//! - macOS: the login keychain, through `security`.
+//! - Linux: the Secret Service (GNOME Keyring, KWallet), through `secret-tool`.
+//!
+//! Secrets reach the helper process on stdin, never in its arguments or
+//! environment, so they do not show up in process listings or inherited
+//! environment blocks.
+
+#[cfg(windows)]
+use crate::CREATE_NO_WINDOW;
+use std::io::Write;
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+use std::process::{Command, Stdio};
+
+const SERVICE: &str = "com.hammer-overlay.app";
+
+/// Which secret an entry holds.
+pub const LLM_API_KEY: &str = "llm-api-key";
+
+/// Run `command`, optionally feeding `input` on stdin, and collect its output.
+fn run(mut command: Command, input: Option<&str>) -> Result<std::process::Output, String> {
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to open the OS credential store: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        if let Some(input) = input {
+            stdin
+                .write_all(input.as_bytes())
+                .map_err(|e| format!("Failed to write to the OS credential store: {e}"))?;
+        }
+    }
+    child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to read the OS credential store: {e}"))
+}
+
+fn stderr_text(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stderr).trim().to_string()
+}
+
+/// Secrets are quoted into `security -i` input, so keep them to one line of
+/// printable characters without quotes or backslashes.
+pub fn validate_secret(secret: &str) -> Result<(), String> {
+    if secret.is_empty() {
+        return Err("Secret must not be empty".to_string());
+    }
+    if secret
+        .chars()
+        .any(|c| c.is_control() || c.is_whitespace() || matches!(c, '"' | '\'' | '\\'))
+    {
+        return Err("Secret must not contain whitespace, quotes or backslashes".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+pub fn set(account: &str, secret: &str) -> Result<(), String> {
+    validate_secret(secret)?;
+    let mut command = Command::new("secret-tool");
+    command.args([
+        "store",
+        "--label=HammerOverlay",
+        "service",
+        SERVICE,
+        "account",
+        account,
+    ]);
+    let output = run(command, Some(secret))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to store secret: {}", stderr_text(&output)))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn get(account: &str) -> Result<Option<String>, String> {
+    let mut command = Command::new("secret-tool");
+    command.args(["lookup", "service", SERVICE, "account", account]);
+    let output = run(command, None)?;
+    let secret = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let error = stderr_text(&output);
+    // `lookup` exits non-zero and prints nothing when no entry matches.
+    if output.status.success() || error.is_empty() {
+        Ok(Some(secret).filter(|secret| !secret.is_empty()))
+    } else {
+        Err(format!("Failed to read secret: {error}"))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn delete(account: &str) -> Result<(), String> {
+    let mut command = Command::new("secret-tool");
+    command.args(["clear", "service", SERVICE, "account", account]);
+    let output = run(command, None)?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to delete secret: {}", stderr_text(&output)))
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn set(account: &str, secret: &str) -> Result<(), String> {
+    validate_secret(secret)?;
+    let script =
+        format!("add-generic-password -U -s \"{SERVICE}\" -a \"{account}\" -w \"{secret}\"\n");
+    let mut command = Command::new("security");
+    command.arg("-i");
+    let output = run(command, Some(&script))?;
+    if output.status.success() && output.stderr.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Failed to store secret: {}", stderr_text(&output)))
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn get(account: &str) -> Result<Option<String>, String> {
+    let mut command = Command::new("security");
+    command.args(["find-generic-password", "-s", SERVICE, "-a", account, "-w"]);
+    let output = run(command, None)?;
+    if output.status.success() {
+        let secret = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(Some(secret).filter(|secret| !secret.is_empty()))
+    } else {
+        // Exit code 44 is errSecItemNotFound.
+        match output.status.code() {
+            Some(44) => Ok(None),
+            _ => Err(format!("Failed to read secret: {}", stderr_text(&output))),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn delete(account: &str) -> Result<(), String> {
+    let mut command = Command::new("security");
+    command.args(["delete-generic-password", "-s", SERVICE, "-a", account]);
+    let output = run(command, None)?;
+    match output.status.code() {
+        Some(0) | Some(44) => Ok(()),
+        _ => Err(format!("Failed to delete secret: {}", stderr_text(&output))),
+    }
+}
+
+#[cfg(windows)]
+const VAULT_PRELUDE: &str = "$null = [Windows.Security.Credentials.PasswordVault, Windows.Security.Credentials, ContentType = WindowsRuntime]; $vault = [Windows.Security.Credentials.PasswordVault]::new();";
+
+/// Run `script` against the vault. A script that needs the secret reads it
+/// with `[Console]::In.ReadLine()`.
+#[cfg(windows)]
+fn vault(script: &str, account: &str, secret: Option<&str>) -> Result<String, String> {
+    let mut command = Command::new("powershell");
+    command
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            &format!("{VAULT_PRELUDE} {script}"),
+        ])
+        .env("HAMMER_VAULT_RESOURCE", SERVICE)
+        .env("HAMMER_VAULT_ACCOUNT", account);
+    let input = secret.map(|secret| format!("{secret}\n"));
+    let output = run(command, input.as_deref())?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(stderr_text(&output))
+    }
+}
+
+#[cfg(windows)]
+pub fn set(account: &str, secret: &str) -> Result<(), String> {
+    validate_secret(secret)?;
+    vault(
+        "$vault.Add([Windows.Security.Credentials.PasswordCredential]::new($env:HAMMER_VAULT_RESOURCE, $env:HAMMER_VAULT_ACCOUNT, [Console]::In.ReadLine()))",
+        account,
+        Some(secret),
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to store secret: {e}"))
+}
+
+#[cfg(windows)]
+pub fn get(account: &str) -> Result<Option<String>, String> {
+    // `Retrieve` throws when nothing matches, so search first.
+    let secret = vault(
+        "$found = $vault.FindAllByResource($env:HAMMER_VAULT_RESOURCE) | Where-Object { $_.UserName -eq $env:HAMMER_VAULT_ACCOUNT } | Select-Object -First 1; if ($found) { $found.RetrievePassword(); $found.Password }",
+        account,
+        None,
+    );
+    match secret {
+        Ok(secret) => Ok(Some(secret).filter(|secret| !secret.is_empty())),
+        // `FindAllByResource` also throws when the resource has no entries.
+        Err(e) if e.contains("0x80070490") => Ok(None),
+        Err(e) => Err(format!("Failed to read secret: {e}")),
+    }
+}
+
+#[cfg(windows)]
+pub fn delete(account: &str) -> Result<(), String> {
+    match vault(
+        "$vault.Remove($vault.Retrieve($env:HAMMER_VAULT_RESOURCE, $env:HAMMER_VAULT_ACCOUNT))",
+        account,
+        None,
+    ) {
+        Ok(_) => Ok(()),
+        Err(e) if e.contains("0x80070490") => Ok(()),
+        Err(e) => Err(format!("Failed to delete secret: {e}")),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+const UNSUPPORTED: &str = "No OS credential store is supported on this platform";
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn set(_account: &str, _secret: &str) -> Result<(), String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn get(_account: &str) -> Result<Option<String>, String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn delete(_account: &str) -> Result<(), String> {
+    Err(UNSUPPORTED.to_string())
+}
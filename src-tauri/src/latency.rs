@@ -0,0 +1,46 @@
+//! Rolling window of parse timings, kept in memory only.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Samples beyond this are dropped oldest-first.
+pub const WINDOW_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    pub count: usize,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct LatencyWindow {
+    samples: VecDeque<u64>,
+}
+
+impl LatencyWindow {
+    pub fn record(&mut self, elapsed_ms: u64) {
+        if self.samples.len() == WINDOW_SIZE {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(elapsed_ms);
+    }
+
+    /// Nearest-rank percentiles; all zeros before the first sample.
+    pub fn stats(&self) -> LatencyStats {
+        let mut sorted = self.samples.iter().copied().collect::<Vec<_>>();
+        sorted.sort_unstable();
+        let Some(&max_ms) = sorted.last() else {
+            return LatencyStats::default();
+        };
+        let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        LatencyStats {
+            count: sorted.len(),
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+            max_ms,
+        }
+    }
+}
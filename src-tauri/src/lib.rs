@@ -1,34 +1,119 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use serde::{Deserialize, Serialize};
-use single_instance::SingleInstance;
 use std::{
+    collections::HashMap,
     fs,
     io::{Read, Write},
     net::{SocketAddr, TcpStream},
     path::{Path, PathBuf},
     process::{Child, Command, Stdio},
-    sync::Mutex,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
     time::Duration,
 };
 use tauri::{
-    menu::{MenuBuilder, MenuItemBuilder},
-    tray::TrayIconBuilder,
+    menu::{CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder},
+    tray::{TrayIcon, TrayIconBuilder},
     AppHandle, Emitter, Manager,
 };
 use tauri_plugin_autostart::ManagerExt as AutostartExt;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_deep_link::DeepLinkExt;
 use tauri_plugin_global_shortcut::GlobalShortcutExt;
+use tauri_plugin_opener::OpenerExt;
 use tauri_plugin_store::StoreBuilder;
 use tauri_plugin_updater::UpdaterExt;
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
+mod backup;
+mod db;
+mod deeplink;
+mod deterministic;
+mod discord;
+mod error;
+mod export;
+mod instance;
+mod keychain;
+mod latency;
+mod llm;
+mod locales;
+mod log_filter;
+mod notify;
+mod parse_cache;
+mod tasks;
+mod timezone;
+mod update_cache;
+
+use error::AppError;
+
 const TIME_PARSER_PORT: u16 = 8857;
 const LOCAL_SLM_DEFAULT_ENDPOINT_BASE_URL: &str = "http://127.0.0.1:8765/v1";
 const LOCAL_SLM_DEFAULT_MODEL: &str = "qwen-temporal-ir-qwen35-bf16-chat-time-range-2687";
 const LOCAL_SLM_DEFAULT_ADAPTER_PATH: &str =
     "ml/temporal-ir/outputs/qwen-temporal-ir-qwen35-08b-bf16-chat-time-range-2687-lora";
 const LOCAL_SLM_DEFAULT_STARTUP_TIMEOUT_SECONDS: u64 = 360;
+const LOCAL_SLM_MIN_STARTUP_TIMEOUT_SECONDS: u64 = 30;
+const LOCAL_SLM_MAX_STARTUP_TIMEOUT_SECONDS: u64 = 900;
+
+const TRAY_ID: &str = "hammer-overlay-tray";
+const TRAY_DEFAULT_TOOLTIP: &str = "HammerOverlay - Discord Timestamp Converter";
+const TRAY_RECENT_CONVERSIONS: u32 = 5;
+const TRAY_RECENT_MENU_PREFIX: &str = "copy_recent:";
+const TRAY_RECENT_LABEL_CHARS: usize = 32;
+
+const CLIPBOARD_POLL_INTERVAL: Duration = Duration::from_millis(400);
+/// A new clipboard value must hold this long before `clipboard-changed` fires.
+const CLIPBOARD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+const HOTKEY_WATCHDOG_INTERVAL: Duration = Duration::from_secs(30);
+/// A watchdog tick that lands this much later than scheduled means the
+/// machine was asleep; shortcuts are re-registered even if they look intact.
+const HOTKEY_RESUME_GAP: Duration = Duration::from_secs(30);
+
+const WINDOW_OPACITY_MIN: f64 = 0.3;
+const DEFAULT_ACCENT_COLOR: &str = "#5865F2";
+/// Matches `minWidth`/`minHeight` in tauri.conf.json.
+const WINDOW_MIN_WIDTH: f64 = 400.0;
+const WINDOW_MIN_HEIGHT: f64 = 100.0;
+const WINDOW_MAX_SIDE: f64 = 4096.0;
+/// Resizes settle this long before an unlocked window's size is saved.
+const WINDOW_SIZE_SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+const CONNECTIVITY_PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+/// `is_online` reuses a probe result for this long.
+const CONNECTIVITY_CACHE_TTL: Duration = Duration::from_secs(10);
+/// Showing the overlay warms the LLM again only after this long.
+const LLM_WARM_TTL: Duration = Duration::from_secs(10 * 60);
+const AUTO_DISMISS_MAX_MS: u32 = 60_000;
+const FULLSCREEN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// A show queued behind a fullscreen app is dropped after this long.
+const FULLSCREEN_WAIT_MAX: Duration = Duration::from_secs(30 * 60);
+/// `update-stage` progress granularity when the download size is unknown.
+const UPDATE_PROGRESS_STEP_BYTES: u64 = 1024 * 1024;
+const MIN_VISIBLE_MAX_MS: u32 = 10_000;
+const SHORTCUT_REGISTER_DELAY_MAX_MS: u32 = 30_000;
+const ROUND_TO_MINUTES_MAX: u32 = 24 * 60;
+const DEFAULT_LLM_TIMEOUT_MS: u32 = 8_000;
+const LLM_TIMEOUT_RANGE_MS: std::ops::RangeInclusive<u32> = 500..=120_000;
+/// `factory_reset` only runs when handed exactly this token.
+const FACTORY_RESET_CONFIRMATION: &str = "RESET";
+/// The frontend's tauri-plugin-sql database, as preloaded in tauri.conf.json.
+const HISTORY_DB_FILE: &str = "hammer_overlay.db";
+const NOTIFICATION_TITLE: &str = "HammerOverlay";
+/// Opened by `open_help` unless the `help_url` setting overrides it.
+const DEFAULT_HELP_URL: &str = "https://github.com/BASIC-BIT/discord-time-app#readme";
+/// Gap between an anchored overlay and the work-area edge, in logical pixels.
+const WINDOW_ANCHOR_MARGIN: f64 = 12.0;
+const WINDOW_ANCHORS: [&str; 5] = [
+    "center",
+    "top-left",
+    "top-right",
+    "bottom-left",
+    "bottom-right",
+];
 
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
@@ -89,6 +174,165 @@ pub struct NativeTimeParserResponse {
     pub body: serde_json::Value,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedTime {
+    pub epoch: i64,
+    pub method: String, // "deterministic", "llm"
+    pub needs_clarification: bool,
+    pub candidates: Vec<ParsedTime>,
+    /// The moment restated in the user's zone and clock style, flagging a
+    /// defaulted time as assumed.
+    pub canonical_text: String,
+    /// Wall time `parse_time` spent on this input; unset on candidates.
+    pub elapsed_ms: Option<u64>,
+    /// Served from `ParseCacheState` rather than parsed again.
+    pub from_cache: bool,
+    /// The zone the input was read in, with its offset at `epoch`; unset on
+    /// candidates.
+    pub timezone: Option<timezone::TimezoneInfo>,
+    /// The pinned `debug_fixed_now` this parse used as now, so the overlay
+    /// can flag it; unset on candidates, with the real clock and when the
+    /// caller passed its own `reference_now`.
+    pub debug_clock: Option<i64>,
+    /// Some reading is "now" or an offset from it, so it goes stale within
+    /// the cache's minute and must not be cached.
+    #[serde(skip)]
+    relative: bool,
+}
+
+impl ParsedTime {
+    fn from_interpretation(
+        interpretation: &deterministic::Interpretation,
+        zone: chrono_tz::Tz,
+        use_24h: bool,
+    ) -> Result<Self, String> {
+        let needs_clarification = interpretation.needs_clarification();
+        let resolution_to_parsed = |resolution: &deterministic::Resolution| {
+            Ok::<_, String>(ParsedTime {
+                epoch: resolution.epoch,
+                method: "deterministic".to_string(),
+                needs_clarification: false,
+                candidates: Vec::new(),
+                canonical_text: timezone::canonical_text(
+                    resolution.epoch,
+                    zone,
+                    use_24h,
+                    resolution.had_explicit_time,
+                )?,
+                elapsed_ms: None,
+                from_cache: false,
+                timezone: None,
+                debug_clock: None,
+                relative: resolution.relative,
+            })
+        };
+        Ok(ParsedTime {
+            candidates: if needs_clarification {
+                interpretation
+                    .candidates
+                    .iter()
+                    .map(resolution_to_parsed)
+                    .collect::<Result<_, _>>()?
+            } else {
+                Vec::new()
+            },
+            needs_clarification,
+            relative: interpretation
+                .candidates
+                .iter()
+                .any(|resolution| resolution.relative),
+            ..resolution_to_parsed(&interpretation.best())?
+        })
+    }
+}
+
+/// One line of a batch parse; exactly one of `parsed` and `error` is set.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchParseItem {
+    pub input: String,
+    pub parsed: Option<ParsedTime>,
+    pub error: Option<String>,
+}
+
+/// Support snapshot returned by `get_diagnostics`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub updater_available: bool,
+    pub autostart_enabled: bool,
+    pub store_path: Option<String>,
+    pub registered_hotkey: Option<String>,
+    pub db_reachable: bool,
+    pub instance_lock: String,
+    /// The OS zone as parses without an explicit zone see it.
+    pub timezone: Option<timezone::TimezoneInfo>,
+    pub fullscreen_active: bool,
+}
+
+/// Outcome of one `self_test` stage.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestStage {
+    pub name: String,
+    pub passed: bool,
+    pub elapsed_ms: u64,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub stages: Vec<SelfTestStage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeInfo {
+    pub uptime_seconds: u64,
+    /// From entering `run` to the end of `setup`; unset while still starting.
+    pub setup_ms: Option<u64>,
+}
+
+/// A display as reported by `list_monitors`. Position and size are in
+/// physical pixels.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub position: tauri::PhysicalPosition<i32>,
+    pub size: tauri::PhysicalSize<u32>,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub field: String,
+    pub message: String,
+}
+
+/// Result of a dry-run update check from `test_updater`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdaterDiagnostics {
+    pub reachable: bool,
+    pub http_status: Option<u16>,
+    pub latency_ms: u64,
+    pub update_available: bool,
+    pub latest_version: Option<String>,
+    /// Whether the release manifest carries a signature for this platform.
+    /// The signature itself is only verified against the downloaded bytes.
+    pub signature_present: bool,
+    pub error: Option<String>,
+}
+
 pub struct TimeParserServiceState {
     child: Mutex<Option<Child>>,
     base_url: String,
@@ -98,6 +342,361 @@ pub struct LocalSlmServiceState {
     starting: Mutex<bool>,
 }
 
+pub struct TrayState {
+    tray: Mutex<Option<TrayIcon>>,
+    icon_variant_override: Mutex<Option<String>>,
+}
+
+impl TrayState {
+    fn new() -> Self {
+        Self {
+            tray: Mutex::new(None),
+            icon_variant_override: Mutex::new(None),
+        }
+    }
+}
+
+pub struct StatsDbState {
+    connection: Mutex<Option<rusqlite::Connection>>,
+}
+
+/// Bumped on every copy and every show; a scheduled hide only fires if the
+/// generation it captured is still current. `shown_at` backs
+/// `min_visible_ms`.
+pub struct AutoDismissState {
+    generation: AtomicU64,
+    shown_at: Mutex<Option<std::time::Instant>>,
+}
+
+impl AutoDismissState {
+    fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            shown_at: Mutex::new(None),
+        }
+    }
+
+    fn bump(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+/// When `run` was entered and how long `setup` took, for `get_runtime_info`.
+pub struct RuntimeState {
+    started: std::time::Instant,
+    setup_ms: Mutex<Option<u64>>,
+}
+
+impl RuntimeState {
+    fn new(started: std::time::Instant) -> Self {
+        Self {
+            started,
+            setup_ms: Mutex::new(None),
+        }
+    }
+}
+
+/// Debounces saving the size of an unlocked window while it is dragged.
+pub struct WindowSizeState {
+    generation: AtomicU64,
+}
+
+impl WindowSizeState {
+    fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    fn bump(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+/// Set by `suspend_auto_close` so the UI can keep the overlay open through a
+/// picker or dialog; cleared on every show. Never persisted.
+pub struct AutoCloseState {
+    suspended: AtomicBool,
+}
+
+impl AutoCloseState {
+    fn new() -> Self {
+        Self {
+            suspended: AtomicBool::new(false),
+        }
+    }
+}
+
+/// `install_update` progress that outlives the command: whether one is
+/// running, and the version installed but not yet running.
+pub struct UpdateState {
+    installing: AtomicBool,
+    pending_version: Mutex<Option<String>>,
+}
+
+impl UpdateState {
+    fn new() -> Self {
+        Self {
+            installing: AtomicBool::new(false),
+            pending_version: Mutex::new(None),
+        }
+    }
+}
+
+/// Set while a hotkey show waits for an exclusive-fullscreen app to exit, so
+/// repeated presses don't start more waits.
+pub struct FullscreenWaitState {
+    pending: AtomicBool,
+}
+
+impl FullscreenWaitState {
+    fn new() -> Self {
+        Self {
+            pending: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Bounds of the window that had focus when the hotkey fired, taken by the
+/// next `position_main_window` when `follow_active_window` is on.
+pub struct ForegroundWindowState {
+    bounds: Mutex<Option<tauri::PhysicalRect<i32, u32>>>,
+}
+
+impl ForegroundWindowState {
+    fn new() -> Self {
+        Self {
+            bounds: Mutex::new(None),
+        }
+    }
+}
+
+/// Background threads and tasks, for `list_active_tasks`.
+pub struct TaskState {
+    registry: Mutex<tasks::TaskRegistry>,
+}
+
+impl TaskState {
+    fn new() -> Self {
+        Self {
+            registry: Mutex::new(tasks::TaskRegistry::default()),
+        }
+    }
+}
+
+/// Keeps a background task listed until it is dropped, which also covers a
+/// thread that returns early or an async task that is aborted.
+struct TaskGuard {
+    app: AppHandle,
+    id: u64,
+}
+
+impl TaskGuard {
+    fn tick(&self) {
+        if let Ok(mut registry) = self.app.state::<TaskState>().registry.lock() {
+            registry.tick(self.id, chrono::Utc::now().timestamp_millis());
+        }
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        if let Ok(mut registry) = self.app.state::<TaskState>().registry.lock() {
+            registry.finish(self.id);
+        }
+    }
+}
+
+/// Register a background task; `interval` marks it periodic, expected to
+/// call `tick` that often.
+fn track_task(app: &AppHandle, name: &'static str, interval: Option<Duration>) -> TaskGuard {
+    let id = app
+        .state::<TaskState>()
+        .registry
+        .lock()
+        .map(|mut registry| registry.start(name, interval, chrono::Utc::now().timestamp_millis()))
+        .unwrap_or_default();
+    TaskGuard {
+        app: app.clone(),
+        id,
+    }
+}
+
+/// The last connectivity probe: when it ran, which host, and the answer.
+pub struct ConnectivityState {
+    last_probe: Mutex<Option<(std::time::Instant, String, bool)>>,
+}
+
+impl ConnectivityState {
+    fn new() -> Self {
+        Self {
+            last_probe: Mutex::new(None),
+        }
+    }
+}
+
+/// The active debug clock and how the tray shows it, so tooltip refreshes
+/// don't read the settings store.
+pub struct DebugClockState {
+    clock: Mutex<Option<(i64, String)>>,
+}
+
+impl DebugClockState {
+    fn new() -> Self {
+        Self {
+            clock: Mutex::new(None),
+        }
+    }
+}
+
+/// Show-window aliases that failed to register, usually because another app
+/// holds them. The watchdog leaves these out, or it would re-register every
+/// hotkey each time it runs.
+pub struct HotkeyAliasState {
+    failed: Mutex<Vec<String>>,
+}
+
+impl HotkeyAliasState {
+    fn new() -> Self {
+        Self {
+            failed: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+/// When `warm_llm` last succeeded and for which provider config.
+pub struct LlmWarmState {
+    last_warmed: Mutex<Option<(std::time::Instant, llm::LlmConfig)>>,
+}
+
+impl LlmWarmState {
+    fn new() -> Self {
+        Self {
+            last_warmed: Mutex::new(None),
+        }
+    }
+}
+
+/// Only the monitor holding the latest generation keeps polling. `own_write`
+/// is the last text the app copied itself, which is not a user change.
+pub struct ClipboardMonitorState {
+    generation: AtomicU64,
+    own_write: Mutex<Option<String>>,
+}
+
+impl ClipboardMonitorState {
+    fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            own_write: Mutex::new(None),
+        }
+    }
+}
+
+/// The last deep-link parse, held until the frontend asks for it so a link
+/// that launched the app is not lost before the webview is listening.
+pub struct DeepLinkState {
+    pending: Mutex<Option<BatchParseItem>>,
+    pending_permalink: Mutex<Option<deeplink::Permalink>>,
+}
+
+impl DeepLinkState {
+    fn new() -> Self {
+        Self {
+            pending: Mutex::new(None),
+            pending_permalink: Mutex::new(None),
+        }
+    }
+}
+
+/// Recent `parse_time` results; cleared whenever settings are saved or
+/// reloaded, since most of them change what an input parses to. Relative
+/// readings are never stored.
+pub struct ParseCacheState {
+    cache: Mutex<parse_cache::ParseCache<ParsedTime>>,
+}
+
+impl ParseCacheState {
+    fn new() -> Self {
+        Self {
+            cache: Mutex::new(parse_cache::ParseCache::default()),
+        }
+    }
+}
+
+/// Abort handles for `parse_time` calls made with a `request_id`.
+pub struct ParseCancelState {
+    in_flight: Mutex<HashMap<String, tokio::task::AbortHandle>>,
+}
+
+impl ParseCancelState {
+    fn new() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Recent `parse_time` timings for `get_parse_latency_stats`.
+pub struct ParseLatencyState {
+    window: Mutex<latency::LatencyWindow>,
+}
+
+impl ParseLatencyState {
+    fn new() -> Self {
+        Self {
+            window: Mutex::new(latency::LatencyWindow::default()),
+        }
+    }
+}
+
+/// Serializes settings writes; see `lock_settings`.
+pub struct SettingsWriteState {
+    lock: Mutex<()>,
+}
+
+impl SettingsWriteState {
+    fn new() -> Self {
+        Self {
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+/// The levels the log plugin's filter reads; shared with the closure handed
+/// to it in `run`.
+pub struct LogFilterState {
+    filters: Arc<RwLock<log_filter::LogFilters>>,
+}
+
+impl LogFilterState {
+    fn new() -> Self {
+        Self {
+            filters: Arc::new(RwLock::new(log_filter::LogFilters::default())),
+        }
+    }
+}
+
+pub struct ErrorLogState {
+    log: Mutex<error::ErrorLog>,
+}
+
+impl ErrorLogState {
+    fn new() -> Self {
+        Self {
+            log: Mutex::new(error::ErrorLog::default()),
+        }
+    }
+}
+
+impl StatsDbState {
+    fn new() -> Self {
+        Self {
+            connection: Mutex::new(None),
+        }
+    }
+}
+
 impl TimeParserServiceState {
     fn new() -> Self {
         Self {
@@ -123,11 +722,58 @@ impl Drop for TimeParserServiceState {
 pub struct AppSettings {
     pub auto_start: bool,
     pub global_hotkey: String,
+    pub copy_last_hotkey: String, // empty disables it
+    /// Extra accelerators that show the window, like `global_hotkey`.
+    pub show_hotkey_aliases: Vec<String>,
+    pub hotkey_enabled: bool,
     pub auto_close_on_focus_loss: bool,
+    /// Auto-hides wait until the window has been up this long.
+    pub min_visible_ms: u32,
     pub auto_load_clipboard: bool,
     pub use_llm_parsing: bool,
+    pub llm: Option<llm::LlmConfig>,
+    pub llm_timeout_ms: u32,
     pub deterministic_preflight: bool,
     pub theme: String, // "dark", "light", "system"
+    pub default_format: String,
+    pub enabled_formats: Vec<String>, // shown in pickers, cycling and the tray
+    pub window_opacity: f64,
+    pub lock_window_size: bool,
+    pub window_size: Option<(f64, f64)>, // logical (width, height); None fits content
+    pub accent_color: String,            // "#RRGGBB"
+    pub auto_dismiss_after_copy_ms: u32,
+    pub last_view: String,     // "converter", "settings"
+    pub window_anchor: String, // "center", "top-left", "top-right", "bottom-left", "bottom-right"
+    pub follow_active_window: bool,
+    /// Hold hotkey shows until an exclusive-fullscreen app exits.
+    pub suppress_over_fullscreen: bool,
+    pub excluded_monitor: Option<String>,
+    pub focus_input_on_show: bool,
+    pub quiet_hours: Option<(String, String)>, // ("HH:MM", "HH:MM") local time
+    pub week_starts_on: String,                // "monday" ... "sunday"
+    pub this_weekday_includes_today: bool,
+    pub bare_time_policy: String,           // "next", "today", "ask"
+    pub default_time_for_date_only: String, // "HH:MM"
+    pub round_to_minutes: u32,              // 0 disables rounding
+    pub round_relative_times: bool,
+    /// Whether a range end earlier than its start ("10pm to 2am") means the
+    /// next day rather than an error.
+    pub range_end_rolls_over: bool,
+    pub shortcut_register_delay_ms: u32,
+    pub show_tray_icon: bool,
+    pub close_button_behavior: String, // "hide", "quit"
+    pub time_display_24h: bool,
+    pub parse_locale: String,
+    pub connectivity_probe_host: String, // "host[:port]"; empty uses the update server
+    pub help_url: String,                // empty uses DEFAULT_HELP_URL
+    pub confirm_on_quit: bool,
+    pub notify_on_copy: bool,
+    /// Copy committed parses in `default_format` without a click.
+    pub auto_copy_on_parse: bool,
+    pub has_completed_onboarding: bool,
+    /// The last update version a notification announced, so each new version
+    /// is announced once rather than on every check.
+    pub last_notified_update: Option<String>,
     pub local_slm_enabled: bool,
     pub local_slm_auto_start: bool,
     pub local_slm_prewarm: bool,
@@ -136,6 +782,20 @@ pub struct AppSettings {
     pub local_slm_launcher_path: String,
     pub local_slm_adapter_path: String,
     pub local_slm_startup_timeout_seconds: u64,
+    /// Debug only: pin "now" to this epoch for demos and bug repros. Only
+    /// debug builds, or builds run with HAMMEROVERLAY_ALLOW_DEBUG_CLOCK=1,
+    /// honor it; the tray tooltip and the `debug-clock` event say when it is
+    /// active.
+    pub debug_fixed_now: Option<i64>,
+    /// Log level per target, e.g. `{"hammer_overlay_lib": "debug",
+    /// "tauri_plugin_updater": "warn", "*": "info"}`. A target covers its
+    /// submodules and `*` covers everything unlisted. This crate logs under
+    /// `hammer_overlay_lib` (this file), `hammer_overlay_lib::llm` and
+    /// `hammer_overlay_lib::instance`; plugins log under their crate names,
+    /// such as
+    /// `tauri_plugin_updater`, `tauri_plugin_global_shortcut`,
+    /// `tauri_plugin_store` and `tauri_plugin_sql`.
+    pub log_filters: HashMap<String, String>,
 }
 
 impl Default for AppSettings {
@@ -143,11 +803,50 @@ impl Default for AppSettings {
         Self {
             auto_start: false,
             global_hotkey: "ctrl+shift+h".to_string(),
+            copy_last_hotkey: String::new(),
+            show_hotkey_aliases: Vec::new(),
+            hotkey_enabled: true,
             auto_close_on_focus_loss: false,
+            min_visible_ms: 0,
             auto_load_clipboard: true,
             use_llm_parsing: true,
+            llm: None,
+            llm_timeout_ms: DEFAULT_LLM_TIMEOUT_MS,
             deterministic_preflight: false,
             theme: "dark".to_string(),
+            default_format: "F".to_string(),
+            enabled_formats: discord::FORMAT_LETTERS.map(str::to_string).to_vec(),
+            window_opacity: 1.0,
+            lock_window_size: true,
+            window_size: None,
+            accent_color: DEFAULT_ACCENT_COLOR.to_string(),
+            auto_dismiss_after_copy_ms: 0,
+            last_view: "converter".to_string(),
+            window_anchor: "center".to_string(),
+            follow_active_window: false,
+            suppress_over_fullscreen: false,
+            excluded_monitor: None,
+            focus_input_on_show: true,
+            quiet_hours: None,
+            week_starts_on: "monday".to_string(),
+            this_weekday_includes_today: true,
+            bare_time_policy: "next".to_string(),
+            default_time_for_date_only: "09:00".to_string(),
+            range_end_rolls_over: true,
+            round_to_minutes: 0,
+            round_relative_times: false,
+            shortcut_register_delay_ms: 0,
+            show_tray_icon: true,
+            close_button_behavior: "hide".to_string(),
+            time_display_24h: timezone::locale_prefers_24h(),
+            parse_locale: locales::ENGLISH.code.to_string(),
+            connectivity_probe_host: String::new(),
+            help_url: String::new(),
+            confirm_on_quit: false,
+            notify_on_copy: false,
+            auto_copy_on_parse: false,
+            has_completed_onboarding: false,
+            last_notified_update: None,
             local_slm_enabled: false,
             local_slm_auto_start: false,
             local_slm_prewarm: true,
@@ -156,6 +855,8 @@ impl Default for AppSettings {
             local_slm_launcher_path: String::new(),
             local_slm_adapter_path: LOCAL_SLM_DEFAULT_ADAPTER_PATH.to_string(),
             local_slm_startup_timeout_seconds: LOCAL_SLM_DEFAULT_STARTUP_TIMEOUT_SECONDS,
+            debug_fixed_now: None,
+            log_filters: HashMap::new(),
         }
     }
 }
@@ -406,7 +1107,10 @@ fn local_slm_startup_timeout(settings: &AppSettings) -> u64 {
     if timeout == 0 {
         LOCAL_SLM_DEFAULT_STARTUP_TIMEOUT_SECONDS
     } else {
-        timeout.clamp(30, 900)
+        timeout.clamp(
+            LOCAL_SLM_MIN_STARTUP_TIMEOUT_SECONDS,
+            LOCAL_SLM_MAX_STARTUP_TIMEOUT_SECONDS,
+        )
     }
 }
 
@@ -896,6 +1600,7 @@ async fn start_local_slm_runtime(app: AppHandle) -> Result<LocalSlmStatus, Strin
 fn trigger_local_slm_start(app: &AppHandle) {
     let app = app.clone();
     tauri::async_runtime::spawn(async move {
+        let _task = track_task(&app, "local-slm-start", None);
         match start_local_slm_runtime(app).await {
             Ok(status) => log::info!("Local SLM startup check completed: {}", status.state),
             Err(e) => log::warn!("Local SLM startup check failed: {e}"),
@@ -985,7 +1690,7 @@ fn local_time_parser_request(
     let body = body.unwrap_or("");
     let request = format!(
         "{method} {path} HTTP/1.1\r\nHost: localhost:{TIME_PARSER_PORT}\r\nConnection: close\r\nContent-Type: application/json\r\nx-api-key: {api_key}\r\nx-api-version: 1\r\nContent-Length: {}\r\n\r\n{body}",
-        body.as_bytes().len()
+        body.len()
     );
 
     stream
@@ -1225,7 +1930,10 @@ fn start_time_parser_service(app: &AppHandle) {
     let mut child_slot = match state.child.lock() {
         Ok(child_slot) => child_slot,
         Err(e) => {
-            log::error!("Failed to lock parser service state: {e}");
+            report_error(
+                app,
+                AppError::Internal(format!("Failed to lock parser service state: {e}")),
+            );
             return;
         }
     };
@@ -1266,6 +1974,9 @@ fn start_time_parser_service(app: &AppHandle) {
 
     apply_optional_api_env(&mut command);
     apply_local_slm_api_env(&mut command, &settings);
+    if let Some(config) = settings.llm.as_ref().filter(|_| settings.use_llm_parsing) {
+        llm::apply_env(&mut command, config, llm_api_key(config).as_deref());
+    }
 
     if let Some(db_path) = time_parser_db_path(app) {
         command.env("DB_PATH", db_path);
@@ -1280,6 +1991,7 @@ fn start_time_parser_service(app: &AppHandle) {
             *child_slot = Some(child);
             let app_handle = app.clone();
             std::thread::spawn(move || {
+                let _task = track_task(&app_handle, "time-parser-health-wait", None);
                 for _ in 0..40 {
                     if time_parser_health_check() {
                         log::info!("Local time parser service became healthy");
@@ -1297,24 +2009,134 @@ fn start_time_parser_service(app: &AppHandle) {
     }
 }
 
+/// Make one cheap call to the configured LLM endpoint. Settings changes
+/// reach the parser service itself on `restart_time_parser_service`.
 #[tauri::command]
-async fn get_time_parser_config(app: AppHandle) -> Result<TimeParserServiceConfig, String> {
-    let state = app.state::<TimeParserServiceState>();
-    let mut supervised = parser_child_is_running(&state)?;
-    let mut available = time_parser_health_check_blocking().await?;
+async fn test_llm_connection(app: AppHandle) -> Result<bool, AppError> {
+    let settings = load_app_settings(&app)?;
+    let config = settings
+        .llm
+        .ok_or_else(|| AppError::Invalid("No LLM provider is configured".to_string()))?;
+    config.validate().map_err(AppError::Invalid)?;
+    let api_key = llm_api_key_blocking(&config)
+        .await
+        .map_err(AppError::Internal)?;
+    llm::test_connection(&config, api_key.as_deref())
+        .await
+        .map_err(AppError::Internal)
+}
 
-    if !available && !supervised && !supervised_time_parser_disabled() {
+/// Start the parser service and open a connection to the LLM endpoint ahead
+/// of the first parse that needs them. Succeeds without doing anything when
+/// LLM parsing is off or no provider is configured, or when the same config
+/// was warmed within `LLM_WARM_TTL`.
+#[tauri::command]
+async fn warm_llm(app: AppHandle) -> Result<(), AppError> {
+    let settings = load_app_settings(&app)?;
+    let Some(config) = settings.llm.filter(|_| settings.use_llm_parsing) else {
+        return Ok(());
+    };
+    let state = app.state::<LlmWarmState>();
+    let recently_warmed = state.last_warmed.lock().is_ok_and(|last| {
+        last.as_ref()
+            .is_some_and(|(at, warmed)| *warmed == config && at.elapsed() < LLM_WARM_TTL)
+    });
+    if recently_warmed {
+        return Ok(());
+    }
+    config.validate().map_err(AppError::Invalid)?;
+    if !time_parser_health_check_blocking()
+        .await
+        .map_err(AppError::Internal)?
+        && !supervised_time_parser_disabled()
+    {
         start_time_parser_service(&app);
-        supervised = parser_child_is_running(&state)?;
-        available = time_parser_health_check_blocking().await?;
     }
-
-    let api_key = time_parser_api_key(&app, false)?;
-
-    let message = if available {
-        "Local time parser service is available.".to_string()
-    } else if supervised {
-        "The local time parser service is still starting. Try again in a moment.".to_string()
+    let api_key = llm_api_key_blocking(&config)
+        .await
+        .map_err(AppError::Internal)?;
+    if !llm::test_connection(&config, api_key.as_deref())
+        .await
+        .map_err(AppError::Internal)?
+    {
+        return Err(AppError::Internal(
+            "LLM endpoint rejected the warm-up request".to_string(),
+        ));
+    }
+    if let Ok(mut last) = state.last_warmed.lock() {
+        *last = Some((std::time::Instant::now(), config));
+    }
+    Ok(())
+}
+
+async fn llm_api_key_blocking(config: &llm::LlmConfig) -> Result<Option<String>, String> {
+    let config = config.clone();
+    tauri::async_runtime::spawn_blocking(move || llm_api_key(&config))
+        .await
+        .map_err(|e| format!("Failed to read LLM key: {e}"))
+}
+
+/// The keychain entry from `set_llm_key`, else the `api_key_env` variable.
+fn llm_api_key(config: &llm::LlmConfig) -> Option<String> {
+    match keychain::get(keychain::LLM_API_KEY) {
+        Ok(Some(key)) => return Some(key),
+        Ok(None) => {}
+        Err(e) => log::warn!("{e}"),
+    }
+    config.env_api_key()
+}
+
+/// Save the LLM API key to the OS keychain. Restart the parser service for
+/// it to take effect.
+#[tauri::command]
+async fn set_llm_key(key: String) -> Result<(), AppError> {
+    let key = key.trim().to_string();
+    keychain::validate_secret(&key).map_err(AppError::Invalid)?;
+    tauri::async_runtime::spawn_blocking(move || keychain::set(keychain::LLM_API_KEY, &key))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to store LLM key: {e}")))?
+        .map_err(AppError::Internal)?;
+    log::info!("Stored LLM API key in the OS keychain");
+    Ok(())
+}
+
+#[tauri::command]
+async fn has_llm_key() -> Result<bool, AppError> {
+    tauri::async_runtime::spawn_blocking(|| keychain::get(keychain::LLM_API_KEY))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read LLM key: {e}")))?
+        .map(|key| key.is_some())
+        .map_err(AppError::Internal)
+}
+
+#[tauri::command]
+async fn clear_llm_key() -> Result<(), AppError> {
+    tauri::async_runtime::spawn_blocking(|| keychain::delete(keychain::LLM_API_KEY))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to delete LLM key: {e}")))?
+        .map_err(AppError::Internal)?;
+    log::info!("Removed LLM API key from the OS keychain");
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_time_parser_config(app: AppHandle) -> Result<TimeParserServiceConfig, AppError> {
+    let state = app.state::<TimeParserServiceState>();
+    let mut supervised = parser_child_is_running(&state)?;
+    let mut available = time_parser_health_check_blocking().await?;
+
+    if !available && !supervised && !supervised_time_parser_disabled() {
+        start_time_parser_service(&app);
+        supervised = parser_child_is_running(&state)?;
+        available = time_parser_health_check_blocking().await?;
+    }
+
+    let api_key = time_parser_api_key(&app, false)?;
+
+    let message = if available {
+        "Local time parser service is available.".to_string()
+    } else if supervised {
+        "The local time parser service is still starting. Try again in a moment.".to_string()
     } else {
         "HammerOverlay could not start the local time parser service. Local fallback parsing remains available.".to_string()
     };
@@ -1332,15 +2154,27 @@ async fn get_time_parser_config(app: AppHandle) -> Result<TimeParserServiceConfi
 async fn parse_time_with_local_service(
     app: AppHandle,
     request: NativeTimeParserRequest,
-) -> Result<NativeTimeParserResponse, String> {
+) -> Result<NativeTimeParserResponse, AppError> {
+    local_service_parse(&app, request, Duration::from_secs(60)).await
+}
+
+/// POST `request` to the parser service, starting it first if needed.
+/// `timeout` bounds each HTTP attempt.
+async fn local_service_parse(
+    app: &AppHandle,
+    request: NativeTimeParserRequest,
+    timeout: Duration,
+) -> Result<NativeTimeParserResponse, AppError> {
     if !time_parser_health_check_blocking().await? && !supervised_time_parser_disabled() {
-        start_time_parser_service(&app);
+        start_time_parser_service(app);
         if !wait_for_time_parser_service_blocking(Duration::from_secs(8)).await? {
-            return Err("The local time parser service is still starting.".to_string());
+            return Err(AppError::Internal(
+                "The local time parser service is still starting.".to_string(),
+            ));
         }
     }
 
-    let api_keys = time_parser_api_key_candidates(&app)?;
+    let api_keys = time_parser_api_key_candidates(app)?;
     let mut body = serde_json::Map::new();
     body.insert("text".to_string(), serde_json::Value::String(request.text));
     body.insert("tz".to_string(), serde_json::Value::String(request.tz));
@@ -1360,7 +2194,7 @@ async fn parse_time_with_local_service(
             "/parse".to_string(),
             api_key,
             Some(body_text.clone()),
-            Duration::from_secs(60),
+            timeout,
         )
         .await?;
         if response.status != 401 {
@@ -1369,7 +2203,559 @@ async fn parse_time_with_local_service(
         last_response = Some(response);
     }
 
-    last_response.ok_or_else(|| "No parser API key candidates were available.".to_string())
+    last_response.ok_or_else(|| {
+        AppError::NotFound("No parser API key candidates were available.".to_string())
+    })
+}
+
+async fn llm_parse_epoch(
+    app: &AppHandle,
+    input: &str,
+    zone: chrono_tz::Tz,
+    reference: chrono::DateTime<chrono::Utc>,
+    timeout: Duration,
+) -> Result<i64, String> {
+    let response = local_service_parse(
+        app,
+        NativeTimeParserRequest {
+            text: input.to_string(),
+            tz: zone.name().to_string(),
+            now: Some(reference.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+            features: None,
+        },
+        timeout,
+    )
+    .await?;
+    if !response.ok {
+        return Err(format!("LLM parser returned HTTP {}", response.status));
+    }
+    response
+        .body
+        .get("epoch")
+        .and_then(|epoch| epoch.as_i64().or_else(|| epoch.as_f64().map(|e| e as i64)))
+        .ok_or_else(|| "LLM parser response did not include an epoch".to_string())
+}
+
+/// Parse free text into an epoch. The deterministic grammar always runs
+/// first so the app works offline; the LLM service is only consulted when
+/// the grammar fails and `use_llm_parsing` is enabled.
+///
+/// This is a dry run, safe to call on every keystroke: it never writes the
+/// clipboard, history, or usage stats. Recording a conversion is the job of
+/// `copy_timestamp` (or `add_conversion` / `increment_format_usage`). The
+/// only things it keeps are an in-memory timing sample and cached result.
+///
+/// With a `request_id`, the parse can be stopped early with `cancel_parse`,
+/// and a newer call with the same id cancels the older one.
+///
+/// The one exception to the dry run: `commit` marks input the user submitted
+/// rather than is still typing, and with `auto_copy_on_parse` on, a
+/// committed parse that needs no clarification goes through
+/// `copy_timestamp` in the default format and emits `auto-copied`.
+#[tauri::command]
+async fn parse_time(
+    app: AppHandle,
+    input: String,
+    reference_now: Option<i64>,
+    tz: Option<String>,
+    request_id: Option<String>,
+    commit: Option<bool>,
+) -> Result<ParsedTime, AppError> {
+    let zone = timezone::resolve_or_system(tz.as_deref()).map_err(AppError::Invalid)?;
+    let settings = load_app_settings(&app)?;
+    let auto_copy_format = (commit.unwrap_or(false) && settings.auto_copy_on_parse)
+        .then(|| settings.default_format.clone());
+    let pinned_now = debug_clock(&settings).filter(|_| reference_now.is_none());
+    let reference =
+        timezone::datetime_from_unix(reference_now.unwrap_or_else(|| app_now(&settings)))
+            .map_err(AppError::Invalid)?;
+    let started = std::time::Instant::now();
+    let key = parse_cache::CacheKey {
+        input: deterministic::normalize_input(&input),
+        reference_minute: reference.timestamp().div_euclid(60),
+        zone: zone.name().to_string(),
+    };
+    let cached = app
+        .state::<ParseCacheState>()
+        .cache
+        .lock()
+        .ok()
+        .and_then(|mut cache| cache.get(&key, started));
+    let mut parsed = match cached {
+        Some(mut parsed) => {
+            parsed.from_cache = true;
+            parsed
+        }
+        None => {
+            let parsed = match request_id {
+                Some(request_id) => {
+                    cancellable_parse(&app, request_id, input.clone(), zone, reference, settings)
+                        .await?
+                }
+                None => parse_time_at(&app, &input, zone, reference, &settings).await?,
+            };
+            // A timed-out LLM parse may well succeed on the next try, and a
+            // relative reading keeps moving with the clock.
+            if parsed.method != "deterministic-fallback" && !parsed.relative {
+                if let Ok(mut cache) = app.state::<ParseCacheState>().cache.lock() {
+                    cache.insert(key, parsed.clone(), std::time::Instant::now());
+                }
+            }
+            parsed
+        }
+    };
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    if let Ok(mut window) = app.state::<ParseLatencyState>().window.lock() {
+        window.record(elapsed_ms);
+    }
+    parsed.elapsed_ms = Some(elapsed_ms);
+    parsed.timezone = timezone::timezone_info(tz.as_deref(), parsed.epoch).ok();
+    parsed.debug_clock = pinned_now;
+    if let Some(copied) = settle_parse(
+        &mut AppConversionSink(&app),
+        &input,
+        &parsed,
+        auto_copy_format.as_deref(),
+    ) {
+        // `settle_parse` only copies when there is a format.
+        let format = auto_copy_format.unwrap_or_default();
+        auto_copy_parse(&app, copied, parsed.epoch, format);
+    }
+    Ok(parsed)
+}
+
+/// The only side effect a parse may have: with an `auto_copy_format` (a
+/// committed parse under `auto_copy_on_parse`), copy and record a result
+/// that needs no clarification. Returns `None` when nothing was written.
+fn settle_parse(
+    sink: &mut impl ConversionSink,
+    input: &str,
+    parsed: &ParsedTime,
+    auto_copy_format: Option<&str>,
+) -> Option<Result<String, AppError>> {
+    let format = auto_copy_format.filter(|_| !parsed.needs_clarification)?;
+    Some(copy_conversion(sink, input, parsed.epoch, format))
+}
+
+/// Payload of the `auto-copied` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoCopied {
+    pub timestamp: String,
+    pub unix: i64,
+    pub format: String,
+}
+
+/// Finish an `auto_copy_on_parse` copy the way `copy_timestamp` does. A
+/// failed copy is reported but leaves the parse result alone.
+fn auto_copy_parse(app: &AppHandle, copied: Result<String, AppError>, unix: i64, format: String) {
+    match copied.and_then(|timestamp| after_copy(app, &timestamp).map(|()| timestamp)) {
+        Ok(timestamp) => {
+            log::debug!("Auto-copied {timestamp}");
+            let payload = AutoCopied {
+                timestamp,
+                unix,
+                format,
+            };
+            if let Err(e) = app.emit("auto-copied", &payload) {
+                log::warn!("Failed to emit auto-copied: {e}");
+            }
+        }
+        Err(e) => {
+            report_error(app, e);
+        }
+    }
+}
+
+/// Run `parse_time_at` as its own task so `cancel_parse` can abort it.
+async fn cancellable_parse(
+    app: &AppHandle,
+    request_id: String,
+    input: String,
+    zone: chrono_tz::Tz,
+    reference: chrono::DateTime<chrono::Utc>,
+    settings: AppSettings,
+) -> Result<ParsedTime, AppError> {
+    let task_app = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        parse_time_at(&task_app, &input, zone, reference, &settings).await
+    });
+    let abort = task.inner().abort_handle();
+    let task_id = abort.id();
+    let state = app.state::<ParseCancelState>();
+    {
+        let mut in_flight = state
+            .in_flight
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock parse requests: {e}")))?;
+        if let Some(previous) = in_flight.insert(request_id.clone(), abort) {
+            previous.abort();
+        }
+    }
+    let result = task.await;
+    if let Ok(mut in_flight) = state.in_flight.lock() {
+        // A newer call with the same id may have replaced this entry.
+        if in_flight
+            .get(&request_id)
+            .is_some_and(|abort| abort.id() == task_id)
+        {
+            in_flight.remove(&request_id);
+        }
+    }
+    match result {
+        Ok(parsed) => Ok(parsed?),
+        Err(_) => Err(AppError::Internal("Parse cancelled".to_string())),
+    }
+}
+
+/// Abort the in-flight `parse_time` call tagged `request_id`. Returns
+/// whether one was still running.
+#[tauri::command]
+async fn cancel_parse(app: AppHandle, request_id: String) -> Result<bool, AppError> {
+    let state = app.state::<ParseCancelState>();
+    let mut in_flight = state
+        .in_flight
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock parse requests: {e}")))?;
+    Ok(match in_flight.remove(&request_id) {
+        Some(abort) => {
+            abort.abort();
+            log::debug!("Cancelled parse request {request_id}");
+            true
+        }
+        None => false,
+    })
+}
+
+/// Count, p50, p95 and max over the most recent `parse_time` calls.
+#[tauri::command]
+async fn get_parse_latency_stats(app: AppHandle) -> Result<latency::LatencyStats, AppError> {
+    let state = app.state::<ParseLatencyState>();
+    let window = state
+        .window
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock parse latency window: {e}")))?;
+    Ok(window.stats())
+}
+
+fn debug_clock_allowed() -> bool {
+    cfg!(debug_assertions) || env_flag_enabled("HAMMEROVERLAY_ALLOW_DEBUG_CLOCK")
+}
+
+/// `debug_fixed_now` if this build honors it.
+fn debug_clock(settings: &AppSettings) -> Option<i64> {
+    settings.debug_fixed_now.filter(|_| debug_clock_allowed())
+}
+
+/// Mirror the active debug clock into `DebugClockState`; when it changes,
+/// emit `debug-clock` with the pinned epoch (or null) and refresh the tray.
+fn sync_debug_clock(app: &AppHandle, settings: &AppSettings) {
+    let clock = debug_clock(settings).map(|fixed_now| {
+        let moment = timezone::canonical_text(
+            fixed_now,
+            timezone::system_timezone(),
+            settings.time_display_24h,
+            true,
+        )
+        .unwrap_or_else(|_| fixed_now.to_string());
+        (fixed_now, moment)
+    });
+    let changed = app
+        .state::<DebugClockState>()
+        .clock
+        .lock()
+        .map(|mut slot| {
+            let changed = *slot != clock;
+            *slot = clock.clone();
+            changed
+        })
+        .unwrap_or(false);
+    if changed {
+        if let Err(e) = app.emit(
+            "debug-clock",
+            clock.as_ref().map(|(fixed_now, _)| fixed_now),
+        ) {
+            log::warn!("Failed to emit debug-clock: {e}");
+        }
+        refresh_tray_tooltip(app);
+    }
+}
+
+/// The reference "now" for parsing and formatting: the real clock unless
+/// `debug_fixed_now` pins it. History timestamps always use the real clock.
+fn app_now(settings: &AppSettings) -> i64 {
+    debug_clock(settings).unwrap_or_else(timezone::now_unix)
+}
+
+fn parse_rules(settings: &AppSettings) -> deterministic::ParseRules {
+    deterministic::ParseRules {
+        starts_on: deterministic::weekday_from_word(&settings.week_starts_on)
+            .unwrap_or(chrono::Weekday::Mon),
+        this_includes_today: settings.this_weekday_includes_today,
+        bare_time: deterministic::BareTimePolicy::from_name(&settings.bare_time_policy)
+            .unwrap_or_default(),
+        date_only_time: timezone::parse_clock(&settings.default_time_for_date_only)
+            .unwrap_or(deterministic::ParseRules::default().date_only_time),
+    }
+}
+
+/// Parse one input against a fixed reference instant: deterministic first,
+/// then the LLM path when enabled. Shares `parse_time`'s no-side-effects
+/// contract; keep clipboard and stats writes out of here.
+async fn parse_time_at(
+    app: &AppHandle,
+    input: &str,
+    zone: chrono_tz::Tz,
+    reference: chrono::DateTime<chrono::Utc>,
+    settings: &AppSettings,
+) -> Result<ParsedTime, String> {
+    let deterministic_error = match deterministic_interpretation(input, zone, reference, settings) {
+        Ok(interpretation) => {
+            return ParsedTime::from_interpretation(
+                &interpretation,
+                zone,
+                settings.time_display_24h,
+            )
+        }
+        Err(e) => e,
+    };
+
+    if !settings.use_llm_parsing {
+        return Err(format!("Could not parse input: {deterministic_error}"));
+    }
+
+    log::debug!("Deterministic parse failed ({deterministic_error}); deferring to LLM path");
+    let timeout = Duration::from_millis(u64::from(settings.llm_timeout_ms));
+    let epoch = match tokio::time::timeout(
+        timeout,
+        llm_parse_epoch(app, input, zone, reference, timeout),
+    )
+    .await
+    {
+        Ok(epoch) => epoch?,
+        Err(_) => {
+            log::warn!(
+                "LLM parse timed out after {} ms; falling back to a partial deterministic parse",
+                settings.llm_timeout_ms
+            );
+            // The whole input already failed the grammar, so look for the
+            // longest part of it that does not.
+            let locale = locales::find(&settings.parse_locale).unwrap_or(&locales::ENGLISH);
+            let rules = parse_rules(settings);
+            let interpretation = deterministic::parse_longest_span(
+                input,
+                &reference.with_timezone(&zone),
+                locale,
+                &rules,
+            )
+            .map_err(|_| format!("LLM parse timed out after {} ms", settings.llm_timeout_ms))?;
+            let mut parsed = ParsedTime::from_interpretation(
+                &round_interpretation(interpretation, zone, settings),
+                zone,
+                settings.time_display_24h,
+            )?;
+            parsed.method = "deterministic-fallback".to_string();
+            return Ok(parsed);
+        }
+    };
+    // The LLM cannot say whether the input was relative; treat it as explicit.
+    let epoch = deterministic::round_epoch(epoch, &zone, settings.round_to_minutes);
+    Ok(ParsedTime {
+        epoch,
+        method: "llm".to_string(),
+        needs_clarification: false,
+        candidates: Vec::new(),
+        // The LLM only returns an epoch, so there is no defaulted time to flag.
+        canonical_text: timezone::canonical_text(epoch, zone, settings.time_display_24h, true)?,
+        elapsed_ms: None,
+        from_cache: false,
+        timezone: None,
+        debug_clock: None,
+        // Caching LLM answers is the point of the cache; an LLM reading of
+        // "in 2 hours" is only off by however far into the minute it was.
+        relative: false,
+    })
+}
+
+/// The grammar half of `parse_time_at`, already rounded. It takes no app
+/// handle, so it cannot reach the clipboard or stats.
+fn deterministic_interpretation(
+    input: &str,
+    zone: chrono_tz::Tz,
+    reference: chrono::DateTime<chrono::Utc>,
+    settings: &AppSettings,
+) -> Result<deterministic::Interpretation, String> {
+    let locale = locales::find(&settings.parse_locale).unwrap_or(&locales::ENGLISH);
+    deterministic::parse_with_locale(
+        input,
+        &reference.with_timezone(&zone),
+        locale,
+        &parse_rules(settings),
+    )
+    .map(|interpretation| round_interpretation(interpretation, zone, settings))
+}
+
+/// Apply `round_to_minutes` to times the input spelled out, leaving "now"
+/// and offsets like "in 2 hours" alone unless `round_relative_times` is set.
+/// A defaulted time ("march 15") is already on the configured clock time.
+fn round_interpretation(
+    mut interpretation: deterministic::Interpretation,
+    zone: chrono_tz::Tz,
+    settings: &AppSettings,
+) -> deterministic::Interpretation {
+    for resolution in &mut interpretation.candidates {
+        if resolution.had_explicit_time && (!resolution.relative || settings.round_relative_times) {
+            resolution.epoch =
+                deterministic::round_epoch(resolution.epoch, &zone, settings.round_to_minutes);
+        }
+    }
+    interpretation
+}
+
+/// Label, raw tag and a sample rendering for each format, using the
+/// configured clock style and `tz` (the system zone when unset).
+/// `enabled_only` limits the list to `enabled_formats`.
+#[tauri::command]
+async fn describe_formats(
+    app: AppHandle,
+    unix: i64,
+    tz: Option<String>,
+    enabled_only: Option<bool>,
+) -> Result<Vec<discord::FormatDescription>, AppError> {
+    let zone = timezone::resolve_or_system(tz.as_deref()).map_err(AppError::Invalid)?;
+    let settings = load_app_settings(&app)?;
+    let formats = format_filter(&app, enabled_only)?;
+    discord::describe_formats(
+        unix,
+        zone,
+        settings.time_display_24h,
+        app_now(&settings),
+        &formats,
+    )
+    .map_err(AppError::Invalid)
+}
+
+/// Ranked completions for a partially typed phrase; lexical only, so it is
+/// cheap enough for every keystroke.
+#[tauri::command]
+async fn suggest_completions(partial: String) -> Result<Vec<String>, AppError> {
+    Ok(deterministic::suggest_completions(&partial))
+}
+
+/// A calendar boundary such as "end-of-day" or "start-of-next-week" on
+/// `tz`'s wall clock; see `timezone::BOUNDARY_KINDS`. Weeks follow
+/// `week_starts_on`.
+#[tauri::command]
+async fn boundary(
+    app: AppHandle,
+    kind: String,
+    reference_now: i64,
+    tz: String,
+) -> Result<i64, AppError> {
+    let zone = timezone::resolve_or_system(Some(&tz)).map_err(AppError::Invalid)?;
+    let settings = load_app_settings(&app)?;
+    let starts_on =
+        deterministic::weekday_from_word(&settings.week_starts_on).unwrap_or(chrono::Weekday::Mon);
+    timezone::boundary(&kind, reference_now, zone, starts_on).map_err(AppError::Invalid)
+}
+
+/// Both ends of a parsed range, each with every Discord format.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedRange {
+    pub start: ParsedTime,
+    pub end: ParsedTime,
+    /// The end read earlier than the start and was moved to the next day.
+    pub end_rolled_over: bool,
+    pub start_formats: discord::DiscordFormats,
+    pub end_formats: discord::DiscordFormats,
+}
+
+/// Parse "3pm to 5pm", "from X until Y" or "X–Y" with the deterministic
+/// grammar; ranges never go to the LLM. `enabled_only` limits both format
+/// lists to `enabled_formats`.
+#[tauri::command]
+async fn parse_range(
+    app: AppHandle,
+    input: String,
+    reference_now: i64,
+    tz: Option<String>,
+    enabled_only: Option<bool>,
+) -> Result<ParsedRange, AppError> {
+    let zone = timezone::resolve_or_system(tz.as_deref()).map_err(AppError::Invalid)?;
+    let reference = timezone::datetime_from_unix(reference_now).map_err(AppError::Invalid)?;
+    let settings = load_app_settings(&app)?;
+    let locale = locales::find(&settings.parse_locale).unwrap_or(&locales::ENGLISH);
+    let range = deterministic::parse_range(
+        &input,
+        &reference.with_timezone(&zone),
+        locale,
+        &parse_rules(&settings),
+        settings.range_end_rolls_over,
+    )
+    .map_err(AppError::Invalid)?;
+    let end_of = |interpretation| {
+        ParsedTime::from_interpretation(
+            &round_interpretation(interpretation, zone, &settings),
+            zone,
+            settings.time_display_24h,
+        )
+        .map_err(AppError::Invalid)
+    };
+    let start = end_of(range.start)?;
+    let end = end_of(range.end)?;
+    let enabled = format_filter(&app, enabled_only)?;
+    Ok(ParsedRange {
+        start_formats: discord::all_formats(start.epoch).only(&enabled),
+        end_formats: discord::all_formats(end.epoch).only(&enabled),
+        start,
+        end,
+        end_rolled_over: range.rolled_over,
+    })
+}
+
+/// Next hit of a simple recurrence ("every monday", "weekdays at 9").
+#[tauri::command]
+async fn next_occurrence(
+    input: String,
+    reference_now: i64,
+    tz: Option<String>,
+) -> Result<i64, AppError> {
+    let zone = timezone::resolve_or_system(tz.as_deref()).map_err(AppError::Invalid)?;
+    let reference = timezone::datetime_from_unix(reference_now).map_err(AppError::Invalid)?;
+    deterministic::next_occurrence(&input, &reference.with_timezone(&zone))
+        .map_err(AppError::Invalid)
+}
+
+/// Parse each line independently; like `parse_time`, this has no side effects.
+#[tauri::command]
+async fn parse_time_batch(
+    app: AppHandle,
+    inputs: Vec<String>,
+    reference_now: i64,
+    tz: Option<String>,
+) -> Result<Vec<BatchParseItem>, AppError> {
+    let zone = timezone::resolve_or_system(tz.as_deref()).map_err(AppError::Invalid)?;
+    let reference = timezone::datetime_from_unix(reference_now).map_err(AppError::Invalid)?;
+    let settings = load_app_settings(&app)?;
+
+    let mut items = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let item = match parse_time_at(&app, input.trim(), zone, reference, &settings).await {
+            Ok(parsed) => BatchParseItem {
+                input,
+                parsed: Some(parsed),
+                error: None,
+            },
+            Err(e) => BatchParseItem {
+                input,
+                parsed: None,
+                error: Some(e),
+            },
+        };
+        items.push(item);
+    }
+    Ok(items)
 }
 
 fn parser_child_is_running(state: &TimeParserServiceState) -> Result<bool, String> {
@@ -1411,7 +2797,7 @@ fn stop_time_parser_service(app: &AppHandle) -> Result<(), String> {
 }
 
 #[tauri::command]
-async fn restart_time_parser_service(app: AppHandle) -> Result<TimeParserServiceConfig, String> {
+async fn restart_time_parser_service(app: AppHandle) -> Result<TimeParserServiceConfig, AppError> {
     stop_time_parser_service(&app)?;
     start_time_parser_service(&app);
     let _ = wait_for_time_parser_service_blocking(Duration::from_secs(8)).await?;
@@ -1419,334 +2805,3233 @@ async fn restart_time_parser_service(app: AppHandle) -> Result<TimeParserService
 }
 
 #[tauri::command]
-async fn get_local_slm_status(app: AppHandle) -> Result<LocalSlmStatus, String> {
+async fn get_local_slm_status(app: AppHandle) -> Result<LocalSlmStatus, AppError> {
     tauri::async_runtime::spawn_blocking(move || get_local_slm_status_sync(&app))
         .await
         .map_err(|e| format!("Failed to join Local SLM status task: {e}"))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn start_local_slm(app: AppHandle) -> Result<LocalSlmStatus, String> {
-    start_local_slm_runtime(app).await
+async fn start_local_slm(app: AppHandle) -> Result<LocalSlmStatus, AppError> {
+    Ok(start_local_slm_runtime(app).await?)
 }
 
 #[tauri::command]
-async fn stop_local_slm(app: AppHandle) -> Result<LocalSlmStatus, String> {
+async fn stop_local_slm(app: AppHandle) -> Result<LocalSlmStatus, AppError> {
     tauri::async_runtime::spawn_blocking(move || stop_local_slm_sync(&app))
         .await
         .map_err(|e| format!("Failed to join Local SLM stop task: {e}"))?
+        .map_err(AppError::from)
 }
 
-#[tauri::command]
-async fn init_stats_db(_app: AppHandle) -> Result<(), String> {
-    Ok(())
+fn stats_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {e}"))?;
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory for stats DB: {e}"))?;
+    Ok(app_data_dir.join("overlay-stats.db"))
 }
 
-#[tauri::command]
-async fn get_format_stats(_app: AppHandle) -> Result<FormatStats, String> {
-    Ok(FormatStats {
-        d: 0,
-        long_date: 0,
-        t: 0,
-        long_time: 0,
-        f: 0,
-        long_date_time: 0,
-        relative_time: 0,
-    })
+/// Run `f` against the stats DB, opening and migrating it on first use.
+fn with_stats_db<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&rusqlite::Connection) -> Result<T, String>,
+) -> Result<T, AppError> {
+    let state = app.state::<StatsDbState>();
+    let mut connection_slot = state
+        .connection
+        .lock()
+        .map_err(|e| AppError::Db(format!("Failed to lock stats database: {e}")))?;
+    if connection_slot.is_none() {
+        let path = stats_db_path(app).map_err(AppError::Db)?;
+        log::info!("Opening stats database at {path:?}");
+        *connection_slot = Some(db::open(&path).map_err(AppError::Db)?);
+    }
+    match connection_slot.as_ref() {
+        Some(connection) => f(connection).map_err(AppError::Db),
+        None => Err(AppError::Db("Stats database is not open".to_string())),
+    }
 }
 
 #[tauri::command]
-async fn increment_format_usage(_app: AppHandle, format: String) -> Result<(), String> {
-    println!("Incrementing usage for format: {format}");
-    Ok(())
+async fn init_stats_db(app: AppHandle) -> Result<(), AppError> {
+    with_stats_db(&app, |_connection| Ok(()))
 }
 
-fn load_app_settings(app: &AppHandle) -> Result<AppSettings, String> {
-    log::debug!("Loading app settings");
+/// Reclaim space from the stats DB; returns the bytes freed.
+#[tauri::command]
+async fn compact_database(app: AppHandle) -> Result<u64, AppError> {
+    let path = stats_db_path(&app).map_err(AppError::Db)?;
+    let reclaimed = with_stats_db(&app, |connection| db::compact(connection, &path))?;
+    log::info!("Compacted stats database, reclaimed {reclaimed} bytes");
+    Ok(reclaimed)
+}
 
-    // Create store with manager and path
-    let store = tauri_plugin_store::StoreBuilder::new(app, "settings.json")
-        .build()
-        .map_err(|e| {
-            log::error!("Failed to build settings store: {e}");
-            e.to_string()
-        })?;
+/// tauri-plugin-sql keeps the history DB in the config directory.
+fn history_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(HISTORY_DB_FILE))
+        .map_err(|e| format!("Failed to resolve config directory: {e}"))
+}
 
-    // Try to reload the store from disk first
-    store
-        .reload()
-        .map_err(|e| {
-            log::warn!("Failed to reload store from disk (this is normal on first run): {e}");
-            e.to_string()
-        })
-        .ok();
+/// Every SQLite database a backup covers.
+fn backup_databases(app: &AppHandle) -> Result<Vec<backup::Database>, String> {
+    Ok(vec![
+        backup::Database {
+            entry: "overlay-stats.db".to_string(),
+            path: stats_db_path(app)?,
+        },
+        backup::Database {
+            entry: HISTORY_DB_FILE.to_string(),
+            path: history_db_path(app)?,
+        },
+    ])
+}
 
-    let settings = if let Some(settings_value) = store.get("settings") {
-        match serde_json::from_value(settings_value.clone()) {
-            Ok(settings) => {
-                log::debug!("Successfully loaded settings from store");
-                settings
-            }
-            Err(e) => {
-                log::warn!("Failed to parse settings, using defaults: {e}");
-                AppSettings::default()
-            }
+/// Close tauri-plugin-sql's pools so their files can be replaced. The
+/// frontend cannot query them again until the app restarts.
+async fn close_sql_pools(app: &AppHandle) {
+    let Some(instances) = app.try_state::<tauri_plugin_sql::DbInstances>() else {
+        return;
+    };
+    let pools = std::mem::take(&mut *instances.0.write().await);
+    for (name, pool) in pools {
+        match pool {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => pool.close().await,
         }
-    } else {
-        log::info!("No settings found, using defaults");
-        AppSettings::default()
+        log::info!("Closed database {name}");
+    }
+}
+
+/// Zip the whole app data directory (settings store and databases) to `dest_path`.
+#[tauri::command]
+async fn backup_data(app: AppHandle, dest_path: String) -> Result<(), AppError> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::StoreIo(format!("Failed to resolve app data directory: {e}")))?;
+    // Databases are snapshotted as they stand; the store may hold unsaved
+    // changes.
+    flush_settings_store(&app).map_err(AppError::StoreIo)?;
+    let databases = backup_databases(&app).map_err(AppError::Db)?;
+
+    let manifest = backup::BackupManifest {
+        schema_version: backup::SCHEMA_VERSION,
+        app_version: app.package_info().version.to_string(),
+        created_at: chrono::Utc::now().timestamp(),
     };
+    let files = backup::write_archive(&data_dir, Path::new(&dest_path), &manifest, &databases)
+        .map_err(AppError::StoreIo)?;
+    log::info!("Backed up {files} files to {dest_path}");
+    Ok(())
+}
 
-    Ok(settings)
+/// Replace the app data directory and databases with a `backup_data`
+/// archive, then restart so everything reopens on the restored files.
+#[tauri::command]
+async fn restore_data(app: AppHandle, src_path: String) -> Result<(), AppError> {
+    let src = Path::new(&src_path);
+    let manifest = backup::read_manifest(src).map_err(AppError::Invalid)?;
+    if manifest.schema_version != backup::SCHEMA_VERSION {
+        return Err(AppError::Invalid(format!(
+            "Backup uses schema version {} but this build reads version {}",
+            manifest.schema_version,
+            backup::SCHEMA_VERSION
+        )));
+    }
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::StoreIo(format!("Failed to resolve app data directory: {e}")))?;
+    let databases = backup_databases(&app).map_err(AppError::Db)?;
+    // A damaged archive fails here, while everything is still open and intact.
+    let staged = backup::stage_archive(src, &data_dir, &databases).map_err(AppError::StoreIo)?;
+    // Release every database so its file can be replaced.
+    close_sql_pools(&app).await;
+    flush_stats_db(&app).map_err(AppError::Db)?;
+    let files = staged.apply().map_err(AppError::StoreIo)?;
+    log::info!(
+        "Restored {files} files from {src_path} (app version {}); restarting",
+        manifest.app_version
+    );
+    app.restart()
 }
 
-fn save_app_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
-    log::info!("Saving app settings");
+/// Add to history and to today's tally.
+fn record_conversion(
+    connection: &rusqlite::Connection,
+    input: &str,
+    unix: i64,
+    format: &str,
+) -> Result<db::Conversion, String> {
+    let now = timezone::now_unix();
+    let conversion = db::add_conversion(connection, input, unix, format, now)?;
+    db::count_conversion_on_day(
+        connection,
+        timezone::start_of_day_unix(now, timezone::system_timezone())?,
+    )?;
+    Ok(conversion)
+}
 
-    // Create store with manager and path
+#[tauri::command]
+async fn add_conversion(
+    app: AppHandle,
+    input: String,
+    unix: i64,
+    format: String,
+) -> Result<db::Conversion, AppError> {
+    discord::validate_format(&format).map_err(AppError::Invalid)?;
+    let conversion = with_stats_db(&app, |connection| {
+        record_conversion(connection, &input, unix, &format)
+    })?;
+    refresh_tray_menu(&app);
+    refresh_tray_tooltip(&app);
+    Ok(conversion)
+}
+
+/// Copy a timestamp to the clipboard, record it in history, and schedule the
+/// overlay to hide when `auto_dismiss_after_copy_ms` is set.
+#[tauri::command]
+async fn copy_timestamp(
+    app: AppHandle,
+    input: String,
+    unix: i64,
+    format: String,
+) -> Result<String, AppError> {
+    let timestamp = copy_conversion(&mut AppConversionSink(&app), &input, unix, &format)?;
+    after_copy(&app, &timestamp)?;
+    Ok(timestamp)
+}
+
+/// Where a copied conversion goes, so the clipboard and history writes can
+/// be checked without a running app.
+trait ConversionSink {
+    fn write_clipboard(&mut self, text: &str) -> Result<(), String>;
+    fn record(&mut self, input: &str, unix: i64, format: &str) -> Result<(), AppError>;
+}
+
+/// The real clipboard and stats DB.
+struct AppConversionSink<'a>(&'a AppHandle);
+
+impl ConversionSink for AppConversionSink<'_> {
+    fn write_clipboard(&mut self, text: &str) -> Result<(), String> {
+        write_clipboard(self.0, text).map_err(|e| e.to_string())
+    }
+
+    fn record(&mut self, input: &str, unix: i64, format: &str) -> Result<(), AppError> {
+        with_stats_db(self.0, |connection| {
+            record_conversion(connection, input, unix, format).map(|_| ())
+        })
+    }
+}
+
+/// Copy `unix` in `format` and record it in history. Returns the copied
+/// timestamp.
+fn copy_conversion(
+    sink: &mut impl ConversionSink,
+    input: &str,
+    unix: i64,
+    format: &str,
+) -> Result<String, AppError> {
+    discord::validate_format(format).map_err(AppError::Invalid)?;
+    let timestamp = discord::timestamp(unix, format);
+    sink.write_clipboard(&timestamp)
+        .map_err(|e| AppError::Clipboard(format!("Failed to copy timestamp: {e}")))?;
+    sink.record(input, unix, format)?;
+    Ok(timestamp)
+}
+
+/// Tray refresh, notification and auto-dismiss after a recorded copy.
+fn after_copy(app: &AppHandle, timestamp: &str) -> Result<(), AppError> {
+    refresh_tray_menu(app);
+    refresh_tray_tooltip(app);
+
+    let settings = load_app_settings(app)?;
+    if settings.notify_on_copy {
+        notify_in_background(format!("Copied {timestamp}"));
+    }
+    let delay_ms = settings.auto_dismiss_after_copy_ms;
+    if delay_ms > 0 {
+        schedule_auto_dismiss(app, delay_ms);
+    } else {
+        invalidate_auto_dismiss(app);
+    }
+    Ok(())
+}
+
+/// Copy every format at once. Deliberately skips history and usage stats so
+/// the bulk copy doesn't skew the most-used format.
+#[tauri::command]
+async fn copy_all_formats(app: AppHandle, unix: i64) -> Result<(), AppError> {
+    let settings = load_app_settings(&app)?;
+    write_clipboard(
+        &app,
+        &discord::labeled_formats(unix, &settings.enabled_formats),
+    )
+    .map_err(|e| AppError::Clipboard(format!("Failed to copy all formats: {e}")))?;
+    if settings.notify_on_copy {
+        notify_in_background("Copied all formats".to_string());
+    }
+    Ok(())
+}
+
+/// Show a notification on the runtime's blocking pool, which reuses its
+/// threads across copies; failures are only logged. Messages must not carry
+/// the user's raw input.
+fn notify_in_background(message: String) {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = notify::show(NOTIFICATION_TITLE, &message) {
+            log::warn!("{e}");
+        }
+    });
+}
+
+/// Show a sample notification so users can confirm the OS allows them.
+#[tauri::command]
+async fn test_notification() -> Result<(), AppError> {
+    tauri::async_runtime::spawn_blocking(|| {
+        notify::show(NOTIFICATION_TITLE, "Notifications are working")
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to show notification: {e}")))?
+    .map_err(AppError::Internal)
+}
+
+/// Write text the app produced, remembering it so the clipboard monitor
+/// doesn't report it back as a change.
+fn write_clipboard(
+    app: &AppHandle,
+    text: &str,
+) -> Result<(), tauri_plugin_clipboard_manager::Error> {
+    if let Ok(mut own_write) = app.state::<ClipboardMonitorState>().own_write.lock() {
+        *own_write = Some(text.to_string());
+    }
+    app.clipboard().write_text(text)
+}
+
+fn schedule_auto_dismiss(app: &AppHandle, delay_ms: u32) {
+    let generation = app.state::<AutoDismissState>().bump();
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        let _task = track_task(&app_handle, "auto-dismiss", None);
+        std::thread::sleep(Duration::from_millis(u64::from(delay_ms)));
+        let state = app_handle.state::<AutoDismissState>();
+        if state.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        auto_hide_main_window(
+            &app_handle,
+            format!("Auto-dismissing overlay {delay_ms}ms after copy"),
+            false,
+        );
+    });
+}
+
+/// Hide the main window on its own initiative, first waiting out whatever is
+/// left of `min_visible_ms` since the last show. A show or copy meanwhile
+/// drops the deferred hide, as does regained focus when `unless_focused`.
+fn auto_hide_main_window(app: &AppHandle, reason: String, unless_focused: bool) {
+    let state = app.state::<AutoDismissState>();
+    let generation = state.generation.load(Ordering::SeqCst);
+    let min_visible = Duration::from_millis(u64::from(
+        load_app_settings(app).unwrap_or_default().min_visible_ms,
+    ));
+    let remaining = state
+        .shown_at
+        .lock()
+        .ok()
+        .and_then(|shown_at| *shown_at)
+        .map(|shown_at| min_visible.saturating_sub(shown_at.elapsed()))
+        .unwrap_or_default();
+    if remaining.is_zero() {
+        if let Some(window) = app.get_webview_window("main") {
+            log::debug!("{reason}");
+            let _ = window.hide();
+        }
+        return;
+    }
+
+    log::debug!(
+        "{reason}; deferring the hide {} ms for min_visible_ms",
+        remaining.as_millis()
+    );
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        let _task = track_task(&app_handle, "deferred-hide", None);
+        std::thread::sleep(remaining);
+        let state = app_handle.state::<AutoDismissState>();
+        if state.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let Some(window) = app_handle.get_webview_window("main") else {
+            return;
+        };
+        if unless_focused && window.is_focused().unwrap_or(false) {
+            log::debug!("Main window regained focus; keeping it");
+            return;
+        }
+        log::debug!("{reason}");
+        let _ = window.hide();
+    });
+}
+
+fn invalidate_auto_dismiss(app: &AppHandle) {
+    app.state::<AutoDismissState>().bump();
+}
+
+/// Called by the frontend on user interaction so a pending hide doesn't fire.
+#[tauri::command]
+async fn cancel_auto_dismiss(app: AppHandle) -> Result<(), AppError> {
+    invalidate_auto_dismiss(&app);
+    Ok(())
+}
+
+/// The enabled format after `current`, wrapping around.
+#[tauri::command]
+async fn next_format(app: AppHandle, current: String) -> Result<String, AppError> {
+    discord::next_format(&current, &load_app_settings(&app)?.enabled_formats)
+        .map(str::to_string)
+        .map_err(AppError::Invalid)
+}
+
+/// The enabled format before `current`, wrapping around.
+#[tauri::command]
+async fn prev_format(app: AppHandle, current: String) -> Result<String, AppError> {
+    discord::prev_format(&current, &load_app_settings(&app)?.enabled_formats)
+        .map(str::to_string)
+        .map_err(AppError::Invalid)
+}
+
+/// `enabled_formats` when `enabled_only` is set, else every format.
+fn format_filter(app: &AppHandle, enabled_only: Option<bool>) -> Result<Vec<String>, AppError> {
+    if enabled_only.unwrap_or(false) {
+        Ok(load_app_settings(app)?.enabled_formats)
+    } else {
+        Ok(discord::FORMAT_LETTERS.map(str::to_string).to_vec())
+    }
+}
+
+#[tauri::command]
+async fn extract_timestamps(input: String) -> Result<Vec<discord::ParsedTimestamp>, AppError> {
+    Ok(discord::extract_timestamps(&input))
+}
+
+#[tauri::command]
+async fn reformat_timestamp(input: String, to_format: String) -> Result<String, AppError> {
+    discord::reformat_timestamp(&input, &to_format).map_err(AppError::Invalid)
+}
+
+/// Formats for the current moment, skipping the parser entirely.
+#[tauri::command]
+async fn timestamp_now(
+    app: AppHandle,
+    reference_now: Option<i64>,
+    enabled_only: Option<bool>,
+) -> Result<discord::DiscordFormats, AppError> {
+    let now = app_now(&load_app_settings(&app)?);
+    let formats =
+        discord::formats_in(reference_now.unwrap_or(now), 0).map_err(AppError::Invalid)?;
+    Ok(formats.only(&format_filter(&app, enabled_only)?))
+}
+
+/// Formats for `seconds` from now.
+#[tauri::command]
+async fn timestamp_in(
+    app: AppHandle,
+    seconds: i64,
+    reference_now: Option<i64>,
+    enabled_only: Option<bool>,
+) -> Result<discord::DiscordFormats, AppError> {
+    let now = app_now(&load_app_settings(&app)?);
+    let formats =
+        discord::formats_in(reference_now.unwrap_or(now), seconds).map_err(AppError::Invalid)?;
+    Ok(formats.only(&format_filter(&app, enabled_only)?))
+}
+
+/// The phrase `<t:unix:R>` will show to someone viewing it at `viewed_at`.
+#[tauri::command]
+async fn preview_relative(unix: i64, viewed_at: i64) -> Result<String, AppError> {
+    Ok(discord::relative_phrase(unix, viewed_at))
+}
+
+#[tauri::command]
+async fn from_epoch(
+    app: AppHandle,
+    input: String,
+    enabled_only: Option<bool>,
+) -> Result<discord::DiscordFormats, AppError> {
+    let unix = discord::parse_epoch(&input).map_err(AppError::Invalid)?;
+    Ok(discord::all_formats(unix).only(&format_filter(&app, enabled_only)?))
+}
+
+/// Raw tags in a code block for pasting; unlike a copy, this records no
+/// history or usage.
+#[tauri::command]
+async fn to_code_block(unix: i64, formats: Vec<String>) -> Result<String, AppError> {
+    discord::code_block(unix, &formats).map_err(AppError::Invalid)
+}
+
+#[tauri::command]
+async fn format_template(unix: i64, template: String) -> Result<String, AppError> {
+    discord::format_template(unix, &template).map_err(AppError::Invalid)
+}
+
+#[tauri::command]
+async fn render_template(text: String, unix: i64, format: String) -> Result<String, AppError> {
+    discord::render_template(&text, unix, &format).map_err(AppError::Invalid)
+}
+
+#[tauri::command]
+async fn get_recent_conversions(
+    app: AppHandle,
+    limit: u32,
+) -> Result<Vec<db::Conversion>, AppError> {
+    with_stats_db(&app, |connection| db::recent_conversions(connection, limit))
+}
+
+/// Write the whole history, newest first, as a Markdown list to `path`.
+#[tauri::command]
+async fn export_history_markdown(app: AppHandle, path: String) -> Result<(), AppError> {
+    let conversions = with_stats_db(&app, |connection| {
+        db::recent_conversions(connection, db::CONVERSION_HISTORY_LIMIT)
+    })?;
+    let settings = load_app_settings(&app)?;
+    let markdown = export::history_markdown(
+        &conversions,
+        timezone::now_unix(),
+        timezone::system_timezone(),
+        settings.time_display_24h,
+    )
+    .map_err(AppError::Internal)?;
+    fs::write(&path, markdown)
+        .map_err(|e| AppError::StoreIo(format!("Failed to write history export: {e}")))?;
+    log::info!("Exported {} conversions to {path}", conversions.len());
+    Ok(())
+}
+
+/// Save `template` under `name`, replacing a snippet of the same name.
+#[tauri::command]
+async fn save_snippet(
+    app: AppHandle,
+    name: String,
+    template: String,
+) -> Result<db::Snippet, AppError> {
+    db::validate_snippet(&name, &template).map_err(AppError::Invalid)?;
+    let saved = with_stats_db(&app, |connection| {
+        let snippets = db::list_snippets(connection)?;
+        let is_new = !snippets
+            .iter()
+            .any(|snippet| snippet.name.eq_ignore_ascii_case(name.trim()));
+        if is_new && snippets.len() >= db::SNIPPET_LIMIT as usize {
+            return Ok(Err(format!(
+                "At most {} snippets can be saved; delete one first",
+                db::SNIPPET_LIMIT
+            )));
+        }
+        db::save_snippet(connection, &name, &template, timezone::now_unix()).map(Ok)
+    })?
+    .map_err(AppError::Invalid)?;
+    log::info!("Saved snippet '{}'", saved.name);
+    Ok(saved)
+}
+
+#[tauri::command]
+async fn list_snippets(app: AppHandle) -> Result<Vec<db::Snippet>, AppError> {
+    with_stats_db(&app, db::list_snippets)
+}
+
+#[tauri::command]
+async fn delete_snippet(app: AppHandle, name: String) -> Result<(), AppError> {
+    if !with_stats_db(&app, |connection| db::delete_snippet(connection, &name))? {
+        return Err(AppError::NotFound(format!(
+            "No snippet named '{}'",
+            name.trim()
+        )));
+    }
+    log::info!("Deleted snippet '{}'", name.trim());
+    Ok(())
+}
+
+#[tauri::command]
+async fn clear_conversions(app: AppHandle) -> Result<(), AppError> {
+    log::info!("Clearing conversion history");
+    with_stats_db(&app, db::clear_conversions)?;
+    refresh_tray_menu(&app);
+    refresh_tray_tooltip(&app);
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_format_stats(_app: AppHandle) -> Result<FormatStats, AppError> {
+    Ok(FormatStats {
+        d: 0,
+        long_date: 0,
+        t: 0,
+        long_time: 0,
+        f: 0,
+        long_date_time: 0,
+        relative_time: 0,
+    })
+}
+
+#[tauri::command]
+async fn increment_format_usage(_app: AppHandle, format: String) -> Result<(), AppError> {
+    println!("Incrementing usage for format: {format}");
+    Ok(())
+}
+
+#[tauri::command]
+async fn preview_in_timezone(app: AppHandle, unix: i64, tz: String) -> Result<String, AppError> {
+    let zone = timezone::resolve_timezone(&tz).map_err(AppError::Invalid)?;
+    let use_24h = load_app_settings(&app)?.time_display_24h;
+    Ok(timezone::render_preview(unix, zone, use_24h)?)
+}
+
+/// The zone `tz` resolves to ("system" when omitted) and its offset now.
+#[tauri::command]
+async fn get_timezone_info(
+    app: AppHandle,
+    tz: Option<String>,
+) -> Result<timezone::TimezoneInfo, AppError> {
+    let settings = load_app_settings(&app)?;
+    timezone::timezone_info(tz.as_deref(), app_now(&settings)).map_err(AppError::Invalid)
+}
+
+#[tauri::command]
+async fn list_parse_locales() -> Result<Vec<locales::LocaleOption>, AppError> {
+    Ok(locales::options())
+}
+
+#[tauri::command]
+async fn list_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, AppError> {
+    let primary = app
+        .primary_monitor()
+        .map_err(|e| AppError::Internal(format!("Failed to read primary monitor: {e}")))?;
+    let monitors = app
+        .available_monitors()
+        .map_err(|e| AppError::Internal(format!("Failed to list monitors: {e}")))?;
+    Ok(monitors
+        .iter()
+        .map(|monitor| MonitorInfo {
+            name: monitor.name().cloned(),
+            position: *monitor.position(),
+            size: *monitor.size(),
+            scale_factor: monitor.scale_factor(),
+            is_primary: primary.as_ref().is_some_and(|primary| {
+                primary.name() == monitor.name() && primary.position() == monitor.position()
+            }),
+        })
+        .collect())
+}
+
+/// The deep-link parse that has not been picked up yet, if any.
+#[tauri::command]
+async fn take_pending_deep_link(app: AppHandle) -> Result<Option<BatchParseItem>, AppError> {
+    let state = app.state::<DeepLinkState>();
+    let mut pending = state
+        .pending
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock deep link state: {e}")))?;
+    Ok(pending.take())
+}
+
+/// The permalink that has not been picked up yet, if any.
+#[tauri::command]
+async fn take_pending_permalink(app: AppHandle) -> Result<Option<deeplink::Permalink>, AppError> {
+    let state = app.state::<DeepLinkState>();
+    let mut pending = state
+        .pending_permalink
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock deep link state: {e}")))?;
+    Ok(pending.take())
+}
+
+/// A `hammeroverlay://show` link that reopens the app on `unix` in `format`.
+#[tauri::command]
+async fn make_permalink(unix: i64, format: String) -> Result<String, AppError> {
+    deeplink::permalink(unix, &format).map_err(AppError::Invalid)
+}
+
+fn scheme_handler_error(e: tauri_plugin_deep_link::Error) -> AppError {
+    match e {
+        tauri_plugin_deep_link::Error::UnsupportedPlatform => AppError::Internal(
+            "URL schemes on this platform are registered by the installed app bundle, not at runtime"
+                .to_string(),
+        ),
+        e => AppError::Internal(format!("Failed to register {}:// handler: {e}", deeplink::SCHEME)),
+    }
+}
+
+#[tauri::command]
+async fn is_default_scheme_handler(app: AppHandle) -> Result<bool, AppError> {
+    app.deep_link()
+        .is_registered(deeplink::SCHEME)
+        .map_err(scheme_handler_error)
+}
+
+#[tauri::command]
+async fn register_scheme_handler(app: AppHandle) -> Result<(), AppError> {
+    app.deep_link()
+        .register(deeplink::SCHEME)
+        .map_err(scheme_handler_error)?;
+    log::info!("Registered {}:// handler", deeplink::SCHEME);
+    Ok(())
+}
+
+#[tauri::command]
+async fn duration_between(a: i64, b: i64) -> Result<timezone::DurationBreakdown, AppError> {
+    Ok(timezone::duration_between(a, b))
+}
+
+#[tauri::command]
+async fn parse_relative(input: String, reference_now: i64) -> Result<i64, AppError> {
+    deterministic::parse_relative(&input, reference_now).map_err(AppError::Invalid)
+}
+
+fn load_app_settings(app: &AppHandle) -> Result<AppSettings, AppError> {
+    log::debug!("Loading app settings");
+
+    // Create store with manager and path
     let store = tauri_plugin_store::StoreBuilder::new(app, "settings.json")
         .build()
         .map_err(|e| {
-            log::error!("Failed to build settings store: {e}");
+            report_error(
+                app,
+                AppError::StoreIo(format!("Failed to build settings store: {e}")),
+            )
+        })?;
+
+    // Try to reload the store from disk first
+    store
+        .reload()
+        .map_err(|e| {
+            log::warn!("Failed to reload store from disk (this is normal on first run): {e}");
             e.to_string()
+        })
+        .ok();
+
+    let settings = if let Some(settings_value) = store.get("settings") {
+        match serde_json::from_value(settings_value.clone()) {
+            Ok(settings) => {
+                log::debug!("Successfully loaded settings from store");
+                settings
+            }
+            Err(e) => {
+                log::warn!("Failed to parse settings, using defaults: {e}");
+                AppSettings::default()
+            }
+        }
+    } else {
+        log::info!("No settings found, using defaults");
+        AppSettings::default()
+    };
+
+    Ok(settings)
+}
+
+/// Held across a settings load-change-save so concurrent writers don't drop
+/// each other's changes. Not reentrant: take it once per write.
+fn lock_settings(app: &AppHandle) -> Result<std::sync::MutexGuard<'_, ()>, AppError> {
+    app.state::<SettingsWriteState>()
+        .inner()
+        .lock
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock settings: {e}")))
+}
+
+fn clear_parse_cache(app: &AppHandle) {
+    if let Ok(mut cache) = app.state::<ParseCacheState>().cache.lock() {
+        cache.clear();
+    }
+}
+
+fn save_app_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), AppError> {
+    log::info!("Saving app settings");
+    clear_parse_cache(app);
+
+    // Create store with manager and path
+    let store = tauri_plugin_store::StoreBuilder::new(app, "settings.json")
+        .build()
+        .map_err(|e| {
+            report_error(
+                app,
+                AppError::StoreIo(format!("Failed to build settings store: {e}")),
+            )
         })?;
 
-    let settings_value = serde_json::to_value(&settings).map_err(|e| {
-        log::error!("Failed to serialize settings: {e}");
-        e.to_string()
-    })?;
+    let settings_value = serde_json::to_value(settings).map_err(|e| {
+        report_error(
+            app,
+            AppError::Serde(format!("Failed to serialize settings: {e}")),
+        )
+    })?;
+
+    // Use set method with proper error handling
+    store.set("settings".to_string(), settings_value);
+
+    // Explicitly save the store
+    store.save().map_err(|e| {
+        report_error(
+            app,
+            AppError::StoreIo(format!("Failed to save settings to disk: {e}")),
+        )
+    })?;
+
+    log::info!("Settings saved successfully");
+    sync_debug_clock(app, settings);
+    apply_log_filters(app, settings);
+    Ok(())
+}
+
+/// The canonical spelling of an accelerator: modifiers as ctrl, alt, shift,
+/// super in that order, then the key, all lowercase ("ctrl+shift+h").
+fn canonical_hotkey(input: &str) -> Result<String, String> {
+    use tauri_plugin_global_shortcut::{Modifiers, Shortcut};
+
+    let spaced = input
+        .split('+')
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("+");
+    let shortcut = spaced
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid hotkey '{}': {e}", input.trim()))?;
+    let mut parts = [
+        (Modifiers::CONTROL, "ctrl"),
+        (Modifiers::ALT, "alt"),
+        (Modifiers::SHIFT, "shift"),
+        (Modifiers::SUPER, "super"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| shortcut.mods.contains(*modifier))
+    .map(|(_, name)| name.to_string())
+    .collect::<Vec<_>>();
+    let code = shortcut.key.to_string();
+    // "KeyH" and "Digit1" read back from plain "h" and "1".
+    let key = ["Key", "Digit"]
+        .iter()
+        .find_map(|prefix| code.strip_prefix(prefix).filter(|rest| rest.len() == 1))
+        .unwrap_or(&code)
+        .to_lowercase();
+    parts.push(key);
+    let canonical = parts.join("+");
+    if canonical.parse::<Shortcut>().ok() == Some(shortcut) {
+        Ok(canonical)
+    } else {
+        Ok(shortcut.to_string())
+    }
+}
+
+/// Rewrite every configured hotkey in `canonical_hotkey` form, leaving ones
+/// that don't parse for validation to report.
+fn normalize_hotkey_settings(settings: &mut AppSettings) {
+    let hotkeys = std::iter::once(&mut settings.global_hotkey)
+        .chain(std::iter::once(&mut settings.copy_last_hotkey))
+        .chain(settings.show_hotkey_aliases.iter_mut());
+    for hotkey in hotkeys.filter(|hotkey| !hotkey.is_empty()) {
+        if let Ok(canonical) = canonical_hotkey(hotkey) {
+            *hotkey = canonical;
+        }
+    }
+}
+
+/// Check an accelerator as typed and return its canonical form.
+#[tauri::command]
+async fn normalize_hotkey(input: String) -> Result<String, AppError> {
+    canonical_hotkey(&input).map_err(AppError::Invalid)
+}
+
+fn validate_copy_last_hotkey(settings: &AppSettings) -> Result<(), String> {
+    if settings.copy_last_hotkey.is_empty() {
+        return Ok(());
+    }
+    let shortcut = settings
+        .copy_last_hotkey
+        .parse::<tauri_plugin_global_shortcut::Shortcut>()
+        .map_err(|e| format!("Invalid hotkey '{}': {e}", settings.copy_last_hotkey))?;
+    if settings.global_hotkey.parse().ok() == Some(shortcut) {
+        return Err("Copy-last hotkey must differ from the global hotkey".to_string());
+    }
+    Ok(())
+}
+
+/// Each alias must parse and name a shortcut no other hotkey already uses.
+fn validate_show_hotkey_aliases(settings: &AppSettings) -> Result<(), String> {
+    let mut taken = [&settings.global_hotkey, &settings.copy_last_hotkey]
+        .into_iter()
+        .filter_map(|hotkey| {
+            hotkey
+                .parse::<tauri_plugin_global_shortcut::Shortcut>()
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    for alias in &settings.show_hotkey_aliases {
+        let shortcut = alias
+            .parse::<tauri_plugin_global_shortcut::Shortcut>()
+            .map_err(|e| format!("Invalid hotkey '{alias}': {e}"))?;
+        if taken.contains(&shortcut) {
+            return Err(format!(
+                "Hotkey alias '{alias}' duplicates another configured hotkey"
+            ));
+        }
+        taken.push(shortcut);
+    }
+    Ok(())
+}
+
+/// Every rule a settings object must satisfy, reported per field.
+fn settings_issues(settings: &AppSettings) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut check = |field: &str, result: Result<(), String>| {
+        if let Err(message) = result {
+            issues.push(ValidationIssue {
+                field: field.to_string(),
+                message,
+            });
+        }
+    };
+
+    check(
+        "global_hotkey",
+        settings
+            .global_hotkey
+            .parse::<tauri_plugin_global_shortcut::Shortcut>()
+            .map(|_| ())
+            .map_err(|e| format!("Invalid hotkey '{}': {e}", settings.global_hotkey)),
+    );
+    check("copy_last_hotkey", validate_copy_last_hotkey(settings));
+    check(
+        "show_hotkey_aliases",
+        validate_show_hotkey_aliases(settings),
+    );
+    // Aliases only register alongside the main hotkey, so with both the tray
+    // and the hotkey off nothing could bring the window back.
+    check(
+        "show_tray_icon",
+        if !settings.show_tray_icon && !settings.hotkey_enabled {
+            Err(
+                "Keep the tray icon or the global hotkey enabled, or the app cannot be reached"
+                    .to_string(),
+            )
+        } else {
+            Ok(())
+        },
+    );
+    check(
+        "theme",
+        if matches!(settings.theme.as_str(), "dark" | "light" | "system") {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid theme '{}'; expected dark, light or system",
+                settings.theme
+            ))
+        },
+    );
+    check(
+        "default_format",
+        discord::validate_format(&settings.default_format),
+    );
+    check(
+        "enabled_formats",
+        discord::validate_enabled_formats(&settings.enabled_formats).and_then(|_| {
+            if settings.enabled_formats.contains(&settings.default_format) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "The default format '{}' must be enabled",
+                    settings.default_format
+                ))
+            }
+        }),
+    );
+    check("last_view", validate_last_view(&settings.last_view));
+    check(
+        "close_button_behavior",
+        if matches!(settings.close_button_behavior.as_str(), "hide" | "quit") {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid close button behavior '{}'; expected hide or quit",
+                settings.close_button_behavior
+            ))
+        },
+    );
+    check(
+        "week_starts_on",
+        match deterministic::weekday_from_word(&settings.week_starts_on) {
+            Some(_) => Ok(()),
+            None => Err(format!(
+                "Invalid week start '{}'; expected a weekday name",
+                settings.week_starts_on
+            )),
+        },
+    );
+    if let Some(config) = &settings.llm {
+        check("llm", config.validate());
+    }
+    if let Some(fixed_now) = settings.debug_fixed_now {
+        check(
+            "debug_fixed_now",
+            timezone::datetime_from_unix(fixed_now).map(|_| ()),
+        );
+    }
+    if !settings.help_url.trim().is_empty() {
+        check("help_url", validate_help_url(settings.help_url.trim()));
+    }
+    if !settings.connectivity_probe_host.trim().is_empty() {
+        check(
+            "connectivity_probe_host",
+            parse_probe_host(settings.connectivity_probe_host.trim()).map(|_| ()),
+        );
+    }
+    check(
+        "llm_timeout_ms",
+        if LLM_TIMEOUT_RANGE_MS.contains(&settings.llm_timeout_ms) {
+            Ok(())
+        } else {
+            Err(format!(
+                "LLM timeout must be between {} and {} ms",
+                LLM_TIMEOUT_RANGE_MS.start(),
+                LLM_TIMEOUT_RANGE_MS.end()
+            ))
+        },
+    );
+    check(
+        "bare_time_policy",
+        match deterministic::BareTimePolicy::from_name(&settings.bare_time_policy) {
+            Some(_) => Ok(()),
+            None => Err(format!(
+                "Invalid bare time policy '{}'; expected next, today or ask",
+                settings.bare_time_policy
+            )),
+        },
+    );
+    check(
+        "default_time_for_date_only",
+        timezone::parse_clock(&settings.default_time_for_date_only).map(|_| ()),
+    );
+    if let Some((start, end)) = &settings.quiet_hours {
+        check(
+            "quiet_hours",
+            timezone::parse_clock(start)
+                .and(timezone::parse_clock(end))
+                .map(|_| ()),
+        );
+    }
+    check(
+        "parse_locale",
+        match locales::find(&settings.parse_locale) {
+            Some(_) => Ok(()),
+            None => Err(format!(
+                "Unsupported parse locale '{}'; expected one of {}",
+                settings.parse_locale,
+                locales::LOCALES
+                    .iter()
+                    .map(|locale| locale.code)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        },
+    );
+    check(
+        "window_anchor",
+        if WINDOW_ANCHORS.contains(&settings.window_anchor.as_str()) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid window anchor '{}'; expected one of {}",
+                settings.window_anchor,
+                WINDOW_ANCHORS.join(", ")
+            ))
+        },
+    );
+    check(
+        "window_opacity",
+        if (WINDOW_OPACITY_MIN..=1.0).contains(&settings.window_opacity) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Window opacity must be between {WINDOW_OPACITY_MIN} and 1.0"
+            ))
+        },
+    );
+    if let Some((width, height)) = settings.window_size {
+        check("window_size", validate_window_size(width, height));
+    }
+    check(
+        "accent_color",
+        parse_hex_color(&settings.accent_color).map(|_| ()),
+    );
+    check(
+        "auto_dismiss_after_copy_ms",
+        if settings.auto_dismiss_after_copy_ms <= AUTO_DISMISS_MAX_MS {
+            Ok(())
+        } else {
+            Err(format!(
+                "Auto-dismiss delay must be at most {AUTO_DISMISS_MAX_MS} ms"
+            ))
+        },
+    );
+    check(
+        "round_to_minutes",
+        if settings.round_to_minutes <= ROUND_TO_MINUTES_MAX {
+            Ok(())
+        } else {
+            Err(format!(
+                "Rounding step must be at most {ROUND_TO_MINUTES_MAX} minutes"
+            ))
+        },
+    );
+    check(
+        "min_visible_ms",
+        if settings.min_visible_ms <= MIN_VISIBLE_MAX_MS {
+            Ok(())
+        } else {
+            Err(format!(
+                "Minimum visible time must be at most {MIN_VISIBLE_MAX_MS} ms"
+            ))
+        },
+    );
+    check(
+        "log_filters",
+        log_filter::LogFilters::from_settings(&settings.log_filters).map(|_| ()),
+    );
+    check(
+        "shortcut_register_delay_ms",
+        if settings.shortcut_register_delay_ms <= SHORTCUT_REGISTER_DELAY_MAX_MS {
+            Ok(())
+        } else {
+            Err(format!(
+                "Shortcut registration delay must be at most {SHORTCUT_REGISTER_DELAY_MAX_MS} ms"
+            ))
+        },
+    );
+    check(
+        "local_slm_endpoint_base_url",
+        if settings.local_slm_endpoint_base_url.starts_with("http://")
+            || settings.local_slm_endpoint_base_url.starts_with("https://")
+        {
+            Ok(())
+        } else {
+            Err("Local SLM endpoint must be an http:// or https:// URL".to_string())
+        },
+    );
+    check(
+        "local_slm_startup_timeout_seconds",
+        // 0 means "use the default".
+        if settings.local_slm_startup_timeout_seconds == 0
+            || (LOCAL_SLM_MIN_STARTUP_TIMEOUT_SECONDS..=LOCAL_SLM_MAX_STARTUP_TIMEOUT_SECONDS)
+                .contains(&settings.local_slm_startup_timeout_seconds)
+        {
+            Ok(())
+        } else {
+            Err(format!(
+                "Local SLM startup timeout must be between {LOCAL_SLM_MIN_STARTUP_TIMEOUT_SECONDS} and {LOCAL_SLM_MAX_STARTUP_TIMEOUT_SECONDS} seconds"
+            ))
+        },
+    );
+    issues
+}
+
+fn validate_app_settings(settings: &AppSettings) -> Result<(), String> {
+    let issues = settings_issues(settings);
+    if issues.is_empty() {
+        return Ok(());
+    }
+    Err(issues
+        .iter()
+        .map(|issue| format!("{}: {}", issue.field, issue.message))
+        .collect::<Vec<_>>()
+        .join("; "))
+}
+
+/// Check a whole settings form without saving it; an empty list means valid.
+#[tauri::command]
+async fn validate_settings(settings: AppSettings) -> Result<Vec<ValidationIssue>, AppError> {
+    Ok(settings_issues(&settings))
+}
+
+fn validate_last_view(view: &str) -> Result<(), String> {
+    if matches!(view, "converter" | "settings") {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid view '{view}'; expected converter or settings"
+        ))
+    }
+}
+
+/// Overwrite a single field, by its serialized name, on top of the stored
+/// settings. Type mismatches surface as deserialization errors.
+fn apply_setting(
+    settings: &AppSettings,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<AppSettings, String> {
+    let mut fields = match serde_json::to_value(settings) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        Ok(_) => return Err("Settings did not serialize to an object".to_string()),
+        Err(e) => return Err(format!("Failed to serialize settings: {e}")),
+    };
+    match fields.get_mut(key) {
+        Some(field) => *field = value,
+        None => return Err(format!("Unknown setting '{key}'")),
+    }
+    serde_json::from_value(serde_json::Value::Object(fields))
+        .map_err(|e| format!("Invalid value for setting '{key}': {e}"))
+}
+
+#[tauri::command]
+async fn get_settings(app: AppHandle) -> Result<AppSettings, AppError> {
+    load_app_settings(&app)
+}
+
+#[tauri::command]
+async fn save_settings(app: AppHandle, mut settings: AppSettings) -> Result<(), AppError> {
+    settings.window_opacity = clamp_window_opacity(settings.window_opacity);
+    normalize_hotkey_settings(&mut settings);
+    validate_app_settings(&settings).map_err(AppError::Invalid)?;
+    let _settings_guard = lock_settings(&app)?;
+    save_app_settings(&app, &settings)?;
+    refresh_tray_icon(&app);
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_setting(
+    app: AppHandle,
+    key: String,
+    value: serde_json::Value,
+) -> Result<AppSettings, AppError> {
+    let _settings_guard = lock_settings(&app)?;
+    let mut settings =
+        apply_setting(&load_app_settings(&app)?, &key, value).map_err(AppError::Invalid)?;
+    normalize_hotkey_settings(&mut settings);
+    validate_app_settings(&settings).map_err(AppError::Invalid)?;
+    save_app_settings(&app, &settings)?;
+    log::info!("Updated setting '{key}'");
+    refresh_tray_icon(&app);
+    app.emit("settings-changed", &settings)
+        .map_err(|e| AppError::Internal(format!("Failed to emit settings-changed: {e}")))?;
+    Ok(settings)
+}
+
+/// Point the log plugin's filter at `settings.log_filters`. Invalid entries
+/// leave the current levels in place.
+fn apply_log_filters(app: &AppHandle, settings: &AppSettings) {
+    let filters = match log_filter::LogFilters::from_settings(&settings.log_filters) {
+        Ok(filters) => filters,
+        Err(e) => {
+            log::warn!("Ignoring log_filters: {e}");
+            return;
+        }
+    };
+    match app.state::<LogFilterState>().filters.write() {
+        Ok(mut current) => *current = filters,
+        Err(_) => log::warn!("Log filter lock poisoned; levels unchanged"),
+    }
+}
+
+/// Set the level for one log target, or for everything unlisted when
+/// `target` is `*`, and apply it right away. A `None` level drops the
+/// target's entry so it falls back to the next match.
+#[tauri::command]
+async fn set_log_level(
+    app: AppHandle,
+    target: String,
+    level: Option<String>,
+) -> Result<AppSettings, AppError> {
+    let target = target.trim().to_string();
+    log_filter::validate_target(&target).map_err(AppError::Invalid)?;
+    let _settings_guard = lock_settings(&app)?;
+    let mut settings = load_app_settings(&app)?;
+    match level {
+        Some(level) => {
+            log_filter::parse_level(&level).map_err(AppError::Invalid)?;
+            settings
+                .log_filters
+                .insert(target.clone(), level.trim().to_ascii_lowercase());
+        }
+        None => {
+            settings.log_filters.remove(&target);
+        }
+    }
+    validate_app_settings(&settings).map_err(AppError::Invalid)?;
+    save_app_settings(&app, &settings)?;
+    log::info!(
+        "Log level for '{target}' set to {}",
+        settings
+            .log_filters
+            .get(&target)
+            .map_or("default", String::as_str)
+    );
+    app.emit("settings-changed", &settings)
+        .map_err(|e| AppError::Internal(format!("Failed to emit settings-changed: {e}")))?;
+    Ok(settings)
+}
+
+/// Write the current settings (defaults on first run) to disk and open the
+/// file in the user's default editor.
+#[tauri::command]
+async fn open_settings_file(app: AppHandle) -> Result<(), AppError> {
+    let settings = load_app_settings(&app)?;
+    save_app_settings(&app, &settings)?;
+    let path = settings_store_path(&app).map_err(AppError::StoreIo)?;
+    log::info!("Opening settings file {path:?}");
+    app.opener()
+        .open_path(path.display().to_string(), None::<&str>)
+        .map_err(|e| AppError::Internal(format!("Failed to open settings file: {e}")))
+}
+
+/// Open the help page in the default browser. Works from the tray while the
+/// window is hidden.
+#[tauri::command]
+async fn open_help(app: AppHandle) -> Result<(), AppError> {
+    let settings = load_app_settings(&app)?;
+    let url = help_url(&settings);
+    log::info!("Opening help page {url}");
+    app.opener().open_url(&url, None::<&str>).map_err(|e| {
+        report_error(
+            &app,
+            AppError::Internal(format!("Failed to open help page: {e}")),
+        )
+    })
+}
+
+fn help_url(settings: &AppSettings) -> String {
+    match settings.help_url.trim() {
+        "" => DEFAULT_HELP_URL.to_string(),
+        url => url.to_string(),
+    }
+}
+
+fn validate_help_url(url: &str) -> Result<(), String> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("Invalid help URL: {e}"))?;
+    if matches!(parsed.scheme(), "http" | "https") {
+        Ok(())
+    } else {
+        Err("Help URL must use http or https".to_string())
+    }
+}
+
+/// Re-read settings after an external edit and apply them.
+#[tauri::command]
+async fn reload_settings(app: AppHandle) -> Result<AppSettings, AppError> {
+    let settings = load_app_settings(&app)?;
+    // The file may have been edited by hand since the cached parses were made.
+    clear_parse_cache(&app);
+    sync_debug_clock(&app, &settings);
+    update_global_hotkey(&app, &settings)?;
+    apply_log_filters(&app, &settings);
+    refresh_tray_menu(&app);
+    refresh_tray_icon(&app);
+    app.emit("settings-changed", &settings)
+        .map_err(|e| AppError::Internal(format!("Failed to emit settings-changed: {e}")))?;
+    Ok(settings)
+}
+
+#[tauri::command]
+async fn should_show_onboarding(app: AppHandle) -> Result<bool, AppError> {
+    Ok(!load_app_settings(&app)?.has_completed_onboarding)
+}
+
+#[tauri::command]
+async fn complete_onboarding(app: AppHandle) -> Result<(), AppError> {
+    let _settings_guard = lock_settings(&app)?;
+    let mut settings = load_app_settings(&app)?;
+    if !settings.has_completed_onboarding {
+        settings.has_completed_onboarding = true;
+        save_app_settings(&app, &settings)?;
+        log::info!("Onboarding completed");
+    }
+    Ok(())
+}
+
+/// Remember which view the main window was on so the next show restores it.
+#[tauri::command]
+async fn set_last_view(app: AppHandle, view: String) -> Result<(), AppError> {
+    validate_last_view(&view).map_err(AppError::Invalid)?;
+    let _settings_guard = lock_settings(&app)?;
+    let mut settings = load_app_settings(&app)?;
+    if settings.last_view != view {
+        settings.last_view = view;
+        save_app_settings(&app, &settings)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_window_opacity(app: AppHandle, opacity: f64) -> Result<f64, AppError> {
+    let opacity = clamp_window_opacity(opacity);
+    let _settings_guard = lock_settings(&app)?;
+    let mut settings = load_app_settings(&app)?;
+    settings.window_opacity = opacity;
+    save_app_settings(&app, &settings)?;
+    if let Some(window) = app.get_webview_window("main") {
+        apply_window_opacity(&window, opacity)?;
+    }
+    Ok(opacity)
+}
+
+/// Persist a `#RRGGBB` accent, tint the native window where supported, and
+/// emit `accent-changed` with the normalized value.
+#[tauri::command]
+async fn set_accent_color(app: AppHandle, hex: String) -> Result<String, AppError> {
+    let rgb = parse_hex_color(&hex).map_err(AppError::Invalid)?;
+    let hex = format!("#{:02X}{:02X}{:02X}", rgb.0, rgb.1, rgb.2);
+    let _settings_guard = lock_settings(&app)?;
+    let mut settings = load_app_settings(&app)?;
+    settings.accent_color = hex.clone();
+    save_app_settings(&app, &settings)?;
+    if let Some(window) = app.get_webview_window("main") {
+        if let Err(e) = apply_accent_color(&window, rgb) {
+            log::warn!("{e}");
+        }
+    }
+    app.emit("accent-changed", &hex)
+        .map_err(|e| AppError::Internal(format!("Failed to emit accent-changed: {e}")))?;
+    Ok(hex)
+}
+
+/// Resize the overlay on purpose and keep that size for later shows.
+#[tauri::command]
+async fn set_window_size(app: AppHandle, width: f64, height: f64) -> Result<(), AppError> {
+    validate_window_size(width, height).map_err(AppError::Invalid)?;
+    let _settings_guard = lock_settings(&app)?;
+    let mut settings = load_app_settings(&app)?;
+    settings.window_size = Some((width, height));
+    save_app_settings(&app, &settings)?;
+    if let Some(window) = app.get_webview_window("main") {
+        apply_window_size(&window, &settings);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_default_format(app: AppHandle, format: String) -> Result<(), AppError> {
+    discord::validate_format(&format).map_err(AppError::Invalid)?;
+    let _settings_guard = lock_settings(&app)?;
+    let mut settings = load_app_settings(&app)?;
+    settings.default_format = format;
+    // Also rejects a default outside `enabled_formats`.
+    validate_app_settings(&settings).map_err(AppError::Invalid)?;
+    save_app_settings(&app, &settings)?;
+    app.emit("settings-changed", &settings)
+        .map_err(|e| AppError::Internal(format!("Failed to emit settings-changed: {e}")))
+}
+
+/// Whether `connectivity_probe_host` accepts a TCP connection, so the UI can
+/// skip the LLM path and hide update checks while offline. Results are
+/// reused for `CONNECTIVITY_CACHE_TTL`.
+#[tauri::command]
+async fn is_online(app: AppHandle) -> Result<bool, AppError> {
+    let settings = load_app_settings(&app)?;
+    let host = match settings.connectivity_probe_host.trim() {
+        "" => updater_host(&app).ok_or_else(|| {
+            AppError::NotFound("No connectivity probe host is configured".to_string())
+        })?,
+        host => host.to_string(),
+    };
+    let state = app.state::<ConnectivityState>();
+    if let Ok(last_probe) = state.last_probe.lock() {
+        if let Some((probed_at, probed_host, online)) = last_probe.as_ref() {
+            if *probed_host == host && probed_at.elapsed() < CONNECTIVITY_CACHE_TTL {
+                return Ok(*online);
+            }
+        }
+    }
+    let online = tauri::async_runtime::spawn_blocking({
+        let host = host.clone();
+        move || probe_connectivity(&host)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to join connectivity probe: {e}")))?;
+    log::debug!("Connectivity probe to {host}: online={online}");
+    if let Ok(mut last_probe) = state.last_probe.lock() {
+        *last_probe = Some((std::time::Instant::now(), host, online));
+    }
+    Ok(online)
+}
+
+/// Host of the first updater endpoint in tauri.conf.json.
+fn updater_host(app: &AppHandle) -> Option<String> {
+    let endpoint = app
+        .config()
+        .plugins
+        .0
+        .get("updater")?
+        .get("endpoints")?
+        .get(0)?
+        .as_str()?;
+    url::Url::parse(endpoint)
+        .ok()?
+        .host_str()
+        .map(str::to_string)
+}
+
+/// Split "host[:port]", defaulting to port 443.
+fn parse_probe_host(host: &str) -> Result<(String, u16), String> {
+    let (name, port) = match host.rsplit_once(':') {
+        Some((name, port)) => (
+            name,
+            port.parse::<u16>()
+                .map_err(|_| format!("Invalid probe port '{port}'"))?,
+        ),
+        None => (host, 443),
+    };
+    if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '/') {
+        return Err(format!(
+            "Invalid probe host '{host}'; expected host or host:port"
+        ));
+    }
+    Ok((name.to_string(), port))
+}
+
+fn probe_connectivity(host: &str) -> bool {
+    use std::net::ToSocketAddrs;
+    let Ok((name, port)) = parse_probe_host(host) else {
+        return false;
+    };
+    // A failed lookup is as offline as a refused connection.
+    let Ok(mut addrs) = (name.as_str(), port).to_socket_addrs() else {
+        return false;
+    };
+    addrs.any(|addr| TcpStream::connect_timeout(&addr, CONNECTIVITY_PROBE_TIMEOUT).is_ok())
+}
+
+/// Record `version` as announced; false if it already was.
+fn mark_update_notified(app: &AppHandle, version: &str) -> Result<bool, AppError> {
+    let _settings_guard = lock_settings(app)?;
+    let mut settings = load_app_settings(app)?;
+    if settings.last_notified_update.as_deref() == Some(version) {
+        return Ok(false);
+    }
+    settings.last_notified_update = Some(version.to_string());
+    save_app_settings(app, &settings)?;
+    Ok(true)
+}
+
+#[tauri::command]
+async fn check_for_updates(app: AppHandle) -> Result<bool, AppError> {
+    log::info!("Checking for updates");
+
+    match app.updater() {
+        Ok(updater) => match updater.check().await {
+            Ok(Some(update)) => {
+                log::info!("Update available: {}", update.version);
+                match mark_update_notified(&app, &update.version) {
+                    Ok(true) => {
+                        notify_in_background(format!("Update {} is available", update.version))
+                    }
+                    Ok(false) => log::debug!("Already notified about update {}", update.version),
+                    Err(e) => log::warn!("Skipping update notification: {e}"),
+                }
+                Ok(true)
+            }
+            Ok(None) => {
+                log::info!("No updates available");
+                Ok(false)
+            }
+            Err(e) => Err(report_error(
+                &app,
+                AppError::Updater(format!("Failed to check for updates: {e}")),
+            )),
+        },
+        Err(e) => Err(report_error(
+            &app,
+            AppError::Updater(format!("Updater not available: {e}")),
+        )),
+    }
+}
+
+/// Time an update check and classify the outcome without downloading.
+#[tauri::command]
+async fn test_updater(app: AppHandle) -> Result<UpdaterDiagnostics, AppError> {
+    use tauri_plugin_updater::Error as UpdaterError;
+
+    let updater = app
+        .updater()
+        .map_err(|e| AppError::Updater(format!("Updater not available: {e}")))?;
+    let started = std::time::Instant::now();
+    let result = updater.check().await;
+    let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+    let mut diagnostics = UpdaterDiagnostics {
+        reachable: false,
+        http_status: None,
+        latency_ms,
+        update_available: false,
+        latest_version: None,
+        signature_present: false,
+        error: None,
+    };
+    match result {
+        Ok(update) => {
+            diagnostics.reachable = true;
+            if let Some(update) = update {
+                diagnostics.update_available = true;
+                diagnostics.latest_version = Some(update.version.clone());
+                diagnostics.signature_present = !update.signature.trim().is_empty();
+            }
+        }
+        Err(e) => {
+            // The endpoint answered; its status or body was the problem.
+            diagnostics.reachable = matches!(
+                e,
+                UpdaterError::ReleaseNotFound | UpdaterError::Serialization(_)
+            );
+            if let UpdaterError::Reqwest(request_error) = &e {
+                diagnostics.http_status = request_error.status().map(|status| status.as_u16());
+                diagnostics.reachable = diagnostics.http_status.is_some();
+            }
+            diagnostics.error = Some(e.to_string());
+        }
+    }
+    log::info!("Updater test finished: {diagnostics:?}");
+    Ok(diagnostics)
+}
+
+/// Payload of `update-stage` events, in the order `install_update` reaches
+/// them. A failing stage is always followed by a final `failed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "stage", rename_all = "camelCase")]
+pub enum UpdateStage {
+    Checking,
+    Downloading {
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    Verifying,
+    Applying,
+    /// Installed; the new version runs once the app restarts.
+    PendingRestart {
+        version: String,
+    },
+    Failed {
+        during: String,
+        message: String,
+    },
+}
+
+fn emit_update_stage(app: &AppHandle, stage: &UpdateStage) {
+    if let Err(e) = app.emit("update-stage", stage) {
+        log::warn!("Failed to emit update stage: {e}");
+    }
+}
+
+/// Download, verify and install the pending update, reporting each step as
+/// an `update-stage` event.
+#[tauri::command]
+async fn install_update(app: AppHandle) -> Result<(), AppError> {
+    let state = app.state::<UpdateState>();
+    if state.installing.swap(true, Ordering::SeqCst) {
+        return Err(AppError::Updater(
+            "An update is already being installed".to_string(),
+        ));
+    }
+    let result = install_update_stages(&app).await;
+    state.installing.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn install_update_stages(app: &AppHandle) -> Result<(), AppError> {
+    let fail = |during: &str, error: AppError| {
+        emit_update_stage(
+            app,
+            &UpdateStage::Failed {
+                during: during.to_string(),
+                message: error.to_string(),
+            },
+        );
+        error
+    };
+
+    emit_update_stage(app, &UpdateStage::Checking);
+    let updater = app.updater().map_err(|e| {
+        fail(
+            "checking",
+            AppError::Updater(format!("Updater not available: {e}")),
+        )
+    })?;
+    let update = match updater.check().await {
+        Ok(Some(update)) => update,
+        Ok(None) => {
+            log::info!("No update available to install");
+            return Err(fail(
+                "checking",
+                AppError::NotFound("No update available".to_string()),
+            ));
+        }
+        Err(e) => {
+            return Err(fail(
+                "checking",
+                report_error(
+                    app,
+                    AppError::Updater(format!("Failed to check for update: {e}")),
+                ),
+            ))
+        }
+    };
+
+    emit_update_stage(
+        app,
+        &UpdateStage::Downloading {
+            downloaded: 0,
+            total: None,
+        },
+    );
+    let mut downloaded = 0u64;
+    let mut reported = 0u64;
+    let mut verifying = false;
+    let bytes = update
+        .download(
+            |chunk_length, content_length| {
+                downloaded += chunk_length as u64;
+                // One event per percent, or per step when the size is unknown.
+                let step =
+                    content_length.map_or(UPDATE_PROGRESS_STEP_BYTES, |total| (total / 100).max(1));
+                if downloaded / step != reported / step {
+                    reported = downloaded;
+                    emit_update_stage(
+                        app,
+                        &UpdateStage::Downloading {
+                            downloaded,
+                            total: content_length,
+                        },
+                    );
+                }
+            },
+            || {
+                verifying = true;
+                emit_update_stage(app, &UpdateStage::Verifying);
+            },
+        )
+        .await;
+    let bytes = bytes.map_err(|e| {
+        fail(
+            if verifying {
+                "verifying"
+            } else {
+                "downloading"
+            },
+            report_error(
+                app,
+                AppError::Updater(format!("Failed to install update: {e}")),
+            ),
+        )
+    })?;
+
+    emit_update_stage(app, &UpdateStage::Applying);
+    update.install(bytes).map_err(|e| {
+        fail(
+            "applying",
+            report_error(
+                app,
+                AppError::Updater(format!("Failed to install update: {e}")),
+            ),
+        )
+    })?;
+    log::info!("Update installed successfully");
+    if let Ok(mut pending) = app.state::<UpdateState>().pending_version.lock() {
+        *pending = Some(update.version.clone());
+    }
+    emit_update_stage(
+        app,
+        &UpdateStage::PendingRestart {
+            version: update.version.clone(),
+        },
+    );
+    Ok(())
+}
+
+fn update_cache_info(app: &AppHandle) -> update_cache::CacheInfo {
+    let pending = app
+        .state::<UpdateState>()
+        .pending_version
+        .lock()
+        .ok()
+        .and_then(|pending| pending.clone());
+    update_cache::info(
+        &std::env::temp_dir(),
+        &app.package_info().name,
+        pending.as_deref(),
+    )
+}
+
+/// Installer directories left by earlier update downloads.
+#[tauri::command]
+async fn get_update_cache_info(app: AppHandle) -> Result<update_cache::CacheInfo, AppError> {
+    Ok(update_cache_info(&app))
+}
+
+/// Delete leftover update downloads, keeping an installed update that is
+/// waiting for a restart. Refused while `install_update` runs.
+#[tauri::command]
+async fn clear_update_cache(app: AppHandle) -> Result<(), AppError> {
+    if app.state::<UpdateState>().installing.load(Ordering::SeqCst) {
+        return Err(AppError::Updater(
+            "An update is being installed; try again once it finishes".to_string(),
+        ));
+    }
+    let freed = update_cache::clear(&update_cache_info(&app)).map_err(AppError::StoreIo)?;
+    log::info!("Cleared {freed} bytes of update downloads");
+    Ok(())
+}
+
+#[tauri::command]
+async fn toggle_autostart(app: AppHandle, enable: bool) -> Result<(), AppError> {
+    let autostart_manager = app.autolaunch();
+
+    if enable {
+        log::info!("Enabling auto-start");
+
+        // In development, this might fail because the executable path is different
+        if cfg!(debug_assertions) {
+            log::warn!("Auto-start may not work correctly in development mode");
+        }
+
+        // Don't fail the entire operation if auto-start fails
+        match autostart_manager.enable() {
+            Ok(_) => {
+                log::info!("Auto-start enabled successfully");
+            }
+            Err(e) => {
+                report_error(
+                    &app,
+                    AppError::Internal(format!("Failed to enable auto-start: {e}")),
+                );
+                // Don't return error, just log it
+                // This is common in development and shouldn't break settings save
+            }
+        }
+    } else {
+        log::info!("Disabling auto-start");
+        autostart_manager.disable().map_err(|e| {
+            report_error(
+                &app,
+                AppError::Internal(format!("Failed to disable auto-start: {e}")),
+            )
+        })?;
+        log::info!("Auto-start disabled successfully");
+    }
+
+    Ok(())
+}
+
+/// Remove a SQLite file along with its WAL and shared-memory sidecars,
+/// returning the files that existed. Files that are already gone are fine;
+/// any other failure stops the removal. Callers do the logging.
+fn remove_database_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut removed = Vec::new();
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = path.as_os_str().to_os_string();
+        file.push(suffix);
+        let file = PathBuf::from(file);
+        match fs::remove_file(&file) {
+            Ok(()) => removed.push(file),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("failed to delete {file:?}: {e}")),
+        }
+    }
+    Ok(removed)
+}
+
+/// Wipe settings, stats and history, turn off autostart and the hotkey, then
+/// restart. `confirmation` must equal `FACTORY_RESET_CONFIRMATION`. A
+/// database or settings file that cannot be deleted fails the reset rather
+/// than restarting into the old data.
+#[tauri::command]
+async fn factory_reset(app: AppHandle, confirmation: String) -> Result<(), AppError> {
+    if confirmation != FACTORY_RESET_CONFIRMATION {
+        return Err(AppError::Invalid(format!(
+            "Factory reset requires the confirmation token '{FACTORY_RESET_CONFIRMATION}'"
+        )));
+    }
+    log::warn!("Factory reset requested");
+
+    if !global_shortcuts_ready(&app) {
+        log::info!("Factory reset: no global shortcuts registered yet");
+    } else if let Err(e) = app.global_shortcut().unregister_all() {
+        log::warn!("Factory reset: failed to unregister shortcuts: {e}");
+    } else {
+        log::info!("Factory reset: unregistered global shortcuts");
+    }
+
+    match app.autolaunch().disable() {
+        Ok(()) => log::info!("Factory reset: disabled auto-start"),
+        Err(e) => log::warn!("Factory reset: failed to disable auto-start: {e}"),
+    }
+
+    // Release every database first; an open handle would keep its file.
+    close_sql_pools(&app).await;
+    flush_stats_db(&app).map_err(AppError::Db)?;
+    for path in [stats_db_path(&app), history_db_path(&app)] {
+        let removed = remove_database_files(&path.map_err(AppError::Db)?)
+            .map_err(|e| AppError::Db(format!("Factory reset: {e}")))?;
+        for file in removed {
+            log::info!("Factory reset: deleted {file:?}");
+        }
+    }
+
+    // Clear the cached store too, or it would write the old settings back.
+    let store = StoreBuilder::new(&app, "settings.json")
+        .build()
+        .map_err(|e| AppError::StoreIo(format!("Failed to build settings store: {e}")))?;
+    store.clear();
+    let store_path = settings_store_path(&app).map_err(AppError::StoreIo)?;
+    match fs::remove_file(&store_path) {
+        Ok(()) => log::info!("Factory reset: deleted {store_path:?}"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            return Err(AppError::StoreIo(format!(
+                "Failed to delete settings store: {e}"
+            )))
+        }
+    }
+
+    log::warn!("Factory reset complete; restarting");
+    app.restart()
+}
+
+#[tauri::command]
+async fn is_autostart_enabled(app: AppHandle) -> Result<bool, AppError> {
+    let autostart_manager = app.autolaunch();
+    Ok(autostart_manager.is_enabled().map_err(|e| e.to_string())?)
+}
+
+/// Unregister every shortcut, then register the copy-last hotkey if set and
+/// the configured hotkey and its aliases unless the user has turned it off.
+fn update_global_hotkey(app: &AppHandle, settings: &AppSettings) -> Result<(), AppError> {
+    if !global_shortcuts_ready(app) {
+        log::info!("Global shortcut plugin not installed yet; hotkey will register on startup");
+        return Ok(());
+    }
+
+    // Unregister all current shortcuts
+    if let Err(e) = app.global_shortcut().unregister_all() {
+        log::warn!("Failed to unregister shortcuts: {e}");
+    }
+
+    register_copy_last_hotkey(app, settings);
+
+    if !settings.hotkey_enabled {
+        log::info!("Global hotkey is disabled; leaving it unregistered");
+        return Ok(());
+    }
+
+    register_show_hotkey_aliases(app, settings);
+
+    let hotkey = canonical_hotkey(&settings.global_hotkey)
+        .unwrap_or_else(|_| settings.global_hotkey.clone());
+    log::info!("Registering new hotkey: {hotkey}");
+
+    // No handler of its own: the plugin's global handler already runs
+    // `handle_shortcut` for every registered shortcut, and a second one would
+    // make each press run twice.
+    match app.global_shortcut().register(hotkey.as_str()) {
+        Ok(()) => {
+            log::info!("Successfully registered global shortcut: {hotkey}");
+            Ok(())
+        }
+        Err(e) => {
+            report_error(
+                app,
+                AppError::Hotkey(format!("Failed to register hotkey '{hotkey}': {e}")),
+            );
+            // Try default as fallback
+            app.global_shortcut()
+                .register("ctrl+shift+h")
+                .map_err(|e| AppError::Hotkey(e.to_string()))?;
+            Ok(())
+        }
+    }
+}
+
+/// Register the copy-last hotkey without a handler of its own; the plugin's
+/// global handler dispatches it through `handle_shortcut`.
+fn register_copy_last_hotkey(app: &AppHandle, settings: &AppSettings) {
+    if settings.copy_last_hotkey.is_empty() {
+        return;
+    }
+    match app
+        .global_shortcut()
+        .register(settings.copy_last_hotkey.as_str())
+    {
+        Ok(()) => log::info!("Registered copy-last hotkey: {}", settings.copy_last_hotkey),
+        Err(e) => {
+            report_error(
+                app,
+                AppError::Hotkey(format!(
+                    "Failed to register copy-last hotkey '{}': {e}",
+                    settings.copy_last_hotkey
+                )),
+            );
+        }
+    }
+}
+
+/// Register each show-window alias the same way as the copy-last hotkey, so
+/// `handle_shortcut` sends them to `handle_hotkey_pressed`.
+fn register_show_hotkey_aliases(app: &AppHandle, settings: &AppSettings) {
+    let mut failed = Vec::new();
+    for alias in &settings.show_hotkey_aliases {
+        match app.global_shortcut().register(alias.as_str()) {
+            Ok(()) => log::info!("Registered show hotkey alias: {alias}"),
+            Err(e) => {
+                report_error(
+                    app,
+                    AppError::Hotkey(format!("Failed to register hotkey alias '{alias}': {e}")),
+                );
+                failed.push(alias.clone());
+            }
+        }
+    }
+    if let Ok(mut current) = app.state::<HotkeyAliasState>().failed.lock() {
+        *current = failed;
+    }
+}
+
+/// Log `error` and keep it for `get_recent_errors`, handing it back so call
+/// sites can `map_err` through this.
+fn report_error(app: &AppHandle, error: AppError) -> AppError {
+    log::error!("{error}");
+    if let Some(state) = app.try_state::<ErrorLogState>() {
+        if let Ok(mut log) = state.log.lock() {
+            log.record(&error, timezone::now_unix());
+        }
+    }
+    error
+}
+
+#[tauri::command]
+async fn get_recent_errors(
+    app: AppHandle,
+    limit: usize,
+) -> Result<Vec<error::ErrorEntry>, AppError> {
+    let state = app.state::<ErrorLogState>();
+    let log = state
+        .log
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock error log: {e}")))?;
+    Ok(log.recent(limit))
+}
+
+/// True while the local wall clock is inside the configured quiet hours.
+fn quiet_hours_active(settings: &AppSettings) -> bool {
+    let Some((start, end)) = &settings.quiet_hours else {
+        return false;
+    };
+    let now = chrono::Utc::now()
+        .with_timezone(&timezone::system_timezone())
+        .time();
+    timezone::in_quiet_hours(start, end, now).unwrap_or_else(|e| {
+        log::warn!("Ignoring invalid quiet hours: {e}");
+        false
+    })
+}
+
+/// Hotkey entry point; the tray and other callers show the window directly.
+fn handle_hotkey_pressed(app: &AppHandle) {
+    let settings = load_app_settings(app).unwrap_or_default();
+    if quiet_hours_active(&settings) {
+        log::debug!("Ignoring global shortcut during quiet hours");
+        return;
+    }
+    // Read the foreground window before ours takes focus.
+    let bounds = settings
+        .follow_active_window
+        .then(|| foreground_window_bounds(app))
+        .flatten();
+    if let Ok(mut slot) = app.state::<ForegroundWindowState>().bounds.lock() {
+        *slot = bounds;
+    }
+    if settings.suppress_over_fullscreen {
+        show_after_fullscreen(app);
+    } else {
+        show_main_window(app);
+    }
+}
+
+/// Entry point for every shortcut the plugin reports.
+fn handle_shortcut(app: &AppHandle, shortcut: &tauri_plugin_global_shortcut::Shortcut) {
+    let settings = load_app_settings(app).unwrap_or_default();
+    if settings.copy_last_hotkey.parse().ok() == Some(*shortcut) {
+        copy_last_conversion(app);
+    } else {
+        handle_hotkey_pressed(app);
+    }
+}
+
+/// Copy the most recent conversion without showing the window, then confirm
+/// with a notification.
+fn copy_last_conversion(app: &AppHandle) {
+    let last = with_stats_db(app, |connection| db::recent_conversions(connection, 1))
+        .map(|conversions| conversions.into_iter().next());
+    let message = match last {
+        Ok(Some(conversion)) => {
+            let text = discord::timestamp(conversion.unix, &conversion.format);
+            match write_clipboard(app, &text) {
+                Ok(()) => {
+                    log::info!("Copied last conversion from hotkey");
+                    format!("Copied {text}")
+                }
+                Err(e) => {
+                    report_error(
+                        app,
+                        AppError::Clipboard(format!("Failed to copy last conversion: {e}")),
+                    );
+                    "Could not copy the last conversion".to_string()
+                }
+            }
+        }
+        Ok(None) => "No conversions in history yet".to_string(),
+        Err(e) => {
+            report_error(app, e);
+            "Could not read conversion history".to_string()
+        }
+    };
+    notify_in_background(message);
+}
+
+#[tauri::command]
+async fn is_in_quiet_hours(app: AppHandle) -> Result<bool, AppError> {
+    Ok(quiet_hours_active(&load_app_settings(&app)?))
+}
+
+#[tauri::command]
+async fn reload_global_shortcuts(app: AppHandle) -> Result<(), AppError> {
+    log::info!("Reloading global shortcuts");
+
+    // Get the latest settings
+    let settings = get_settings(app.clone()).await?;
+    update_global_hotkey(&app, &settings)?;
+    refresh_tray_menu(&app);
+    Ok(())
+}
+
+fn toggle_global_hotkey_from_tray(app: &AppHandle) {
+    let result = lock_settings(app).and_then(|_settings_guard| {
+        let mut settings = load_app_settings(app)?;
+        settings.hotkey_enabled = !settings.hotkey_enabled;
+        save_app_settings(app, &settings)?;
+        update_global_hotkey(app, &settings)?;
+        Ok(settings.hotkey_enabled)
+    });
+    match result {
+        Ok(enabled) => log::info!("Global hotkey toggled from system tray: enabled={enabled}"),
+        Err(e) => {
+            report_error(
+                app,
+                AppError::Hotkey(format!("Failed to toggle global hotkey: {e}")),
+            );
+        }
+    }
+    // Rebuild either way so the checkmark matches the persisted state.
+    refresh_tray_menu(app);
+}
+
+#[tauri::command]
+async fn debug_store_location(app: AppHandle) -> Result<String, AppError> {
+    use tauri::Manager;
+
+    let mut debug_info = String::new();
+
+    // Get various app directories
+    if let Ok(app_data) = app.path().app_data_dir() {
+        debug_info.push_str(&format!("AppData: {app_data:?}\n"));
+    }
+
+    if let Ok(app_local_data) = app.path().app_local_data_dir() {
+        debug_info.push_str(&format!("AppLocalData: {app_local_data:?}\n"));
+    }
+
+    if let Ok(app_config) = app.path().app_config_dir() {
+        debug_info.push_str(&format!("AppConfig: {app_config:?}\n"));
+    }
+
+    // Try to get the actual store path
+    let _store = StoreBuilder::new(&app, "settings.json")
+        .build()
+        .map_err(|e| format!("Failed to build store: {e}"))?;
+
+    // Get store path using the path method if available
+    debug_info.push_str(
+        "\nStore file should be in one of the above directories with filename: settings.json",
+    );
+
+    Ok(debug_info)
+}
+
+/// The store plugin resolves relative store paths against the app data dir.
+fn settings_store_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("settings.json"))
+        .map_err(|e| format!("Failed to resolve app data directory: {e}"))
+}
+
+/// The shortcut actually live right now: the configured one, the fallback, or
+/// none when the hotkey is disabled or registration failed.
+fn registered_hotkey(app: &AppHandle, settings: &AppSettings) -> Option<String> {
+    if !settings.hotkey_enabled || !global_shortcuts_ready(app) {
+        return None;
+    }
+    [settings.global_hotkey.as_str(), "ctrl+shift+h"]
+        .into_iter()
+        .find(|hotkey| app.global_shortcut().is_registered(*hotkey))
+        .map(str::to_string)
+}
+
+/// Whether every hotkey the settings ask for is currently registered, apart
+/// from aliases that never registered in the first place.
+fn hotkeys_intact(app: &AppHandle, settings: &AppSettings) -> bool {
+    let main = !settings.hotkey_enabled || registered_hotkey(app, settings).is_some();
+    let copy_last = settings.copy_last_hotkey.is_empty()
+        || (global_shortcuts_ready(app)
+            && app
+                .global_shortcut()
+                .is_registered(settings.copy_last_hotkey.as_str()));
+    let failed = app
+        .state::<HotkeyAliasState>()
+        .failed
+        .lock()
+        .map(|failed| failed.clone())
+        .unwrap_or_default();
+    let aliases = !settings.hotkey_enabled
+        || settings
+            .show_hotkey_aliases
+            .iter()
+            .filter(|alias| !failed.contains(alias))
+            .all(|alias| {
+                global_shortcuts_ready(app) && app.global_shortcut().is_registered(alias.as_str())
+            });
+    main && copy_last && aliases
+}
+
+/// Whether one show-window alias is live; an alias another app holds stays
+/// unregistered.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyAliasStatus {
+    pub hotkey: String,
+    pub registered: bool,
+}
+
+#[tauri::command]
+async fn show_hotkey_alias_status(app: AppHandle) -> Result<Vec<HotkeyAliasStatus>, AppError> {
+    let settings = load_app_settings(&app)?;
+    let ready = global_shortcuts_ready(&app);
+    Ok(settings
+        .show_hotkey_aliases
+        .iter()
+        .map(|alias| HotkeyAliasStatus {
+            hotkey: alias.clone(),
+            registered: ready && app.global_shortcut().is_registered(alias.as_str()),
+        })
+        .collect())
+}
+
+#[tauri::command]
+async fn verify_hotkey_registered(app: AppHandle) -> Result<bool, AppError> {
+    Ok(hotkeys_intact(&app, &load_app_settings(&app)?))
+}
+
+/// Periodically check the hotkeys and re-register them when one went missing
+/// or the machine just resumed from sleep, which can drop OS registrations
+/// the plugin still believes it holds.
+fn start_hotkey_watchdog(app: &AppHandle) {
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        let task = track_task(
+            &app_handle,
+            "hotkey-watchdog",
+            Some(HOTKEY_WATCHDOG_INTERVAL),
+        );
+        let mut last_tick = std::time::SystemTime::now();
+        loop {
+            std::thread::sleep(HOTKEY_WATCHDOG_INTERVAL);
+            task.tick();
+            let now = std::time::SystemTime::now();
+            let resumed = now
+                .duration_since(last_tick)
+                .is_ok_and(|elapsed| elapsed > HOTKEY_WATCHDOG_INTERVAL + HOTKEY_RESUME_GAP);
+            last_tick = now;
+
+            if !global_shortcuts_ready(&app_handle) {
+                continue;
+            }
+            let Ok(settings) = load_app_settings(&app_handle) else {
+                continue;
+            };
+            let reason = if resumed {
+                "system resumed from sleep"
+            } else if !hotkeys_intact(&app_handle, &settings) {
+                "a hotkey is no longer registered"
+            } else {
+                continue;
+            };
+            log::info!("Re-registering global shortcuts: {reason}");
+            let main_thread_handle = app_handle.clone();
+            let scheduled = app_handle.run_on_main_thread(move || {
+                if let Err(e) = update_global_hotkey(&main_thread_handle, &settings) {
+                    log::warn!("Hotkey watchdog failed to re-register shortcuts: {e}");
+                }
+            });
+            if let Err(e) = scheduled {
+                log::warn!("Failed to schedule hotkey re-registration: {e}");
+            }
+        }
+    });
+}
+
+const SELF_TEST_INPUT: &str = "tomorrow 3pm";
+
+/// Run a fixed input through parse, format, clipboard and history, each
+/// stage timed on its own. History goes to a throwaway database and the
+/// clipboard is restored, so real user data is untouched.
+#[tauri::command]
+async fn self_test(app: AppHandle) -> Result<SelfTestReport, AppError> {
+    let settings = load_app_settings(&app)?;
+    let mut stages = Vec::new();
+    let mut stage = |name: &str, run: &mut dyn FnMut() -> Result<String, String>| {
+        let started = std::time::Instant::now();
+        let result = run();
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        let passed = result.is_ok();
+        log::info!("Self-test stage '{name}' passed={passed} in {elapsed_ms} ms");
+        stages.push(SelfTestStage {
+            name: name.to_string(),
+            passed,
+            elapsed_ms,
+            detail: result.unwrap_or_else(|e| e),
+        });
+        passed
+    };
+
+    let mut epoch = None;
+    stage("parse", &mut || {
+        let now = chrono::Utc::now().with_timezone(&timezone::system_timezone());
+        let locale = locales::find(&settings.parse_locale).unwrap_or(&locales::ENGLISH);
+        let interpretation = deterministic::parse_with_locale(
+            SELF_TEST_INPUT,
+            &now,
+            locale,
+            &parse_rules(&settings),
+        )?;
+        let best = interpretation.best().epoch;
+        epoch = Some(best);
+        Ok(format!("'{SELF_TEST_INPUT}' -> {best}"))
+    });
+    let Some(epoch) = epoch else {
+        return Ok(SelfTestReport {
+            passed: false,
+            stages,
+        });
+    };
+
+    let mut formatted = String::new();
+    let formats_ok = stage("format", &mut || {
+        let formats = discord::all_formats(epoch);
+        if formats.formats.len() != discord::FORMAT_LETTERS.len() {
+            return Err(format!("Expected 7 formats, got {}", formats.formats.len()));
+        }
+        formatted = discord::labeled_formats(epoch, &discord::FORMAT_LETTERS.map(str::to_string));
+        Ok(format!("{} formats", formats.formats.len()))
+    });
 
-    // Use set method with proper error handling
-    store.set("settings".to_string(), settings_value);
+    if formats_ok {
+        stage("clipboard", &mut || {
+            let previous = app.clipboard().read_text().ok();
+            write_clipboard(&app, &formatted).map_err(|e| format!("Write failed: {e}"))?;
+            let read_back = app.clipboard().read_text();
+            if let Some(previous) = &previous {
+                if let Err(e) = write_clipboard(&app, previous) {
+                    log::warn!("Self-test could not restore the clipboard: {e}");
+                }
+            }
+            match read_back {
+                Ok(text) if text == formatted => Ok("Round trip matched".to_string()),
+                Ok(_) => Err("Clipboard read back different text".to_string()),
+                Err(e) => Err(format!("Read failed: {e}")),
+            }
+        });
+    }
 
-    // Explicitly save the store
-    store.save().map_err(|e| {
-        log::error!("Failed to save settings to disk: {e}");
-        e.to_string()
-    })?;
+    stage("history", &mut || {
+        let path = std::env::temp_dir().join(format!(
+            "hammer-overlay-self-test-{}.db",
+            std::process::id()
+        ));
+        let result = db::open(&path).and_then(|connection| {
+            let before = db::count_conversions_since(&connection, 0)?;
+            db::add_conversion(
+                &connection,
+                SELF_TEST_INPUT,
+                epoch,
+                "F",
+                timezone::now_unix(),
+            )?;
+            let after = db::count_conversions_since(&connection, 0)?;
+            if after == before + 1 {
+                Ok("Conversion recorded in a scratch database".to_string())
+            } else {
+                Err(format!(
+                    "Expected {} conversions, found {after}",
+                    before + 1
+                ))
+            }
+        });
+        if let Err(e) = remove_database_files(&path) {
+            log::warn!("Self-test {e}");
+        }
+        result
+    });
 
-    log::info!("Settings saved successfully");
-    Ok(())
+    Ok(SelfTestReport {
+        passed: stages.iter().all(|stage| stage.passed),
+        stages,
+    })
 }
 
 #[tauri::command]
-async fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
-    load_app_settings(&app)
+async fn get_runtime_info(app: AppHandle) -> Result<RuntimeInfo, AppError> {
+    let state = app.state::<RuntimeState>();
+    let setup_ms = *state
+        .setup_ms
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock runtime state: {e}")))?;
+    Ok(RuntimeInfo {
+        uptime_seconds: state.started.elapsed().as_secs(),
+        setup_ms,
+    })
 }
 
+/// Background threads and tasks still running, flagging periodic ones that
+/// stopped ticking and names running more than once.
 #[tauri::command]
-async fn save_settings(app: AppHandle, settings: AppSettings) -> Result<(), String> {
-    save_app_settings(&app, &settings)
+async fn list_active_tasks(app: AppHandle) -> Result<Vec<tasks::TaskInfo>, AppError> {
+    let state = app.state::<TaskState>();
+    let registry = state
+        .registry
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock task registry: {e}")))?;
+    Ok(registry.list(chrono::Utc::now().timestamp_millis()))
 }
 
 #[tauri::command]
-async fn check_for_updates(app: AppHandle) -> Result<bool, String> {
-    log::info!("Checking for updates");
+async fn get_diagnostics(app: AppHandle) -> Result<Diagnostics, AppError> {
+    let settings = load_app_settings(&app).unwrap_or_else(|e| {
+        log::warn!("Failed to load settings for diagnostics: {e}");
+        AppSettings::default()
+    });
+    let autostart_enabled = app.autolaunch().is_enabled().unwrap_or_else(|e| {
+        log::warn!("Failed to read auto-start state for diagnostics: {e}");
+        false
+    });
+    let store_path = match settings_store_path(&app) {
+        Ok(path) => Some(path.display().to_string()),
+        Err(e) => {
+            log::warn!("{e}");
+            None
+        }
+    };
+    let db_reachable = match with_stats_db(&app, db::ping) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("{e}");
+            false
+        }
+    };
 
-    match app.updater() {
-        Ok(updater) => match updater.check().await {
-            Ok(Some(update)) => {
-                log::info!("Update available: {}", update.version);
-                Ok(true)
-            }
-            Ok(None) => {
-                log::info!("No updates available");
-                Ok(false)
-            }
-            Err(e) => {
-                log::error!("Error checking for updates: {e}");
-                Err(format!("Failed to check for updates: {e}"))
-            }
-        },
+    let timezone = match timezone::timezone_info(None, app_now(&settings)) {
+        Ok(info) => Some(info),
         Err(e) => {
-            log::error!("Updater not available: {e}");
-            Err(format!("Updater not available: {e}"))
+            log::warn!("Failed to read the timezone for diagnostics: {e}");
+            None
         }
+    };
+
+    Ok(Diagnostics {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        updater_available: app.updater().is_ok(),
+        autostart_enabled,
+        store_path,
+        registered_hotkey: registered_hotkey(&app, &settings),
+        db_reachable,
+        instance_lock: instance::lock_name(&app.config().identifier),
+        timezone,
+        fullscreen_active: exclusive_fullscreen_active(),
+    })
+}
+
+fn truncate_label(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
     }
+    let kept = text
+        .chars()
+        .take(max_chars.saturating_sub(1))
+        .collect::<String>();
+    format!("{}…", kept.trim_end())
 }
 
-#[tauri::command]
-async fn install_update(app: AppHandle) -> Result<(), String> {
-    match app.updater() {
-        Ok(updater) => match updater.check().await {
-            Ok(Some(update)) => {
-                match update
-                    .download_and_install(|_chunk_length, _content_length| {}, || {})
-                    .await
-                {
-                    Ok(_) => {
-                        log::info!("Update installed successfully");
-                        Ok(())
-                    }
-                    Err(e) => {
-                        log::error!("Error installing update: {e}");
-                        Err(format!("Failed to install update: {e}"))
-                    }
-                }
-            }
-            Ok(None) => {
-                log::info!("No update available to install");
-                Err("No update available".to_string())
-            }
-            Err(e) => {
-                log::error!("Error checking for update: {e}");
-                Err(format!("Failed to check for update: {e}"))
-            }
+fn create_system_tray_menu(
+    app: &AppHandle,
+    recent: &[db::Conversion],
+    hotkey_enabled: bool,
+) -> Result<tauri::menu::Menu<tauri::Wry>, tauri::Error> {
+    let show_item = MenuItemBuilder::with_id("show", "Show HammerOverlay")
+        .enabled(true)
+        .build(app)?;
+    let settings_item = MenuItemBuilder::with_id("settings", "Settings")
+        .enabled(true)
+        .build(app)?;
+    let check_updates_item = MenuItemBuilder::with_id("check_updates", "Check for Updates")
+        .enabled(true)
+        .build(app)?;
+    let toggle_hotkey_item = CheckMenuItemBuilder::with_id("toggle_hotkey", "Enable Global Hotkey")
+        .checked(hotkey_enabled)
+        .enabled(true)
+        .build(app)?;
+    let help_item = MenuItemBuilder::with_id("help", "Help")
+        .enabled(true)
+        .build(app)?;
+    let quit_item = MenuItemBuilder::with_id("quit", "Quit")
+        .enabled(true)
+        .build(app)?;
+
+    let mut menu = MenuBuilder::new(app)
+        .item(&show_item)
+        .item(&settings_item)
+        .item(&check_updates_item)
+        .item(&help_item)
+        .item(&toggle_hotkey_item);
+
+    if !recent.is_empty() {
+        let header = MenuItemBuilder::with_id("recent_header", "Recent Conversions")
+            .enabled(false)
+            .build(app)?;
+        menu = menu.separator().item(&header);
+        for conversion in recent {
+            // The id carries everything needed to rebuild the Discord string.
+            let item = MenuItemBuilder::with_id(
+                format!(
+                    "{TRAY_RECENT_MENU_PREFIX}{}:{}",
+                    conversion.unix, conversion.format
+                ),
+                format!(
+                    "{} ({})",
+                    truncate_label(&conversion.input, TRAY_RECENT_LABEL_CHARS),
+                    conversion.format
+                ),
+            )
+            .enabled(true)
+            .build(app)?;
+            menu = menu.item(&item);
+        }
+        menu = menu.separator();
+    }
+
+    menu.item(&quit_item).build()
+}
+
+/// Recent conversions in enabled formats only, so the tray can list fewer
+/// than `TRAY_RECENT_CONVERSIONS`.
+fn recent_conversions_for_tray(app: &AppHandle) -> Vec<db::Conversion> {
+    let enabled = load_app_settings(app).unwrap_or_default().enabled_formats;
+    let mut recent = with_stats_db(app, |connection| {
+        db::recent_conversions(connection, TRAY_RECENT_CONVERSIONS)
+    })
+    .unwrap_or_else(|e| {
+        log::warn!("Failed to load recent conversions for tray menu: {e}");
+        Vec::new()
+    });
+    recent.retain(|conversion| enabled.contains(&conversion.format));
+    recent
+}
+
+/// The tray handle stored by `setup_system_tray`, if the tray exists.
+fn tray_icon(app: &AppHandle) -> Result<Option<TrayIcon>, String> {
+    let state = app.state::<TrayState>();
+    let tray_slot = state
+        .tray
+        .lock()
+        .map_err(|e| format!("Failed to lock tray state: {e}"))?;
+    Ok(tray_slot.clone())
+}
+
+/// Rebuild the tray menu so it reflects the current conversion history.
+fn set_tray_menu(app: &AppHandle) -> Result<(), String> {
+    let Some(tray) = tray_icon(app)? else {
+        return Ok(());
+    };
+    let recent = recent_conversions_for_tray(app);
+    let hotkey_enabled = load_app_settings(app)
+        .map(|settings| settings.hotkey_enabled)
+        .unwrap_or(true);
+    let menu = create_system_tray_menu(app, &recent, hotkey_enabled)
+        .map_err(|e| format!("Failed to build tray menu: {e}"))?;
+    tray.set_menu(Some(menu))
+        .map_err(|e| format!("Failed to update tray menu: {e}"))
+}
+
+fn refresh_tray_menu(app: &AppHandle) {
+    if let Err(e) = set_tray_menu(app) {
+        log::warn!("{e}");
+    }
+}
+
+fn update_tray_tooltip(app: &AppHandle, count: u32) -> Result<(), String> {
+    let Some(tray) = tray_icon(app)? else {
+        return Ok(());
+    };
+    let debug_moment = app
+        .state::<DebugClockState>()
+        .clock
+        .lock()
+        .ok()
+        .and_then(|clock| clock.as_ref().map(|(_, moment)| moment.clone()));
+    let tooltip = if let Some(moment) = debug_moment {
+        format!("HammerOverlay — DEBUG: clock fixed at {moment}")
+    } else if count == 0 {
+        TRAY_DEFAULT_TOOLTIP.to_string()
+    } else {
+        format!("HammerOverlay — {count} today")
+    };
+    tray.set_tooltip(Some(tooltip))
+        .map_err(|e| format!("Failed to update tray tooltip: {e}"))
+}
+
+fn conversions_today(app: &AppHandle) -> Result<u32, AppError> {
+    let today = timezone::start_of_day_unix(timezone::now_unix(), timezone::system_timezone())?;
+    with_stats_db(app, |connection| db::conversions_on_day(connection, today))
+}
+
+fn refresh_tray_tooltip(app: &AppHandle) {
+    let result = conversions_today(app)
+        .map_err(String::from)
+        .and_then(|count| update_tray_tooltip(app, count));
+    if let Err(e) = result {
+        log::warn!("{e}");
+    }
+}
+
+/// `system` follows the OS theme reported by the main window.
+fn resolved_theme(app: &AppHandle, settings: &AppSettings) -> &'static str {
+    match settings.theme.as_str() {
+        "light" => "light",
+        "dark" => "dark",
+        _ => match app
+            .get_webview_window("main")
+            .and_then(|window| window.theme().ok())
+        {
+            Some(tauri::Theme::Light) => "light",
+            _ => "dark",
         },
-        Err(e) => Err(format!("Updater not available: {e}")),
     }
 }
 
-#[tauri::command]
-async fn toggle_autostart(app: AppHandle, enable: bool) -> Result<(), String> {
-    let autostart_manager = app.autolaunch();
+fn tray_icon_variant_path(app: &AppHandle, variant: &str) -> Option<PathBuf> {
+    let relative = PathBuf::from("icons").join(format!("tray-{variant}.png"));
+    path_candidate_from_text(app, &relative.to_string_lossy())
+        .into_iter()
+        .find(|path| path.is_file())
+}
 
-    if enable {
-        log::info!("Enabling auto-start");
+/// Swap the tray icon. A missing or unreadable asset keeps the current icon.
+fn apply_tray_icon_variant(app: &AppHandle, variant: &str) -> Result<(), String> {
+    let Some(tray) = tray_icon(app)? else {
+        return Ok(());
+    };
+    let Some(path) = tray_icon_variant_path(app, variant) else {
+        log::warn!("Tray icon variant '{variant}' was not found; keeping the current icon");
+        return Ok(());
+    };
+    let icon = match tauri::image::Image::from_path(&path) {
+        Ok(icon) => icon,
+        Err(e) => {
+            log::warn!("Failed to load tray icon {path:?}: {e}; keeping the current icon");
+            return Ok(());
+        }
+    };
+    tray.set_icon(Some(icon))
+        .map_err(|e| format!("Failed to set tray icon: {e}"))
+}
 
-        // In development, this might fail because the executable path is different
-        if cfg!(debug_assertions) {
-            log::warn!("Auto-start may not work correctly in development mode");
+fn refresh_tray_icon(app: &AppHandle) {
+    let override_variant = app
+        .state::<TrayState>()
+        .icon_variant_override
+        .lock()
+        .ok()
+        .and_then(|variant| variant.clone());
+    let variant = match override_variant {
+        Some(variant) => variant,
+        None => {
+            let settings = load_app_settings(app).unwrap_or_default();
+            resolved_theme(app, &settings).to_string()
         }
+    };
+    if let Err(e) = apply_tray_icon_variant(app, &variant) {
+        log::warn!("{e}");
+    }
+}
 
-        // Don't fail the entire operation if auto-start fails
-        match autostart_manager.enable() {
-            Ok(_) => {
-                log::info!("Auto-start enabled successfully");
-            }
-            Err(e) => {
-                log::error!("Failed to enable auto-start: {e}");
-                // Don't return error, just log it
-                // This is common in development and shouldn't break settings save
+/// Force the "light" or "dark" tray icon, or "auto" to follow the theme again.
+#[tauri::command]
+async fn set_tray_icon_variant(app: AppHandle, variant: String) -> Result<(), AppError> {
+    let override_variant = match variant.as_str() {
+        "light" | "dark" => Some(variant),
+        "auto" => None,
+        _ => {
+            return Err(AppError::Invalid(format!(
+                "Unknown tray icon variant '{variant}'; expected light, dark, or auto"
+            )))
+        }
+    };
+    {
+        let state = app.state::<TrayState>();
+        let mut slot = state
+            .icon_variant_override
+            .lock()
+            .map_err(|e| format!("Failed to lock tray state: {e}"))?;
+        *slot = override_variant;
+    }
+    refresh_tray_icon(&app);
+    Ok(())
+}
+
+fn copy_recent_conversion(app: &AppHandle, menu_id: &str) {
+    let Some((unix, format)) = menu_id
+        .strip_prefix(TRAY_RECENT_MENU_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+    else {
+        log::warn!("Malformed recent conversion menu id: {menu_id}");
+        return;
+    };
+    let Ok(unix) = unix.parse::<i64>() else {
+        log::warn!("Malformed recent conversion timestamp: {menu_id}");
+        return;
+    };
+    if let Err(e) = discord::validate_format(format) {
+        log::warn!("{e}");
+        return;
+    }
+
+    let timestamp = discord::timestamp(unix, format);
+    match write_clipboard(app, &timestamp) {
+        Ok(()) => {
+            log::info!("Copied recent conversion from system tray");
+            if load_app_settings(app).is_ok_and(|settings| settings.notify_on_copy) {
+                notify_in_background(format!("Copied {timestamp}"));
             }
         }
+        Err(e) => {
+            report_error(
+                app,
+                AppError::Clipboard(format!("Failed to copy recent conversion: {e}")),
+            );
+        }
+    }
+}
+
+fn clamp_window_opacity(opacity: f64) -> f64 {
+    if opacity.is_finite() {
+        opacity.clamp(WINDOW_OPACITY_MIN, 1.0)
     } else {
-        log::info!("Disabling auto-start");
-        autostart_manager.disable().map_err(|e| {
-            log::error!("Failed to disable auto-start: {e}");
-            e.to_string()
-        })?;
-        log::info!("Auto-start disabled successfully");
+        1.0
+    }
+}
+
+#[cfg(windows)]
+fn apply_window_opacity(window: &tauri::WebviewWindow, opacity: f64) -> Result<(), String> {
+    use windows::Win32::Foundation::COLORREF;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongW, SetLayeredWindowAttributes, SetWindowLongW, GWL_EXSTYLE, LWA_ALPHA,
+        WS_EX_LAYERED,
+    };
+
+    let hwnd = window
+        .hwnd()
+        .map_err(|e| format!("Failed to get main window handle: {e}"))?;
+    let alpha = (clamp_window_opacity(opacity) * 255.0).round() as u8;
+    // SAFETY: `hwnd` belongs to a live window owned by this process.
+    unsafe {
+        let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE);
+        SetWindowLongW(hwnd, GWL_EXSTYLE, ex_style | WS_EX_LAYERED.0 as i32);
+        SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA)
+            .map_err(|e| format!("Failed to set window opacity: {e}"))
     }
+}
 
+/// Other platforms have no per-window alpha here; the overlay stays opaque.
+#[cfg(not(windows))]
+fn apply_window_opacity(_window: &tauri::WebviewWindow, opacity: f64) -> Result<(), String> {
+    if opacity < 1.0 {
+        log::debug!("Window opacity is not supported on this platform; staying opaque");
+    }
     Ok(())
 }
 
-#[tauri::command]
-async fn is_autostart_enabled(app: AppHandle) -> Result<bool, String> {
-    let autostart_manager = app.autolaunch();
-    autostart_manager.is_enabled().map_err(|e| e.to_string())
+fn validate_window_size(width: f64, height: f64) -> Result<(), String> {
+    if (WINDOW_MIN_WIDTH..=WINDOW_MAX_SIDE).contains(&width)
+        && (WINDOW_MIN_HEIGHT..=WINDOW_MAX_SIDE).contains(&height)
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "Window size must be between {WINDOW_MIN_WIDTH}x{WINDOW_MIN_HEIGHT} and \
+             {WINDOW_MAX_SIDE}x{WINDOW_MAX_SIDE}"
+        ))
+    }
 }
 
-#[tauri::command]
-async fn reload_global_shortcuts(app: AppHandle) -> Result<(), String> {
-    log::info!("Reloading global shortcuts");
+/// Apply the resize lock, and the saved size when there is one.
+fn apply_window_size(window: &tauri::WebviewWindow, settings: &AppSettings) {
+    let _ = window.set_resizable(!settings.lock_window_size);
+    if let Some((width, height)) = settings.window_size {
+        let _ = window.set_size(tauri::LogicalSize::new(width, height));
+    }
+}
 
-    // Get the latest settings
-    let settings = get_settings(app.clone()).await?;
+/// Remember an unlocked window's size once the user stops resizing it.
+fn schedule_window_size_save(app: &AppHandle, size: tauri::PhysicalSize<u32>, scale: f64) {
+    let generation = app.state::<WindowSizeState>().bump();
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        let _task = track_task(&app_handle, "window-size-save", None);
+        std::thread::sleep(WINDOW_SIZE_SAVE_DEBOUNCE);
+        if app_handle
+            .state::<WindowSizeState>()
+            .generation
+            .load(Ordering::SeqCst)
+            != generation
+        {
+            return;
+        }
+        let logical = size.to_logical::<f64>(scale);
+        if validate_window_size(logical.width, logical.height).is_err() {
+            return;
+        }
+        let result = lock_settings(&app_handle).and_then(|_settings_guard| {
+            let mut settings = load_app_settings(&app_handle)?;
+            if settings.lock_window_size {
+                return Ok(());
+            }
+            settings.window_size = Some((logical.width, logical.height));
+            save_app_settings(&app_handle, &settings)
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to remember window size: {e}");
+        }
+    });
+}
 
-    // Unregister all current shortcuts
-    if let Err(e) = app.global_shortcut().unregister_all() {
-        log::warn!("Failed to unregister shortcuts: {e}");
+fn parse_hex_color(hex: &str) -> Result<(u8, u8, u8), String> {
+    let invalid = || format!("Invalid accent color '{hex}'; expected #RRGGBB");
+    let digits = hex.trim().strip_prefix('#').ok_or_else(invalid)?;
+    if digits.len() != 6 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid());
     }
+    let channel = |index: usize| u8::from_str_radix(&digits[index..index + 2], 16);
+    Ok((
+        channel(0).map_err(|_| invalid())?,
+        channel(2).map_err(|_| invalid())?,
+        channel(4).map_err(|_| invalid())?,
+    ))
+}
 
-    // Re-register with new hotkey
-    use tauri_plugin_global_shortcut::ShortcutState;
+/// Windows 11 tints the window border; older builds reject the attribute.
+#[cfg(windows)]
+fn apply_accent_color(window: &tauri::WebviewWindow, rgb: (u8, u8, u8)) -> Result<(), String> {
+    use windows::Win32::Foundation::COLORREF;
+    use windows::Win32::Graphics::Dwm::{DwmSetWindowAttribute, DWMWA_BORDER_COLOR};
+
+    let hwnd = window
+        .hwnd()
+        .map_err(|e| format!("Failed to get main window handle: {e}"))?;
+    let color = COLORREF(u32::from(rgb.0) | u32::from(rgb.1) << 8 | u32::from(rgb.2) << 16);
+    // SAFETY: `hwnd` belongs to a live window and `color` outlives the call.
+    unsafe {
+        DwmSetWindowAttribute(
+            hwnd,
+            DWMWA_BORDER_COLOR,
+            &color as *const COLORREF as *const core::ffi::c_void,
+            std::mem::size_of::<COLORREF>() as u32,
+        )
+        .map_err(|e| format!("Failed to set window accent color: {e}"))
+    }
+}
 
-    let hotkey = settings.global_hotkey;
-    log::info!("Registering new hotkey: {hotkey}");
+/// Elsewhere the accent is only a value the frontend applies.
+#[cfg(not(windows))]
+fn apply_accent_color(_window: &tauri::WebviewWindow, _rgb: (u8, u8, u8)) -> Result<(), String> {
+    Ok(())
+}
 
-    match app
-        .global_shortcut()
-        .on_shortcut(hotkey.as_str(), move |app, _shortcut, event| {
-            if event.state() == ShortcutState::Pressed {
-                log::debug!("Global shortcut activated: {_shortcut}");
-                show_main_window(app);
+/// Monitor the overlay should open on. A monitor named `excluded` is skipped
+/// in favor of the next available one, unless it is the only display.
+fn active_monitor(
+    app: &AppHandle,
+    window: &tauri::WebviewWindow,
+    excluded: Option<&str>,
+) -> Option<tauri::Monitor> {
+    let monitor = app
+        .cursor_position()
+        .ok()
+        .and_then(|cursor| app.monitor_from_point(cursor.x, cursor.y).ok().flatten())
+        .or_else(|| window.current_monitor().ok().flatten())
+        .or_else(|| app.primary_monitor().ok().flatten())?;
+    let Some(excluded) = excluded.filter(|name| monitor.name().is_some_and(|n| n == name)) else {
+        return Some(monitor);
+    };
+    let fallback = app
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .into_iter()
+        .chain(app.available_monitors().unwrap_or_default())
+        .find(|candidate| candidate.name().is_none_or(|n| n != excluded));
+    Some(fallback.unwrap_or(monitor))
+}
+
+/// Bounds of the focused window, unless it is minimized or one of ours.
+#[cfg(windows)]
+fn foreground_window_bounds(app: &AppHandle) -> Option<tauri::PhysicalRect<i32, u32>> {
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowRect, IsIconic};
+
+    // SAFETY: these calls only read window state; a stale handle makes
+    // them fail rather than misbehave.
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() || IsIconic(hwnd).as_bool() {
+            return None;
+        }
+        let ours = app
+            .webview_windows()
+            .values()
+            .any(|window| window.hwnd().is_ok_and(|own| own == hwnd));
+        if ours {
+            return None;
+        }
+        let mut rect = RECT::default();
+        GetWindowRect(hwnd, &mut rect).ok()?;
+        Some(tauri::PhysicalRect {
+            position: tauri::PhysicalPosition::new(rect.left, rect.top),
+            size: tauri::PhysicalSize::new(
+                u32::try_from(rect.right - rect.left).ok()?,
+                u32::try_from(rect.bottom - rect.top).ok()?,
+            ),
+        })
+    }
+}
+
+/// Whether a Direct3D app holds the display in exclusive fullscreen, where
+/// showing the overlay steals focus and can drop the game out of it.
+#[cfg(windows)]
+fn exclusive_fullscreen_active() -> bool {
+    use windows::Win32::UI::Shell::{SHQueryUserNotificationState, QUNS_RUNNING_D3D_FULL_SCREEN};
+
+    // SAFETY: the call only reads shell state.
+    unsafe { SHQueryUserNotificationState() }
+        .is_ok_and(|state| state == QUNS_RUNNING_D3D_FULL_SCREEN)
+}
+
+#[cfg(not(windows))]
+fn exclusive_fullscreen_active() -> bool {
+    false
+}
+
+#[tauri::command]
+async fn is_fullscreen_active() -> Result<bool, AppError> {
+    Ok(exclusive_fullscreen_active())
+}
+
+/// Show the window once exclusive fullscreen ends, or right away if it
+/// already has. Gives up after `FULLSCREEN_WAIT_MAX` so a stale request never
+/// pops the overlay up much later.
+fn show_after_fullscreen(app: &AppHandle) {
+    if !exclusive_fullscreen_active() {
+        show_main_window(app);
+        return;
+    }
+    if app
+        .state::<FullscreenWaitState>()
+        .pending
+        .swap(true, Ordering::SeqCst)
+    {
+        log::debug!("Already waiting for fullscreen to end");
+        return;
+    }
+    log::info!("Exclusive fullscreen is active; showing the overlay once it ends");
+    notify_in_background("HammerOverlay will open when the fullscreen app exits".to_string());
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        let task = track_task(
+            &app_handle,
+            "fullscreen-wait",
+            Some(FULLSCREEN_POLL_INTERVAL),
+        );
+        let started = std::time::Instant::now();
+        while exclusive_fullscreen_active() {
+            if started.elapsed() > FULLSCREEN_WAIT_MAX {
+                log::info!("Fullscreen outlasted the wait; dropping the queued show");
+                app_handle
+                    .state::<FullscreenWaitState>()
+                    .pending
+                    .store(false, Ordering::SeqCst);
+                return;
             }
-        }) {
-        Ok(_) => {
-            log::info!("Successfully registered global shortcut: {hotkey}");
-            Ok(())
+            std::thread::sleep(FULLSCREEN_POLL_INTERVAL);
+            task.tick();
         }
-        Err(e) => {
-            log::error!("Failed to register hotkey '{hotkey}': {e}");
-            // Try default as fallback
-            app.global_shortcut()
-                .on_shortcut("ctrl+shift+h", move |app, _shortcut, event| {
-                    if event.state() == ShortcutState::Pressed {
-                        show_main_window(app);
-                    }
-                })
-                .map_err(|e| e.to_string())?;
-            Ok(())
+        app_handle
+            .state::<FullscreenWaitState>()
+            .pending
+            .store(false, Ordering::SeqCst);
+        let main_thread_handle = app_handle.clone();
+        let scheduled = app_handle.run_on_main_thread(move || {
+            log::info!("Fullscreen ended; showing the queued overlay");
+            show_main_window(&main_thread_handle);
+        });
+        if let Err(e) = scheduled {
+            log::warn!("Failed to schedule the queued show: {e}");
         }
-    }
+    });
 }
 
+/// Queue a show behind an exclusive-fullscreen app, as the hotkey does when
+/// `suppress_over_fullscreen` is on.
 #[tauri::command]
-async fn debug_store_location(app: AppHandle) -> Result<String, String> {
-    use tauri::Manager;
+async fn queue_show_after_fullscreen(app: AppHandle) -> Result<(), AppError> {
+    show_after_fullscreen(&app);
+    Ok(())
+}
 
-    let mut debug_info = String::new();
+/// Other platforms expose no portable way to read another app's window, so
+/// the overlay keeps its usual placement.
+#[cfg(not(windows))]
+fn foreground_window_bounds(_app: &AppHandle) -> Option<tauri::PhysicalRect<i32, u32>> {
+    None
+}
 
-    // Get various app directories
-    if let Ok(app_data) = app.path().app_data_dir() {
-        debug_info.push_str(&format!("AppData: {app_data:?}\n"));
-    }
+/// Beside `target`, preferring its right, then left, below and above, as long
+/// as the overlay stays inside `work_area`.
+fn position_beside(
+    target: &tauri::PhysicalRect<i32, u32>,
+    size: tauri::PhysicalSize<u32>,
+    work_area: &tauri::PhysicalRect<i32, u32>,
+    margin: i32,
+) -> Option<(i32, i32)> {
+    let (width, height) = (size.width as i32, size.height as i32);
+    let target_right = target.position.x + target.size.width as i32;
+    let target_bottom = target.position.y + target.size.height as i32;
+    let area_left = work_area.position.x + margin;
+    let area_top = work_area.position.y + margin;
+    let area_right = work_area.position.x + work_area.size.width as i32 - margin;
+    let area_bottom = work_area.position.y + work_area.size.height as i32 - margin;
+    // Line up with the target's top or left edge, kept on screen.
+    let y = target
+        .position
+        .y
+        .clamp(area_top, (area_bottom - height).max(area_top));
+    let x = target
+        .position
+        .x
+        .clamp(area_left, (area_right - width).max(area_left));
+    [
+        (target_right + margin, y),
+        (target.position.x - margin - width, y),
+        (x, target_bottom + margin),
+        (x, target.position.y - margin - height),
+    ]
+    .into_iter()
+    .find(|&(x, y)| {
+        x >= area_left && y >= area_top && x + width <= area_right && y + height <= area_bottom
+    })
+}
 
-    if let Ok(app_local_data) = app.path().app_local_data_dir() {
-        debug_info.push_str(&format!("AppLocalData: {app_local_data:?}\n"));
+/// Place the overlay beside the window captured at hotkey time. False when
+/// there is none or no side has room, leaving the usual placement to run.
+fn position_beside_foreground(app: &AppHandle, window: &tauri::WebviewWindow) -> bool {
+    let Some(target) = app
+        .state::<ForegroundWindowState>()
+        .bounds
+        .lock()
+        .ok()
+        .and_then(|mut bounds| bounds.take())
+    else {
+        return false;
+    };
+    let center_x = target.position.x + target.size.width as i32 / 2;
+    let center_y = target.position.y + target.size.height as i32 / 2;
+    let (Some(monitor), Ok(size)) = (
+        app.monitor_from_point(f64::from(center_x), f64::from(center_y))
+            .ok()
+            .flatten(),
+        window.outer_size(),
+    ) else {
+        return false;
+    };
+    let margin = (WINDOW_ANCHOR_MARGIN * monitor.scale_factor()).round() as i32;
+    match position_beside(&target, size, monitor.work_area(), margin) {
+        Some((x, y)) => {
+            let _ = window.set_position(tauri::PhysicalPosition::new(x, y));
+            true
+        }
+        None => false,
     }
+}
 
-    if let Ok(app_config) = app.path().app_config_dir() {
-        debug_info.push_str(&format!("AppConfig: {app_config:?}\n"));
+/// Center the overlay, or pin it to a corner of the active monitor's work
+/// area so it never sits under the taskbar. With `follow_active_window`, a
+/// spot beside the window that had focus at hotkey time wins.
+fn position_main_window(app: &AppHandle, window: &tauri::WebviewWindow, settings: &AppSettings) {
+    if settings.follow_active_window && position_beside_foreground(app, window) {
+        return;
     }
-
-    // Try to get the actual store path
-    let _store = StoreBuilder::new(&app, "settings.json")
-        .build()
-        .map_err(|e| format!("Failed to build store: {e}"))?;
-
-    // Get store path using the path method if available
-    debug_info.push_str(
-        "\nStore file should be in one of the above directories with filename: settings.json",
-    );
-
-    Ok(debug_info)
+    let anchor = settings.window_anchor.as_str();
+    let excluded = settings.excluded_monitor.as_deref();
+    let (Some(monitor), Ok(size)) = (active_monitor(app, window, excluded), window.outer_size())
+    else {
+        let _ = window.center();
+        return;
+    };
+    let work_area = monitor.work_area();
+    let margin = (WINDOW_ANCHOR_MARGIN * monitor.scale_factor()).round() as i32;
+    let left = work_area.position.x + margin;
+    let top = work_area.position.y + margin;
+    let right = work_area.position.x + work_area.size.width as i32 - size.width as i32 - margin;
+    let bottom = work_area.position.y + work_area.size.height as i32 - size.height as i32 - margin;
+    let (x, y) = match anchor {
+        "center" => (
+            work_area.position.x + (work_area.size.width as i32 - size.width as i32) / 2,
+            work_area.position.y + (work_area.size.height as i32 - size.height as i32) / 2,
+        ),
+        "top-left" => (left, top),
+        "top-right" => (right, top),
+        "bottom-left" => (left, bottom),
+        _ => (right, bottom),
+    };
+    let _ = window.set_position(tauri::PhysicalPosition::new(x.max(left), y.max(top)));
 }
 
-fn create_system_tray_menu(app: &AppHandle) -> Result<tauri::menu::Menu<tauri::Wry>, tauri::Error> {
-    let show_item = MenuItemBuilder::with_id("show", "Show HammerOverlay")
-        .enabled(true)
-        .build(app)?;
-    let settings_item = MenuItemBuilder::with_id("settings", "Settings")
-        .enabled(true)
-        .build(app)?;
-    let check_updates_item = MenuItemBuilder::with_id("check_updates", "Check for Updates")
-        .enabled(true)
-        .build(app)?;
-    let quit_item = MenuItemBuilder::with_id("quit", "Quit")
-        .enabled(true)
-        .build(app)?;
-
-    MenuBuilder::new(app)
-        .item(&show_item)
-        .item(&settings_item)
-        .item(&check_updates_item)
-        .item(&quit_item)
-        .build()
+/// Poll the clipboard while the main window is visible and emit
+/// `clipboard-changed` when new text settles. Exits on its own once the window
+/// hides or a newer monitor replaces it.
+fn start_clipboard_monitor(app: &AppHandle) {
+    let generation = app
+        .state::<ClipboardMonitorState>()
+        .generation
+        .fetch_add(1, Ordering::SeqCst)
+        + 1;
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        let task = track_task(
+            &app_handle,
+            "clipboard-monitor",
+            Some(CLIPBOARD_POLL_INTERVAL),
+        );
+        let state = app_handle.state::<ClipboardMonitorState>();
+        let mut last_emitted = app_handle.clipboard().read_text().ok();
+        let mut pending: Option<(String, std::time::Instant)> = None;
+        loop {
+            std::thread::sleep(CLIPBOARD_POLL_INTERVAL);
+            task.tick();
+            if state.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let visible = app_handle
+                .get_webview_window("main")
+                .and_then(|window| window.is_visible().ok())
+                .unwrap_or(false);
+            if !visible {
+                log::debug!("Main window hidden; stopping clipboard monitor");
+                return;
+            }
+            // Non-text clipboard content reads as an error; ignore it.
+            let Ok(text) = app_handle.clipboard().read_text() else {
+                continue;
+            };
+            let own_write = state
+                .own_write
+                .lock()
+                .is_ok_and(|own_write| own_write.as_deref() == Some(text.as_str()));
+            if own_write || last_emitted.as_deref() == Some(text.as_str()) {
+                pending = None;
+                continue;
+            }
+            match &pending {
+                Some((candidate, since))
+                    if *candidate == text && since.elapsed() >= CLIPBOARD_DEBOUNCE =>
+                {
+                    let _ = app_handle.emit_to("main", "clipboard-changed", &text);
+                    last_emitted = Some(text);
+                    pending = None;
+                }
+                Some((candidate, _)) if *candidate == text => {}
+                _ => pending = Some((text, std::time::Instant::now())),
+            }
+        }
+    });
 }
 
 fn show_main_window(app: &AppHandle) {
     maybe_trigger_local_slm_for_overlay(app);
+    invalidate_auto_dismiss(app);
+    if let Ok(mut shown_at) = app.state::<AutoDismissState>().shown_at.lock() {
+        *shown_at = Some(std::time::Instant::now());
+    }
+    app.state::<AutoCloseState>()
+        .suspended
+        .store(false, Ordering::SeqCst);
 
     if let Some(window) = app.get_webview_window("main") {
+        let settings = load_app_settings(app).unwrap_or_default();
+        if let Err(e) = apply_window_opacity(&window, settings.window_opacity) {
+            log::warn!("{e}");
+        }
+        if let Ok(rgb) = parse_hex_color(&settings.accent_color) {
+            if let Err(e) = apply_accent_color(&window, rgb) {
+                log::debug!("{e}");
+            }
+        }
         let _ = window.show();
         let _ = window.set_focus();
         let _ = window.set_always_on_top(true);
-        let _ = window.center();
-        let _ = window.emit("show-overlay-view", ());
+        apply_window_size(&window, &settings);
+        position_main_window(app, &window, &settings);
+        let view_event = if settings.last_view == "settings" {
+            "show-settings-view"
+        } else {
+            "show-overlay-view"
+        };
+        let _ = window.emit(view_event, ());
+        // The input only lives on the converter view.
+        if settings.focus_input_on_show && view_event == "show-overlay-view" {
+            let _ = window.emit("focus-input", ());
+        }
+        if settings.auto_load_clipboard {
+            start_clipboard_monitor(app);
+        }
     }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _task = track_task(&app, "llm-warm-up", None);
+        if let Err(e) = warm_llm(app.clone()).await {
+            log::debug!("LLM warm-up failed: {e}");
+        }
+    });
 }
 
 fn maybe_trigger_local_slm_for_overlay(app: &AppHandle) {
@@ -1762,14 +6047,177 @@ fn maybe_trigger_local_slm_for_overlay(app: &AppHandle) {
     trigger_local_slm_start(app);
 }
 
+/// Hide the main window when it loses focus, if `auto_close_on_focus_loss` is
+/// on and the UI hasn't suspended it.
+fn handle_main_focus_lost(app: &AppHandle) {
+    if app
+        .state::<AutoCloseState>()
+        .suspended
+        .load(Ordering::SeqCst)
+    {
+        log::debug!("Focus lost while auto-close is suspended; keeping the window");
+        return;
+    }
+    let settings = load_app_settings(app).unwrap_or_default();
+    if !settings.auto_close_on_focus_loss {
+        return;
+    }
+    auto_hide_main_window(app, "Main window lost focus; hiding".to_string(), true);
+}
+
+/// Keep the overlay open on focus loss until `enable` is false again or the
+/// window is next shown. The stored setting is left alone.
+#[tauri::command]
+async fn suspend_auto_close(app: AppHandle, enable: bool) -> Result<(), AppError> {
+    app.state::<AutoCloseState>()
+        .suspended
+        .store(enable, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Quit immediately, or hand off to the frontend when `confirm_on_quit` is set.
+fn request_quit(app: &AppHandle) {
+    let confirm = load_app_settings(app)
+        .map(|settings| settings.confirm_on_quit)
+        .unwrap_or(false);
+    if !confirm {
+        graceful_shutdown(app);
+        return;
+    }
+
+    log::info!("Asking the frontend to confirm quit");
+    show_main_window(app);
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit("confirm-quit", ());
+    } else {
+        log::warn!("Main window not found for quit confirmation; exiting");
+        graceful_shutdown(app);
+    }
+}
+
+/// Quit from the UI, honoring `confirm_on_quit`; the only quit path when the
+/// tray icon is hidden.
+#[tauri::command]
+async fn quit_app(app: AppHandle) -> Result<(), AppError> {
+    log::info!("Quit requested from the UI");
+    request_quit(&app);
+    Ok(())
+}
+
+/// Exit without the `confirm_on_quit` prompt.
+#[tauri::command]
+async fn force_quit(app: AppHandle) -> Result<(), AppError> {
+    log::info!("Force quit requested");
+    graceful_shutdown(&app);
+    Ok(())
+}
+
+/// Show the window and act on a deep link: parse the text of a
+/// `hammeroverlay://parse` link, emitting `deep-link-parse` with the result,
+/// or emit `deep-link-show` with the timestamp of a permalink.
+fn handle_deep_link(app: &AppHandle, link: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let text = match deeplink::parse_link(&link) {
+            Ok(deeplink::Link::Parse(text)) => text,
+            Ok(deeplink::Link::Show(permalink)) => {
+                log::info!("Handling permalink");
+                show_main_window(&app);
+                if let Ok(mut pending) = app.state::<DeepLinkState>().pending_permalink.lock() {
+                    *pending = Some(permalink.clone());
+                }
+                if let Err(e) = app.emit("deep-link-show", &permalink) {
+                    log::warn!("Failed to emit deep-link-show: {e}");
+                }
+                return;
+            }
+            Err(e) => {
+                log::warn!("Ignoring deep link {link}: {e}");
+                return;
+            }
+        };
+        log::info!("Handling deep link parse request");
+        show_main_window(&app);
+
+        let settings = load_app_settings(&app).unwrap_or_default();
+        let result = match timezone::datetime_from_unix(app_now(&settings)) {
+            Ok(reference) => {
+                let zone = timezone::system_timezone();
+                parse_time_at(&app, &text, zone, reference, &settings).await
+            }
+            Err(e) => Err(e),
+        };
+        let item = match result {
+            Ok(parsed) => BatchParseItem {
+                input: text,
+                parsed: Some(parsed),
+                error: None,
+            },
+            Err(e) => BatchParseItem {
+                input: text,
+                parsed: None,
+                error: Some(e),
+            },
+        };
+
+        if let Ok(mut pending) = app.state::<DeepLinkState>().pending.lock() {
+            *pending = Some(item.clone());
+        }
+        if let Err(e) = app.emit("deep-link-parse", &item) {
+            log::warn!("Failed to emit deep-link-parse: {e}");
+        }
+    });
+}
+
+fn flush_stats_db(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<StatsDbState>();
+    let mut connection_slot = state
+        .connection
+        .lock()
+        .map_err(|e| format!("Failed to lock stats database: {e}"))?;
+    let Some(connection) = connection_slot.take() else {
+        return Ok(());
+    };
+    db::checkpoint(&connection)?;
+    connection
+        .close()
+        .map_err(|(_connection, e)| format!("Failed to close stats database: {e}"))
+}
+
+fn flush_settings_store(app: &AppHandle) -> Result<(), String> {
+    let store = StoreBuilder::new(app, "settings.json")
+        .build()
+        .map_err(|e| format!("Failed to build settings store: {e}"))?;
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings store: {e}"))
+}
+
+/// Flush the stats DB and settings store before exiting so a quit never cuts
+/// off the last write.
+fn graceful_shutdown(app: &AppHandle) {
+    log::info!("Shutting down gracefully");
+    if let Err(e) = flush_stats_db(app) {
+        report_error(app, AppError::Db(e));
+    }
+    if let Err(e) = flush_settings_store(app) {
+        report_error(app, AppError::StoreIo(e));
+    }
+    app.exit(0);
+}
+
 fn setup_system_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     log::info!("Setting up system tray");
 
-    let menu = create_system_tray_menu(app)?;
+    let recent = recent_conversions_for_tray(app);
+    let hotkey_enabled = load_app_settings(app)
+        .map(|settings| settings.hotkey_enabled)
+        .unwrap_or(true);
+    let menu = create_system_tray_menu(app, &recent, hotkey_enabled)?;
 
-    let _tray = TrayIconBuilder::new()
+    let tray = TrayIconBuilder::with_id(TRAY_ID)
         .menu(&menu)
-        .tooltip("HammerOverlay - Discord Timestamp Converter")
+        .tooltip(TRAY_DEFAULT_TOOLTIP)
         .on_menu_event(|app, event| {
             log::debug!("System tray menu event: {}", event.id.as_ref());
 
@@ -1806,7 +6254,12 @@ fn setup_system_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>>
                                 let _ = window.emit("show-settings-view", ());
                             }
                             Err(e) => {
-                                log::error!("Failed to create settings window: {e}");
+                                report_error(
+                                    app,
+                                    AppError::Internal(format!(
+                                        "Failed to create settings window: {e}"
+                                    )),
+                                );
                             }
                         }
                     }
@@ -1839,14 +6292,33 @@ fn setup_system_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>>
                                 let _ = window.emit("show-update-checker-view", ());
                             }
                             Err(e) => {
-                                log::error!("Failed to create update checker window: {e}");
+                                report_error(
+                                    app,
+                                    AppError::Internal(format!(
+                                        "Failed to create update checker window: {e}"
+                                    )),
+                                );
                             }
                         }
                     }
                 }
+                "help" => {
+                    log::info!("Help requested from system tray");
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        // `open_help` already reports and logs failures.
+                        let _ = open_help(app).await;
+                    });
+                }
+                "toggle_hotkey" => {
+                    toggle_global_hotkey_from_tray(app);
+                }
                 "quit" => {
                     log::info!("Application exit requested from system tray");
-                    app.exit(0);
+                    request_quit(app);
+                }
+                id if id.starts_with(TRAY_RECENT_MENU_PREFIX) => {
+                    copy_recent_conversion(app, id);
                 }
                 _ => {
                     log::warn!("Unknown system tray menu event: {}", event.id.as_ref());
@@ -1855,10 +6327,116 @@ fn setup_system_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>>
         })
         .build(app)?;
 
+    // Keep the handle so the menu and tooltip can be changed after setup.
+    match app.state::<TrayState>().tray.lock() {
+        Ok(mut tray_slot) => *tray_slot = Some(tray),
+        Err(e) => {
+            report_error(
+                app,
+                AppError::Internal(format!("Failed to store tray handle: {e}")),
+            );
+        }
+    }
+    refresh_tray_tooltip(app);
+    refresh_tray_icon(app);
+
     log::info!("System tray setup completed");
     Ok(())
 }
 
+fn set_show_tray_icon(app: &AppHandle, show: bool) -> Result<(), AppError> {
+    let _settings_guard = lock_settings(app)?;
+    let mut settings = load_app_settings(app)?;
+    if settings.show_tray_icon != show {
+        settings.show_tray_icon = show;
+        save_app_settings(app, &settings)?;
+        app.emit("settings-changed", &settings)
+            .map_err(|e| AppError::Internal(format!("Failed to emit settings-changed: {e}")))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn enable_tray(app: AppHandle) -> Result<(), AppError> {
+    if tray_icon(&app)?.is_none() {
+        setup_system_tray(&app)
+            .map_err(|e| AppError::Internal(format!("Failed to setup system tray: {e}")))?;
+    }
+    set_show_tray_icon(&app, true)
+}
+
+/// Remove the tray icon. The hotkey, tray-less window and `quit_app` keep
+/// working without it.
+#[tauri::command]
+async fn disable_tray(app: AppHandle) -> Result<(), AppError> {
+    if !load_app_settings(&app)?.hotkey_enabled {
+        return Err(AppError::Invalid(
+            "Enable the global hotkey before hiding the tray icon".to_string(),
+        ));
+    }
+    let removed = app
+        .state::<TrayState>()
+        .tray
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock tray state: {e}")))?
+        .take();
+    if removed.is_some() {
+        app.remove_tray_by_id(TRAY_ID);
+        log::info!("System tray removed");
+    }
+    set_show_tray_icon(&app, false)
+}
+
+/// False until `setup_global_shortcuts` has installed the plugin, which a
+/// `shortcut_register_delay_ms` can hold back past startup.
+fn global_shortcuts_ready(app: &AppHandle) -> bool {
+    app.try_state::<tauri_plugin_global_shortcut::GlobalShortcut<tauri::Wry>>()
+        .is_some()
+}
+
+/// Install global shortcuts now, or after `shortcut_register_delay_ms` on the
+/// main thread so slow machines can finish starting up first.
+fn schedule_global_shortcuts(app: &AppHandle) {
+    let delay_ms = load_app_settings(app)
+        .map(|settings| settings.shortcut_register_delay_ms)
+        .unwrap_or_default()
+        .min(SHORTCUT_REGISTER_DELAY_MAX_MS);
+    if delay_ms == 0 {
+        if let Err(e) = setup_global_shortcuts(app) {
+            report_error(
+                app,
+                AppError::Hotkey(format!("Failed to setup global shortcuts: {e}")),
+            );
+            eprintln!("Failed to setup global shortcuts: {e}");
+        }
+        return;
+    }
+
+    log::info!("Delaying global shortcut registration by {delay_ms} ms");
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        let _task = track_task(&app_handle, "delayed-shortcut-setup", None);
+        std::thread::sleep(Duration::from_millis(u64::from(delay_ms)));
+        let main_thread_handle = app_handle.clone();
+        let scheduled = app_handle.run_on_main_thread(move || {
+            if let Err(e) = setup_global_shortcuts(&main_thread_handle) {
+                report_error(
+                    &main_thread_handle,
+                    AppError::Hotkey(format!(
+                        "Failed to setup global shortcuts after a {delay_ms} ms delay: {e}"
+                    )),
+                );
+            }
+        });
+        if let Err(e) = scheduled {
+            report_error(
+                &app_handle,
+                AppError::Hotkey(format!("Failed to schedule global shortcut setup: {e}")),
+            );
+        }
+    });
+}
+
 fn setup_global_shortcuts(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     use tauri_plugin_global_shortcut::ShortcutState;
 
@@ -1868,23 +6446,34 @@ fn setup_global_shortcuts(app: &AppHandle) -> Result<(), Box<dyn std::error::Err
     let app_handle = app.clone();
     let settings_result = tauri::async_runtime::block_on(async { get_settings(app_handle).await });
 
-    let hotkey = match settings_result {
-        Ok(settings) => settings.global_hotkey,
-        Err(e) => {
-            log::warn!("Failed to load settings for hotkey, using default: {e}");
-            "ctrl+shift+h".to_string()
-        }
+    let settings = settings_result.unwrap_or_else(|e| {
+        log::warn!("Failed to load settings for hotkey, using default: {e}");
+        AppSettings::default()
+    });
+    let hotkey = settings.global_hotkey.clone();
+    let hotkey_enabled = settings.hotkey_enabled;
+    let copy_last_hotkey = settings.copy_last_hotkey.clone();
+
+    // The plugin is always installed so the hotkey can be re-enabled later.
+    let mut shortcuts = if hotkey_enabled {
+        log::info!("Attempting to register hotkey: {hotkey}");
+        vec![hotkey.as_str()]
+    } else {
+        log::info!("Global hotkey is disabled in settings");
+        Vec::new()
     };
-
-    log::info!("Attempting to register hotkey: {hotkey}");
+    if !copy_last_hotkey.is_empty() {
+        log::info!("Attempting to register copy-last hotkey: {copy_last_hotkey}");
+        shortcuts.push(copy_last_hotkey.as_str());
+    }
 
     let plugin_result = (|| -> Result<_, Box<dyn std::error::Error>> {
         let plugin = tauri_plugin_global_shortcut::Builder::new()
-            .with_shortcuts([hotkey.as_str()])?
-            .with_handler(|app, _shortcut, event| {
+            .with_shortcuts(shortcuts)?
+            .with_handler(|app, shortcut, event| {
                 if event.state == ShortcutState::Pressed {
-                    log::debug!("Global shortcut activated: {_shortcut}");
-                    show_main_window(app);
+                    log::debug!("Global shortcut activated: {shortcut}");
+                    handle_shortcut(app, shortcut);
                 }
             })
             .build();
@@ -1894,27 +6483,34 @@ fn setup_global_shortcuts(app: &AppHandle) -> Result<(), Box<dyn std::error::Err
     })();
 
     match plugin_result {
-        Ok(_) => {
+        Ok(_) if hotkey_enabled => {
             log::info!("Successfully registered global shortcut: {hotkey}");
+            // One at a time, so a bad alias can't take the main hotkey down.
+            register_show_hotkey_aliases(app, &settings);
         }
+        Ok(_) => {}
         Err(e) => {
-            log::error!("Failed to register hotkey '{hotkey}': {e}");
+            report_error(
+                app,
+                AppError::Hotkey(format!("Failed to register hotkey '{hotkey}': {e}")),
+            );
 
             // Try default hotkey as fallback
             if hotkey != "ctrl+shift+h" {
                 log::info!("Attempting to register default hotkey as fallback");
                 let fallback_plugin = tauri_plugin_global_shortcut::Builder::new()
                     .with_shortcuts(["ctrl+shift+h"])?
-                    .with_handler(|app, _shortcut, event| {
+                    .with_handler(|app, shortcut, event| {
                         if event.state == ShortcutState::Pressed {
-                            log::debug!("Global shortcut activated: {_shortcut}");
-                            show_main_window(app);
+                            log::debug!("Global shortcut activated: {shortcut}");
+                            handle_shortcut(app, shortcut);
                         }
                     })
                     .build();
 
                 app.plugin(fallback_plugin)?;
                 log::info!("Successfully registered fallback hotkey: ctrl+shift+h");
+                register_copy_last_hotkey(app, &settings);
             }
         }
     }
@@ -1925,24 +6521,49 @@ fn setup_global_shortcuts(app: &AppHandle) -> Result<(), Box<dyn std::error::Err
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Check for single instance
-    let instance = SingleInstance::new("hammer-overlay-app").unwrap();
-    if !instance.is_single() {
-        log::warn!("Another instance of HammerOverlay is already running");
-        eprintln!("HammerOverlay is already running!");
-
-        // Try to show the existing instance window
-        // This would require implementing inter-process communication
-        // For now, just exit gracefully
-        std::process::exit(1);
-    }
+    let started = std::time::Instant::now();
+    let context = tauri::generate_context!();
 
-    log::info!("Single instance check passed");
+    // Check for single instance
+    let lock_name = instance::lock_name(&context.config().identifier);
+    let launch_link = deeplink::from_args(std::env::args());
+    let mut instance = instance::acquire(&lock_name, launch_link.as_deref());
+    let launches = instance
+        .as_mut()
+        .and_then(instance::Instance::take_launches);
+
+    // Settings need the store plugin, so the filter starts permissive and
+    // setup swaps in `log_filters`.
+    let log_filters = LogFilterState::new();
+    let plugin_filters = Arc::clone(&log_filters.filters);
 
     tauri::Builder::default()
+        .manage(RuntimeState::new(started))
         .manage(TimeParserServiceState::new())
         .manage(LocalSlmServiceState::new())
+        .manage(StatsDbState::new())
+        .manage(TrayState::new())
+        .manage(AutoDismissState::new())
+        .manage(FullscreenWaitState::new())
+        .manage(UpdateState::new())
+        .manage(WindowSizeState::new())
+        .manage(ClipboardMonitorState::new())
+        .manage(DeepLinkState::new())
+        .manage(ParseLatencyState::new())
+        .manage(ParseCancelState::new())
+        .manage(ParseCacheState::new())
+        .manage(ConnectivityState::new())
+        .manage(LlmWarmState::new())
+        .manage(DebugClockState::new())
+        .manage(HotkeyAliasState::new())
+        .manage(TaskState::new())
+        .manage(ForegroundWindowState::new())
+        .manage(ErrorLogState::new())
+        .manage(AutoCloseState::new())
+        .manage(SettingsWriteState::new())
+        .manage(log_filters)
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_sql::Builder::default().build())
         .plugin(tauri_plugin_store::Builder::default().build())
@@ -1951,42 +6572,178 @@ pub fn run() {
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             Some(vec!["--minimized"]),
         ))
-        .plugin(tauri_plugin_log::Builder::default().build())
+        .plugin(
+            tauri_plugin_log::Builder::default()
+                .filter(move |metadata| {
+                    plugin_filters
+                        .read()
+                        .map_or(true, |filters| filters.enabled(metadata))
+                })
+                .build(),
+        )
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::ThemeChanged(theme) => {
+                log::debug!("Window theme changed: {theme:?}");
+                refresh_tray_icon(window.app_handle());
+            }
+            // The debounced save checks `lock_window_size`, so a drag doesn't
+            // read the store on every event.
+            tauri::WindowEvent::Resized(size) if window.label() == "main" && size.width > 0 => {
+                let scale = window.scale_factor().unwrap_or(1.0);
+                schedule_window_size_save(window.app_handle(), *size, scale);
+            }
+            tauri::WindowEvent::Focused(false) if window.label() == "main" => {
+                handle_main_focus_lost(window.app_handle());
+            }
+            tauri::WindowEvent::CloseRequested { api, .. } if window.label() == "main" => {
+                // Closing the overlay never destroys it; it hides or quits the app.
+                api.prevent_close();
+                let app = window.app_handle();
+                let settings = load_app_settings(app).unwrap_or_default();
+                if settings.close_button_behavior == "quit" {
+                    log::info!("Main window close requested; quitting");
+                    request_quit(app);
+                } else {
+                    log::debug!("Main window close requested; hiding");
+                    let _ = window.hide();
+                }
+            }
+            _ => {}
+        })
         .invoke_handler(tauri::generate_handler![
             init_stats_db,
             get_format_stats,
             increment_format_usage,
             get_settings,
             save_settings,
+            set_default_format,
+            set_window_opacity,
+            set_accent_color,
+            set_window_size,
+            should_show_onboarding,
+            complete_onboarding,
+            self_test,
+            get_runtime_info,
+            test_llm_connection,
+            warm_llm,
+            set_llm_key,
+            has_llm_key,
+            clear_llm_key,
+            set_last_view,
+            open_settings_file,
+            reload_settings,
+            open_help,
+            update_setting,
+            validate_settings,
             check_for_updates,
+            is_online,
+            test_notification,
             install_update,
+            get_update_cache_info,
+            clear_update_cache,
+            test_updater,
             toggle_autostart,
             is_autostart_enabled,
             reload_global_shortcuts,
             debug_store_location,
+            get_diagnostics,
+            list_active_tasks,
             get_time_parser_config,
             parse_time_with_local_service,
             restart_time_parser_service,
             get_local_slm_status,
             start_local_slm,
             stop_local_slm,
+            preview_in_timezone,
+            get_timezone_info,
+            parse_relative,
+            parse_time,
+            suggest_completions,
+            cancel_parse,
+            parse_time_batch,
+            next_occurrence,
+            parse_range,
+            boundary,
+            describe_formats,
+            verify_hotkey_registered,
+            is_fullscreen_active,
+            queue_show_after_fullscreen,
+            normalize_hotkey,
+            show_hotkey_alias_status,
+            list_parse_locales,
+            duration_between,
+            list_monitors,
+            compact_database,
+            backup_data,
+            restore_data,
+            take_pending_deep_link,
+            take_pending_permalink,
+            make_permalink,
+            factory_reset,
+            is_in_quiet_hours,
+            get_parse_latency_stats,
+            get_recent_errors,
+            suspend_auto_close,
+            reformat_timestamp,
+            timestamp_now,
+            timestamp_in,
+            preview_relative,
+            render_template,
+            is_default_scheme_handler,
+            enable_tray,
+            disable_tray,
+            quit_app,
+            register_scheme_handler,
+            set_tray_icon_variant,
+            force_quit,
+            add_conversion,
+            get_recent_conversions,
+            export_history_markdown,
+            clear_conversions,
+            save_snippet,
+            list_snippets,
+            delete_snippet,
+            format_template,
+            to_code_block,
+            set_log_level,
+            from_epoch,
+            extract_timestamps,
+            next_format,
+            prev_format,
+            copy_timestamp,
+            copy_all_formats,
+            cancel_auto_dismiss,
         ])
-        .setup(|app| {
+        .setup(move |app| {
             // Initialize logging
+            apply_log_filters(
+                app.handle(),
+                &load_app_settings(app.handle()).unwrap_or_default(),
+            );
             log::info!("HammerOverlay starting up...");
             log::info!("Application version: {}", env!("CARGO_PKG_VERSION"));
 
-            // Set up system tray
-            if let Err(e) = setup_system_tray(app.handle()) {
-                log::error!("Failed to setup system tray: {e}");
-                eprintln!("Failed to setup system tray: {e}");
+            // Set up system tray. Without the tray or the hotkey there would be
+            // no way to reach the app, so the tray stays in that case.
+            let tray_settings = load_app_settings(app.handle()).unwrap_or_default();
+            if tray_settings.show_tray_icon || !tray_settings.hotkey_enabled {
+                if !tray_settings.show_tray_icon {
+                    log::warn!("Tray icon is hidden but the hotkey is disabled; showing the tray");
+                }
+                if let Err(e) = setup_system_tray(app.handle()) {
+                    report_error(
+                        app.handle(),
+                        AppError::Internal(format!("Failed to setup system tray: {e}")),
+                    );
+                    eprintln!("Failed to setup system tray: {e}");
+                }
+            } else {
+                log::info!("System tray disabled in settings");
             }
 
             // Set up global shortcuts
-            if let Err(e) = setup_global_shortcuts(app.handle()) {
-                log::error!("Failed to setup global shortcuts: {e}");
-                eprintln!("Failed to setup global shortcuts: {e}");
-            }
+            schedule_global_shortcuts(app.handle());
+            start_hotkey_watchdog(app.handle());
 
             if let Ok(settings) = load_app_settings(app.handle()) {
                 if settings.local_slm_enabled && settings.local_slm_auto_start {
@@ -1996,9 +6753,22 @@ pub fn run() {
 
             start_time_parser_service(app.handle());
 
+            let clock_settings = load_app_settings(app.handle()).unwrap_or_default();
+            match (debug_clock(&clock_settings), clock_settings.debug_fixed_now) {
+                (Some(fixed_now), _) => log::warn!(
+                    "debug_fixed_now is set; parsing and formatting treat {fixed_now} as now"
+                ),
+                (None, Some(_)) => log::warn!(
+                    "debug_fixed_now is set but ignored by this release build. Set HAMMEROVERLAY_ALLOW_DEBUG_CLOCK=1 to opt in."
+                ),
+                (None, None) => {}
+            }
+            sync_debug_clock(app.handle(), &clock_settings);
+
             // Initialize auto-start based on user settings
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
+                let _task = track_task(&app_handle, "autostart-sync", None);
                 if let Ok(settings) = get_settings(app_handle.clone()).await {
                     if settings.auto_start {
                         if let Err(e) = toggle_autostart(app_handle, true).await {
@@ -2013,17 +6783,174 @@ pub fn run() {
             // Single instance check completed during app initialization
             log::debug!("Single instance enforcement active");
 
-            // Hide window by default (start in system tray)
-            if let Some(window) = app.get_webview_window("main") {
+            // Hide window by default (start in system tray), except on first
+            // run where the welcome flow needs to be seen.
+            let onboarding = load_app_settings(app.handle())
+                .is_ok_and(|settings| !settings.has_completed_onboarding);
+            if onboarding {
+                log::info!("First run; showing onboarding");
+                show_main_window(app.handle());
+                let _ = app.emit("show-onboarding", ());
+            } else if let Some(window) = app.get_webview_window("main") {
                 log::info!("Hiding main window on startup");
                 let _ = window.hide();
             } else {
                 log::warn!("Main window not found during startup");
             }
 
-            log::info!("HammerOverlay startup completed successfully");
+            // The plugin reads the launch link from argv on Windows and Linux
+            // and from the open-URL Apple event on macOS.
+            let launch_link = app
+                .deep_link()
+                .get_current()
+                .ok()
+                .flatten()
+                .and_then(|urls| urls.into_iter().next())
+                .map(String::from)
+                .or(launch_link);
+            if let Some(link) = launch_link {
+                handle_deep_link(app.handle(), link);
+            }
+            // Links opened while running. On Windows and Linux those start a
+            // second process, which hands them over through `launches`.
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    handle_deep_link(&handle, url.to_string());
+                }
+            });
+            if let Some(launches) = launches {
+                let handle = app.handle().clone();
+                launches.spawn(move |link| match link {
+                    Some(link) => handle_deep_link(&handle, link),
+                    None => show_main_window(&handle),
+                });
+            }
+
+            let runtime = app.state::<RuntimeState>();
+            let setup_ms = runtime.started.elapsed().as_millis() as u64;
+            if let Ok(mut slot) = runtime.setup_ms.lock() {
+                *slot = Some(setup_ms);
+            }
+            log::info!("HammerOverlay startup completed successfully in {setup_ms} ms");
             Ok(())
         })
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A real stats DB and a clipboard that only remembers what it was given.
+    struct RecordingSink {
+        connection: rusqlite::Connection,
+        clipboard: Vec<String>,
+    }
+
+    impl ConversionSink for RecordingSink {
+        fn write_clipboard(&mut self, text: &str) -> Result<(), String> {
+            self.clipboard.push(text.to_string());
+            Ok(())
+        }
+
+        fn record(&mut self, input: &str, unix: i64, format: &str) -> Result<(), AppError> {
+            record_conversion(&self.connection, input, unix, format)
+                .map(|_| ())
+                .map_err(AppError::Db)
+        }
+    }
+
+    #[test]
+    fn parsing_leaves_stats_and_clipboard_unchanged() {
+        let path = std::env::temp_dir().join(format!(
+            "hammer-overlay-dry-run-test-{}.db",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let mut sink = RecordingSink {
+            connection: db::open(&path).unwrap(),
+            clipboard: Vec::new(),
+        };
+        db::add_conversion(
+            &sink.connection,
+            "tomorrow 3pm",
+            1_710_594_000,
+            "F",
+            1_710_512_400,
+        )
+        .unwrap();
+        let count =
+            |sink: &RecordingSink| db::count_conversions_since(&sink.connection, 0).unwrap();
+        let before = count(&sink);
+
+        let settings = AppSettings::default();
+        let zone = chrono_tz::Tz::America__New_York;
+        let reference = timezone::datetime_from_unix(1_710_512_400).unwrap();
+        let parse = |input: &str| {
+            let interpretation =
+                deterministic_interpretation(input, zone, reference, &settings).unwrap();
+            ParsedTime::from_interpretation(&interpretation, zone, false).unwrap()
+        };
+        for _ in 0..500 {
+            for input in ["tomorrow 3pm", "in 2 hours", "at 5", "march 15"] {
+                assert!(settle_parse(&mut sink, input, &parse(input), None).is_none());
+            }
+        }
+        assert_eq!(count(&sink), before);
+        assert!(sink.clipboard.is_empty());
+
+        // A committed parse under auto_copy_on_parse is the one that writes.
+        let copied = settle_parse(&mut sink, "tomorrow 3pm", &parse("tomorrow 3pm"), Some("F"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(sink.clipboard, [copied]);
+        assert_eq!(count(&sink), before + 1);
+        drop(sink);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn hiding_tray_and_hotkey_is_an_issue() {
+        let flagged = |settings: &AppSettings| {
+            settings_issues(settings)
+                .iter()
+                .any(|issue| issue.field == "show_tray_icon")
+        };
+        let mut settings = AppSettings {
+            show_tray_icon: false,
+            hotkey_enabled: false,
+            ..AppSettings::default()
+        };
+        assert!(flagged(&settings));
+        settings.show_hotkey_aliases = vec!["Ctrl+Alt+T".to_string()];
+        assert!(flagged(&settings));
+        settings.hotkey_enabled = true;
+        assert!(!flagged(&settings));
+        settings.hotkey_enabled = false;
+        settings.show_tray_icon = true;
+        assert!(!flagged(&settings));
+    }
+
+    #[test]
+    fn rounding_leaves_defaulted_times_alone() {
+        let settings = AppSettings {
+            round_to_minutes: 15,
+            default_time_for_date_only: "09:07".to_string(),
+            ..AppSettings::default()
+        };
+        let zone = chrono_tz::Tz::UTC;
+        // Monday 2024-03-11 10:00 UTC.
+        let reference = timezone::datetime_from_unix(1_710_151_200).unwrap();
+        let best = |input| {
+            deterministic_interpretation(input, zone, reference, &settings)
+                .unwrap()
+                .best()
+                .epoch
+        };
+        // 2024-03-15 09:07 and 09:00 UTC.
+        assert_eq!(best("march 15"), 1_710_493_620);
+        assert_eq!(best("march 15 9:07am"), 1_710_493_200);
+    }
+}
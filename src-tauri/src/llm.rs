@@ -0,0 +1,132 @@
+//! User-configured LLM provider for the parser service's fallback path.
+//!
+//! The local parser service makes the actual LLM calls; this module turns
+//! the `llm` setting into the environment the service reads at startup and
+//! checks that the endpoint answers.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub const PROVIDERS: [&str; 2] = ["openai", "openai_compatible"];
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmConfig {
+    pub provider: String, // "openai", "openai_compatible"
+    /// Required for `openai_compatible`; `openai` defaults to the public API.
+    pub base_url: String,
+    pub model: String,
+    /// Environment variable holding the API key, used when no key was saved
+    /// to the OS keychain with `set_llm_key`. Keys never go in settings.
+    pub api_key_env: Option<String>,
+}
+
+impl LlmConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !PROVIDERS.contains(&self.provider.as_str()) {
+            return Err(format!(
+                "Invalid LLM provider '{}'; expected one of {}",
+                self.provider,
+                PROVIDERS.join(", ")
+            ));
+        }
+        if self.model.trim().is_empty() {
+            return Err("LLM model must not be empty".to_string());
+        }
+        if self.provider == "openai_compatible" && self.base_url.trim().is_empty() {
+            return Err("An openai_compatible provider needs a base URL".to_string());
+        }
+        if !self.base_url.trim().is_empty() {
+            let url = url::Url::parse(self.base_url.trim())
+                .map_err(|e| format!("Invalid LLM base URL: {e}"))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err("LLM base URL must use http or https".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn base_url(&self) -> String {
+        let trimmed = self.base_url.trim().trim_end_matches('/');
+        if trimmed.is_empty() {
+            OPENAI_BASE_URL.to_string()
+        } else {
+            trimmed.to_string()
+        }
+    }
+
+    /// The key named by `api_key_env`, if that variable is set.
+    pub fn env_api_key(&self) -> Option<String> {
+        let name = self.api_key_env.as_deref()?.trim();
+        std::env::var(name)
+            .ok()
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+    }
+}
+
+/// Point the parser service at `config`. Takes precedence over the
+/// `local_slm_*` endpoint, so call it after `apply_local_slm_api_env`.
+pub fn apply_env(command: &mut Command, config: &LlmConfig, api_key: Option<&str>) {
+    match config.provider.as_str() {
+        "openai" => {
+            command
+                .env("OPENAI_BASE_URL", config.base_url())
+                .env("OPENAI_MODEL", config.model.trim());
+            if let Some(key) = api_key {
+                command.env("OPENAI_API_KEY", key);
+            }
+        }
+        _ => {
+            command
+                .env("TEMPORAL_FEATURE_PLAN_IR", "true")
+                .env("TEMPORAL_PLAN_IR_ENDPOINT_BASE_URL", config.base_url())
+                .env("TEMPORAL_PLAN_IR_ENDPOINT_MODEL", config.model.trim())
+                .env("TEMPORAL_PLAN_IR_ENDPOINT_API", "chat")
+                .env("TEMPORAL_PLAN_IR_ENDPOINT_PROMPT_FORMAT", "chat");
+            if let Some(key) = api_key {
+                command.env("TEMPORAL_PLAN_IR_ENDPOINT_API_KEY", key);
+            }
+        }
+    }
+}
+
+/// One client for every call, so its connection pool outlives a request.
+fn client() -> Result<&'static reqwest::Client, String> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    if rustls::crypto::CryptoProvider::get_default().is_none() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+    }
+    let client = reqwest::Client::builder()
+        .timeout(CONNECTION_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+/// List the endpoint's models, the cheapest call every OpenAI-style API
+/// answers. `Ok(false)` means the endpoint answered with a non-success
+/// status; `Err` means it could not be reached at all.
+pub async fn test_connection(config: &LlmConfig, api_key: Option<&str>) -> Result<bool, String> {
+    config.validate()?;
+    let mut request = client()?.get(format!("{}/models", config.base_url()));
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+    let response = request
+        .send()
+        .await
+        // Strip the URL so nothing request-specific lands in the error.
+        .map_err(|e| format!("Failed to reach LLM endpoint: {}", e.without_url()))?;
+    let status = response.status();
+    log::info!("LLM connection test answered HTTP {}", status.as_u16());
+    Ok(status.is_success())
+}
@@ -0,0 +1,211 @@
+//! Keyword tables that let the deterministic grammar read other languages.
+//!
+//! The grammar itself only speaks English. A locale is a list of phrases
+//! mapped onto the English words the grammar already knows, so adding a
+//! language means adding a table here, not touching the parser.
+
+use serde::Serialize;
+
+pub struct ParseLocale {
+    pub code: &'static str,
+    pub name: &'static str,
+    /// Lowercase phrase in the locale, English replacement. The first match
+    /// wins, so list multi-word phrases before their single-word prefixes.
+    pub phrases: &'static [(&'static str, &'static str)],
+}
+
+pub const ENGLISH: ParseLocale = ParseLocale {
+    code: "en",
+    name: "English",
+    phrases: &[],
+};
+
+pub const GERMAN: ParseLocale = ParseLocale {
+    code: "de",
+    name: "Deutsch",
+    phrases: &[
+        ("heute abend", "tonight"),
+        ("heiligabend", "christmas eve"),
+        ("weihnachten", "christmas"),
+        ("silvester", "new year's eve"),
+        ("neujahr", "new year's day"),
+        ("heute nacht", "tonight"),
+        ("heute", "today"),
+        ("morgen", "tomorrow"),
+        ("gestern", "yesterday"),
+        ("jetzt", "now"),
+        ("nächsten", "next"),
+        ("nächste", "next"),
+        ("nächster", "next"),
+        ("naechsten", "next"),
+        ("kommenden", "next"),
+        ("kommende", "next"),
+        ("diesen", "this"),
+        ("diese", "this"),
+        ("dieser", "this"),
+        ("dieses", "this"),
+        ("wochenende", "weekend"),
+        ("montag", "monday"),
+        ("dienstag", "tuesday"),
+        ("mittwoch", "wednesday"),
+        ("donnerstag", "thursday"),
+        ("freitag", "friday"),
+        ("samstag", "saturday"),
+        ("sonnabend", "saturday"),
+        ("sonntag", "sunday"),
+        ("januar", "january"),
+        ("jänner", "january"),
+        ("februar", "february"),
+        ("märz", "march"),
+        ("maerz", "march"),
+        ("april", "april"),
+        ("mai", "may"),
+        ("juni", "june"),
+        ("juli", "july"),
+        ("august", "august"),
+        ("september", "september"),
+        ("oktober", "october"),
+        ("november", "november"),
+        ("dezember", "december"),
+        ("um", "at"),
+        ("am", "on"),
+        ("gegen", "around"),
+        ("uhr", "o'clock"),
+        ("und", "and"),
+        ("einer", "a"),
+        ("einem", "a"),
+        ("einen", "a"),
+        ("eine", "a"),
+        ("ein", "a"),
+        ("sekunde", "second"),
+        ("sekunden", "seconds"),
+        ("minute", "minute"),
+        ("minuten", "minutes"),
+        ("stunde", "hour"),
+        ("stunden", "hours"),
+        ("tag", "day"),
+        ("tage", "days"),
+        ("tagen", "days"),
+        ("woche", "week"),
+        ("wochen", "weeks"),
+        ("monat", "month"),
+        ("monate", "months"),
+        ("monaten", "months"),
+        ("jahr", "year"),
+        ("jahre", "years"),
+        ("jahren", "years"),
+    ],
+};
+
+pub const LOCALES: &[&ParseLocale] = &[&ENGLISH, &GERMAN];
+
+/// What the settings picker shows for a locale.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocaleOption {
+    pub code: &'static str,
+    pub name: &'static str,
+}
+
+pub fn options() -> Vec<LocaleOption> {
+    LOCALES
+        .iter()
+        .map(|locale| LocaleOption {
+            code: locale.code,
+            name: locale.name,
+        })
+        .collect()
+}
+
+pub fn find(code: &str) -> Option<&'static ParseLocale> {
+    LOCALES
+        .iter()
+        .copied()
+        .find(|locale| locale.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// Rewrite already-normalized input into the English the grammar reads.
+/// Returns `None` when nothing was translated.
+pub fn translate(normalized: &str, locale: &ParseLocale) -> Option<String> {
+    if locale.phrases.is_empty() {
+        return None;
+    }
+    let words = normalized.split_whitespace().collect::<Vec<_>>();
+    let mut output = Vec::with_capacity(words.len());
+    let mut changed = false;
+    let mut index = 0;
+    'words: while index < words.len() {
+        for (phrase, english) in locale.phrases {
+            let length = phrase.split_whitespace().count();
+            if index + length <= words.len() && words[index..index + length].join(" ") == *phrase {
+                output.push((*english).to_string());
+                changed |= *phrase != *english;
+                index += length;
+                continue 'words;
+            }
+        }
+        // German writes ordinals as "15." ("am 15. märz").
+        let word = words[index];
+        match word.strip_suffix('.') {
+            Some(number) if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) => {
+                output.push(number.to_string());
+                changed = true;
+            }
+            _ => output.push(word.to_string()),
+        }
+        index += 1;
+    }
+    changed.then(|| output.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deterministic::{parse_with_locale, ParseRules};
+    use chrono::{DateTime, TimeZone};
+    use chrono_tz::Tz;
+
+    fn berlin(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Tz> {
+        Tz::Europe__Berlin
+            .with_ymd_and_hms(2024, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn german(input: &str, now: &DateTime<Tz>) -> DateTime<Tz> {
+        let epoch = parse_with_locale(input, now, &GERMAN, &ParseRules::default())
+            .unwrap_or_else(|e| panic!("{input}: {e}"))
+            .best()
+            .epoch;
+        DateTime::from_timestamp(epoch, 0)
+            .unwrap()
+            .with_timezone(&now.timezone())
+    }
+
+    #[test]
+    fn german_weekdays_translate() {
+        assert_eq!(
+            translate("nächsten montag um 14:30", &GERMAN).as_deref(),
+            Some("next monday at 14:30")
+        );
+        assert_eq!(translate("sonnabend", &GERMAN).as_deref(), Some("saturday"));
+        assert_eq!(translate("friday", &GERMAN), None);
+        assert_eq!(translate("montag", &ENGLISH), None);
+    }
+
+    #[test]
+    fn german_weekdays_resolve_like_english() {
+        // Friday 2024-03-15 10:00.
+        let friday = berlin(3, 15, 10, 0);
+        assert_eq!(german("Montag 9 Uhr", &friday), berlin(3, 18, 9, 0));
+        assert_eq!(german("Freitag 18 Uhr", &friday), berlin(3, 15, 18, 0));
+        assert_eq!(
+            german("nächsten Montag um 14:30", &friday),
+            berlin(3, 18, 14, 30)
+        );
+        assert_eq!(german("nächsten Freitag", &friday), berlin(3, 22, 9, 0));
+        assert_eq!(german("diesen Freitag", &friday), berlin(3, 15, 9, 0));
+        assert_eq!(german("Samstag um 20:00", &friday), berlin(3, 16, 20, 0));
+        assert_eq!(german("kommenden Sonntag", &friday), berlin(3, 24, 9, 0));
+        // English still works with the German locale selected.
+        assert_eq!(german("monday 9am", &friday), berlin(3, 18, 9, 0));
+    }
+}
@@ -0,0 +1,87 @@
+//! Per-target log levels for the `log_filters` setting.
+//!
+//! A target matches its own records and those of its submodules, so
+//! `hammer_overlay_lib` also covers `hammer_overlay_lib::db`. The most
+//! specific match wins; `*` sets the level for everything unmatched.
+
+use log::{LevelFilter, Metadata};
+use std::collections::HashMap;
+
+/// Key for the level of targets no other entry matches.
+pub const FALLBACK_TARGET: &str = "*";
+pub const LEVEL_NAMES: [&str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
+
+pub fn parse_level(name: &str) -> Result<LevelFilter, String> {
+    name.trim().parse().map_err(|_| {
+        format!(
+            "Invalid log level '{name}'; expected one of {}",
+            LEVEL_NAMES.join(", ")
+        )
+    })
+}
+
+pub fn validate_target(target: &str) -> Result<(), String> {
+    if target == FALLBACK_TARGET
+        || (!target.is_empty()
+            && target.split("::").all(|part| {
+                !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+            }))
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid log target '{target}'; expected a module path like hammer_overlay_lib::db or {FALLBACK_TARGET}"
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilters {
+    fallback: LevelFilter,
+    // Longest target first, so the first match is the most specific.
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogFilters {
+    /// Everything passes, as before any filters were configured.
+    fn default() -> Self {
+        Self {
+            fallback: LevelFilter::Trace,
+            targets: Vec::new(),
+        }
+    }
+}
+
+impl LogFilters {
+    pub fn from_settings(filters: &HashMap<String, String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        for (target, level) in filters {
+            validate_target(target)?;
+            let level = parse_level(level)?;
+            if target == FALLBACK_TARGET {
+                parsed.fallback = level;
+            } else {
+                parsed.targets.push((target.clone(), level));
+            }
+        }
+        parsed
+            .targets
+            .sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.0.cmp(&b.0)));
+        Ok(parsed)
+    }
+
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .find(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.fallback, |(_, level)| *level)
+    }
+
+    pub fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+}
@@ -0,0 +1,79 @@
+//! Native desktop notifications through each OS's own tooling.
+//!
+//! Title and body travel as process arguments or environment variables,
+//! never spliced into a script, so the text cannot inject commands.
+
+#[cfg(windows)]
+use crate::CREATE_NO_WINDOW;
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+use std::process::Command;
+
+fn run(mut command: Command) -> Result<(), String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to show notification: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to show notification: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn show(title: &str, body: &str) -> Result<(), String> {
+    let mut command = Command::new("notify-send");
+    command
+        .args(["--app-name", "HammerOverlay", "--expire-time", "3000"])
+        .arg(title)
+        .arg(body);
+    run(command)
+}
+
+#[cfg(target_os = "macos")]
+pub fn show(title: &str, body: &str) -> Result<(), String> {
+    let mut command = Command::new("osascript");
+    command
+        .args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display notification (item 2 of argv) with title (item 1 of argv)",
+            "-e",
+            "end run",
+        ])
+        .arg(title)
+        .arg(body);
+    run(command)
+}
+
+#[cfg(windows)]
+const TOAST_SCRIPT: &str = r#"
+$null = [Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime]
+$template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $template.GetElementsByTagName('text')
+$null = $text.Item(0).AppendChild($template.CreateTextNode($env:HAMMER_NOTIFY_TITLE))
+$null = $text.Item(1).AppendChild($template.CreateTextNode($env:HAMMER_NOTIFY_BODY))
+$toast = [Windows.UI.Notifications.ToastNotification]::new($template)
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:HAMMER_NOTIFY_APP).Show($toast)
+"#;
+
+#[cfg(windows)]
+pub fn show(title: &str, body: &str) -> Result<(), String> {
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", TOAST_SCRIPT])
+        .env("HAMMER_NOTIFY_TITLE", title)
+        .env("HAMMER_NOTIFY_BODY", body)
+        .env("HAMMER_NOTIFY_APP", "com.hammer-overlay.app")
+        .creation_flags(CREATE_NO_WINDOW);
+    run(command)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn show(_title: &str, _body: &str) -> Result<(), String> {
+    Err("Notifications are not supported on this platform".to_string())
+}
@@ -0,0 +1,62 @@
+//! Recent parse results, kept in memory only.
+//!
+//! Keys carry the reference minute, so a relative phrase like "in 2 hours"
+//! misses once the minute rolls over; the age limit then drops it.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Entries beyond this are evicted least-recently-used first.
+pub const CAPACITY: usize = 64;
+/// Entries older than this are never returned.
+pub const MAX_AGE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    pub input: String,
+    pub reference_minute: i64,
+    pub zone: String,
+}
+
+#[derive(Debug)]
+pub struct ParseCache<V> {
+    // Least recently used at the front.
+    entries: VecDeque<(CacheKey, Instant, V)>,
+}
+
+impl<V> Default for ParseCache<V> {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+        }
+    }
+}
+
+impl<V: Clone> ParseCache<V> {
+    pub fn get(&mut self, key: &CacheKey, now: Instant) -> Option<V> {
+        self.evict_expired(now);
+        let index = self.entries.iter().position(|(k, _, _)| k == key)?;
+        let entry = self.entries.remove(index)?;
+        let value = entry.2.clone();
+        self.entries.push_back(entry);
+        Some(value)
+    }
+
+    pub fn insert(&mut self, key: CacheKey, value: V, now: Instant) {
+        self.evict_expired(now);
+        self.entries.retain(|(k, _, _)| *k != key);
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, now, value));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn evict_expired(&mut self, now: Instant) {
+        self.entries
+            .retain(|(_, inserted, _)| now.saturating_duration_since(*inserted) < MAX_AGE);
+    }
+}
@@ -0,0 +1,98 @@
+//! Bookkeeping for background threads and tasks, kept in memory only, so
+//! diagnostics can show what is running and spot a wedged periodic task.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A periodic task is stalled once it misses this many ticks in a row.
+pub const STALL_MISSED_TICKS: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    pub id: u64,
+    pub name: String,
+    pub started_at_ms: i64,
+    pub last_tick_ms: i64,
+    pub ticks: u64,
+    /// Expected gap between ticks; unset for one-shot tasks.
+    pub interval_ms: Option<u64>,
+    /// Running tasks with this name, counting this one. Above 1 usually
+    /// means a duplicate.
+    pub instances: usize,
+    pub stalled: bool,
+}
+
+#[derive(Debug)]
+struct Entry {
+    name: &'static str,
+    started_at_ms: i64,
+    last_tick_ms: i64,
+    ticks: u64,
+    interval: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    tasks: HashMap<u64, Entry>,
+    next_id: u64,
+}
+
+impl TaskRegistry {
+    pub fn start(&mut self, name: &'static str, interval: Option<Duration>, now_ms: i64) -> u64 {
+        self.next_id += 1;
+        self.tasks.insert(
+            self.next_id,
+            Entry {
+                name,
+                started_at_ms: now_ms,
+                last_tick_ms: now_ms,
+                ticks: 0,
+                interval,
+            },
+        );
+        self.next_id
+    }
+
+    pub fn tick(&mut self, id: u64, now_ms: i64) {
+        if let Some(entry) = self.tasks.get_mut(&id) {
+            entry.last_tick_ms = now_ms;
+            entry.ticks += 1;
+        }
+    }
+
+    pub fn finish(&mut self, id: u64) {
+        self.tasks.remove(&id);
+    }
+
+    /// Running tasks, oldest first.
+    pub fn list(&self, now_ms: i64) -> Vec<TaskInfo> {
+        let mut infos = self
+            .tasks
+            .iter()
+            .map(|(&id, entry)| {
+                let interval_ms = entry.interval.map(|interval| interval.as_millis() as u64);
+                TaskInfo {
+                    id,
+                    name: entry.name.to_string(),
+                    started_at_ms: entry.started_at_ms,
+                    last_tick_ms: entry.last_tick_ms,
+                    ticks: entry.ticks,
+                    interval_ms,
+                    instances: self
+                        .tasks
+                        .values()
+                        .filter(|other| other.name == entry.name)
+                        .count(),
+                    stalled: interval_ms.is_some_and(|interval_ms| {
+                        now_ms - entry.last_tick_ms
+                            > interval_ms as i64 * i64::from(STALL_MISSED_TICKS)
+                    }),
+                }
+            })
+            .collect::<Vec<_>>();
+        infos.sort_by_key(|info| info.id);
+        infos
+    }
+}
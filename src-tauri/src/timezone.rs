@@ -0,0 +1,442 @@
+use chrono::{
+    DateTime, Datelike, Months, NaiveDate, NaiveTime, Offset, TimeZone, Timelike, Utc, Weekday,
+};
+use chrono_tz::{OffsetComponents, Tz};
+use serde::Serialize;
+
+/// Resolve an IANA zone name such as `Asia/Tokyo`.
+pub fn resolve_timezone(name: &str) -> Result<Tz, String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err("Timezone name is empty".to_string());
+    }
+    trimmed
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown IANA timezone: {trimmed}"))
+}
+
+/// The OS zone, if it names an IANA zone chrono-tz knows.
+fn detected_system_timezone() -> Option<Tz> {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse::<Tz>().ok())
+}
+
+/// The OS zone, falling back to UTC when it can't be resolved.
+pub fn system_timezone() -> Tz {
+    detected_system_timezone().unwrap_or(Tz::UTC)
+}
+
+/// Resolve an optional zone name; absent, empty, or "system" means the OS zone.
+pub fn resolve_or_system(name: Option<&str>) -> Result<Tz, String> {
+    match name
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && !name.eq_ignore_ascii_case("system"))
+    {
+        Some(name) => resolve_timezone(name),
+        None => Ok(system_timezone()),
+    }
+}
+
+/// Which zone input is read in, and its offset at one moment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimezoneInfo {
+    /// IANA name, or "system" when the OS zone could not be resolved and UTC
+    /// stands in for it.
+    pub zone: String,
+    /// The zone came from the OS rather than the caller.
+    pub from_system: bool,
+    pub abbreviation: String,
+    /// Offset from UTC, such as "+05:30".
+    pub utc_offset: String,
+    pub utc_offset_seconds: i32,
+    pub is_dst: bool,
+}
+
+/// Describe the zone `resolve_or_system(name)` picks, as it stands at `unix`.
+pub fn timezone_info(name: Option<&str>, unix: i64) -> Result<TimezoneInfo, String> {
+    let requested = name
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && !name.eq_ignore_ascii_case("system"));
+    let (tz, zone) = match requested {
+        Some(name) => {
+            let tz = resolve_timezone(name)?;
+            (tz, tz.name().to_string())
+        }
+        None => match detected_system_timezone() {
+            Some(tz) => (tz, tz.name().to_string()),
+            None => (Tz::UTC, "system".to_string()),
+        },
+    };
+    let local = datetime_from_unix(unix)?.with_timezone(&tz);
+    let offset = local.offset();
+    let seconds = offset.fix().local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.unsigned_abs() / 60;
+    Ok(TimezoneInfo {
+        zone,
+        from_system: requested.is_none(),
+        abbreviation: local.format("%Z").to_string(),
+        utc_offset: format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60),
+        utc_offset_seconds: seconds,
+        is_dst: !offset.dst_offset().is_zero(),
+    })
+}
+
+pub fn now_unix() -> i64 {
+    Utc::now().timestamp()
+}
+
+pub fn datetime_from_unix(unix: i64) -> Result<DateTime<Utc>, String> {
+    DateTime::from_timestamp(unix, 0)
+        .ok_or_else(|| format!("Unix timestamp is out of range: {unix}"))
+}
+
+/// Human-readable rendering of an epoch in `tz`, used for previews only.
+/// The generated `<t:...>` string is always zone-independent.
+pub fn render_preview(unix: i64, tz: Tz, use_24h: bool) -> Result<String, String> {
+    let local = datetime_from_unix(unix)?.with_timezone(&tz);
+    let format = if use_24h {
+        "%A, %B %-d, %Y %H:%M %Z (UTC%:z)"
+    } else {
+        "%A, %B %-d, %Y %-I:%M %p %Z (UTC%:z)"
+    };
+    Ok(local.format(format).to_string())
+}
+
+/// Restatement of a parsed moment for an "I understood: ..." confirmation.
+/// A time the input never gave is marked as an assumption rather than
+/// passed off as what the user said.
+pub fn canonical_text(
+    unix: i64,
+    tz: Tz,
+    use_24h: bool,
+    had_explicit_time: bool,
+) -> Result<String, String> {
+    let local = datetime_from_unix(unix)?.with_timezone(&tz);
+    let time = if use_24h { "%H:%M" } else { "%-I:%M %p" };
+    let text = local
+        .format(&format!("%A, %B %-d, %Y at {time} %Z"))
+        .to_string();
+    Ok(if had_explicit_time {
+        text
+    } else {
+        format!("{text} (assumed; no time given)")
+    })
+}
+
+/// The OS locale as a BCP 47-ish tag ("en-US", "de_DE.UTF-8"), if known.
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    use windows::Win32::Globalization::GetUserDefaultLocaleName;
+
+    let mut buffer = [0u16; 85];
+    // SAFETY: the buffer is LOCALE_NAME_MAX_LENGTH wide, as the API requires.
+    let len = unsafe { GetUserDefaultLocaleName(&mut buffer) };
+    let len = usize::try_from(len).ok().filter(|len| *len > 1)?;
+    Some(String::from_utf16_lossy(&buffer[..len - 1]))
+}
+
+#[cfg(not(windows))]
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+}
+
+/// Whether the OS locale conventionally writes times as 24-hour. Unknown
+/// locales keep the 24-hour preview the app has always shown.
+pub fn locale_prefers_24h() -> bool {
+    const TWELVE_HOUR_REGIONS: &[&str] = &["US", "CA", "AU", "NZ", "PH", "IN", "PK", "EG", "SA"];
+    const TWELVE_HOUR_LANGUAGES: &[&str] = &["ko", "hi", "ar", "ur", "bn"];
+
+    let Some(locale) = system_locale() else {
+        return true;
+    };
+    let tag = locale.split(['.', '@']).next().unwrap_or_default();
+    let mut parts = tag.split(['-', '_']);
+    let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+    let region = parts
+        .find(|part| part.len() == 2)
+        .map(str::to_ascii_uppercase)
+        .unwrap_or_default();
+    !(TWELVE_HOUR_REGIONS.contains(&region.as_str())
+        || TWELVE_HOUR_LANGUAGES.contains(&language.as_str()))
+}
+
+/// Epoch of the most recent local midnight in `tz` at or before `unix`.
+pub fn start_of_day_unix(unix: i64, tz: Tz) -> Result<i64, String> {
+    day_start(
+        datetime_from_unix(unix)?.with_timezone(&tz).date_naive(),
+        tz,
+    )
+}
+
+fn day_start(date: NaiveDate, tz: Tz) -> Result<i64, String> {
+    let midnight = date
+        .and_hms_opt(0, 0, 0)
+        .ok_or_else(|| "Failed to compute local midnight".to_string())?;
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .map(|moment| moment.timestamp())
+        // Zones that skip midnight for DST start the day at the first valid instant.
+        .or_else(|| {
+            tz.from_local_datetime(&(midnight + chrono::Duration::hours(1)))
+                .earliest()
+                .map(|moment| moment.timestamp())
+        })
+        .ok_or_else(|| "Failed to compute local midnight".to_string())
+}
+
+/// Names `boundary` accepts. "end-of-*" is the last second before the next
+/// period starts.
+pub const BOUNDARY_KINDS: [&str; 9] = [
+    "start-of-next-hour",
+    "start-of-day",
+    "end-of-day",
+    "start-of-next-day",
+    "start-of-week",
+    "end-of-week",
+    "start-of-next-week",
+    "end-of-month",
+    "start-of-next-month",
+];
+
+/// Epoch of a calendar boundary relative to `unix` on `tz`'s wall clock, so
+/// days run midnight to midnight locally even when DST makes them 23 or 25
+/// hours long. Weeks start on `week_starts_on`.
+pub fn boundary(kind: &str, unix: i64, tz: Tz, week_starts_on: Weekday) -> Result<i64, String> {
+    let today = datetime_from_unix(unix)?.with_timezone(&tz).date_naive();
+    let days_into_week = i64::from(
+        (today.weekday().num_days_from_monday() + 7 - week_starts_on.num_days_from_monday()) % 7,
+    );
+    let shift = |days: i64| {
+        today
+            .checked_add_signed(chrono::Duration::days(days))
+            .ok_or_else(|| "Date is out of range".to_string())
+    };
+    let first_of_next_month = || {
+        today
+            .with_day(1)
+            .and_then(|first| first.checked_add_months(Months::new(1)))
+            .ok_or_else(|| "Date is out of range".to_string())
+    };
+    match kind {
+        "start-of-next-hour" => next_hour_start(unix, tz),
+        "start-of-day" => day_start(today, tz),
+        "end-of-day" => Ok(day_start(shift(1)?, tz)? - 1),
+        "start-of-next-day" => day_start(shift(1)?, tz),
+        "start-of-week" => day_start(shift(-days_into_week)?, tz),
+        "end-of-week" => Ok(day_start(shift(7 - days_into_week)?, tz)? - 1),
+        "start-of-next-week" => day_start(shift(7 - days_into_week)?, tz),
+        "end-of-month" => Ok(day_start(first_of_next_month()?, tz)? - 1),
+        "start-of-next-month" => day_start(first_of_next_month()?, tz),
+        _ => Err(format!(
+            "Unknown boundary '{kind}'; expected one of {}",
+            BOUNDARY_KINDS.join(", ")
+        )),
+    }
+}
+
+/// The first instant after `unix` that reads :00:00 locally. Every zone's
+/// offset is a whole number of quarter hours, so only those need checking.
+fn next_hour_start(unix: i64, tz: Tz) -> Result<i64, String> {
+    const QUARTER_HOUR: i64 = 15 * 60;
+    let first = (unix.div_euclid(QUARTER_HOUR) + 1) * QUARTER_HOUR;
+    (0..8)
+        .map(|step| first + step * QUARTER_HOUR)
+        .find(|&candidate| {
+            datetime_from_unix(candidate)
+                .is_ok_and(|moment| moment.with_timezone(&tz).minute() == 0)
+        })
+        .ok_or_else(|| "Failed to find the next hour".to_string())
+}
+
+/// Signed gap between two epochs, split into whole units.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DurationBreakdown {
+    /// `b - a` in seconds.
+    pub total_seconds: i64,
+    /// -1 when `b` is before `a`, 0 when equal, 1 otherwise.
+    pub sign: i8,
+    pub days: u64,
+    pub hours: u64,
+    pub minutes: u64,
+    pub seconds: u64,
+    /// Unsigned, e.g. "2 days, 3 hours, 15 minutes".
+    pub human: String,
+}
+
+fn plural(count: u64, unit: &str) -> String {
+    if count == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
+
+pub fn duration_between(a: i64, b: i64) -> DurationBreakdown {
+    let total_seconds = b.saturating_sub(a);
+    let magnitude = total_seconds.unsigned_abs();
+    let days = magnitude / 86_400;
+    let hours = magnitude % 86_400 / 3_600;
+    let minutes = magnitude % 3_600 / 60;
+    let seconds = magnitude % 60;
+
+    let mut parts = [(days, "day"), (hours, "hour"), (minutes, "minute")]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, unit)| plural(count, unit))
+        .collect::<Vec<_>>();
+    if parts.is_empty() {
+        parts.push(if seconds > 0 {
+            plural(seconds, "second")
+        } else {
+            plural(0, "minute")
+        });
+    }
+
+    DurationBreakdown {
+        total_seconds,
+        sign: total_seconds.signum() as i8,
+        days,
+        hours,
+        minutes,
+        seconds,
+        human: parts.join(", "),
+    }
+}
+
+/// Parse a 24-hour `HH:MM` wall-clock time.
+pub fn parse_clock(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time '{value}'; expected HH:MM"))
+}
+
+/// Whether `now` falls in `[start, end)`. A range whose end is earlier than its
+/// start wraps past midnight; equal ends cover nothing.
+pub fn in_quiet_hours(start: &str, end: &str, now: NaiveTime) -> Result<bool, String> {
+    let start = parse_clock(start)?;
+    let end = parse_clock(end)?;
+    Ok(if start <= end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_duration_keeps_magnitude_and_sign() {
+        // One day, hour, minute and second earlier.
+        let d = duration_between(100_000, 100_000 - 90_061);
+        assert_eq!(d.total_seconds, -90_061);
+        assert_eq!(d.sign, -1);
+        assert_eq!((d.days, d.hours, d.minutes, d.seconds), (1, 1, 1, 1));
+        assert_eq!(d.human, "1 day, 1 hour, 1 minute");
+        assert_eq!(duration_between(60, 30).human, "30 seconds");
+        assert_eq!(duration_between(60, 30).sign, -1);
+    }
+
+    #[test]
+    fn zero_duration() {
+        let d = duration_between(1_710_512_400, 1_710_512_400);
+        assert_eq!((d.total_seconds, d.sign), (0, 0));
+        assert_eq!((d.days, d.hours, d.minutes, d.seconds), (0, 0, 0, 0));
+        assert_eq!(d.human, "0 minutes");
+    }
+
+    #[test]
+    fn positive_duration() {
+        let d = duration_between(0, 2 * 86_400 + 7_200);
+        assert_eq!(d.sign, 1);
+        assert_eq!(d.human, "2 days, 2 hours");
+    }
+
+    #[test]
+    fn canonical_text_labels_a_defaulted_time_as_assumed() {
+        use crate::deterministic::{parse, ParseRules};
+        use chrono::TimeZone;
+
+        let tz = Tz::America__New_York;
+        let now = tz.with_ymd_and_hms(2024, 3, 11, 10, 0, 0).unwrap();
+        let rules = ParseRules::default();
+
+        let dated = parse("march 15", &now, &rules).unwrap().best();
+        assert_eq!(
+            canonical_text(dated.epoch, tz, false, dated.had_explicit_time).unwrap(),
+            "Friday, March 15, 2024 at 9:00 AM EDT (assumed; no time given)"
+        );
+        let timed = parse("march 15 3pm", &now, &rules).unwrap().best();
+        assert_eq!(
+            canonical_text(timed.epoch, tz, true, timed.had_explicit_time).unwrap(),
+            "Friday, March 15, 2024 at 15:00 EDT"
+        );
+    }
+
+    #[test]
+    fn day_boundaries_follow_the_wall_clock_across_dst() {
+        use chrono::TimeZone;
+
+        let tz = Tz::America__New_York;
+        let at = |m: u32, d: u32, h: u32| {
+            tz.with_ymd_and_hms(2024, m, d, h, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+        let day = |kind: &str, unix: i64| boundary(kind, unix, tz, Weekday::Mon).unwrap();
+
+        // March 10 2024 skips 2:00-3:00, so the day is 23 hours long.
+        let spring = at(3, 10, 12);
+        assert_eq!(day("start-of-day", spring), at(3, 10, 0));
+        assert_eq!(day("start-of-next-day", spring), at(3, 11, 0));
+        assert_eq!(day("end-of-day", spring), at(3, 11, 0) - 1);
+        assert_eq!(
+            day("start-of-next-day", spring) - day("start-of-day", spring),
+            23 * 3600
+        );
+        assert_eq!(day("start-of-next-hour", at(3, 10, 1)), at(3, 10, 3));
+
+        // November 3 2024 repeats 1:00-2:00, so the day is 25 hours long.
+        let fall = at(11, 3, 12);
+        assert_eq!(day("start-of-day", fall), at(11, 3, 0));
+        assert_eq!(day("end-of-day", fall), at(11, 4, 0) - 1);
+        assert_eq!(
+            day("start-of-next-day", fall) - day("start-of-day", fall),
+            25 * 3600
+        );
+        // Both 1:00 hours start on the hour.
+        let first_one_am = at(11, 3, 0) + 3600;
+        assert_eq!(day("start-of-next-hour", first_one_am), first_one_am + 3600);
+        assert_eq!(
+            day("start-of-next-hour", first_one_am + 3600),
+            first_one_am + 7200
+        );
+    }
+
+    #[test]
+    fn day_starts_when_midnight_is_skipped() {
+        use chrono::TimeZone;
+
+        // Santiago moved its clocks from 0:00 to 1:00 on September 8 2024.
+        let tz = Tz::America__Santiago;
+        let noon = tz
+            .with_ymd_and_hms(2024, 9, 8, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        let one_am = tz
+            .with_ymd_and_hms(2024, 9, 8, 1, 0, 0)
+            .unwrap()
+            .timestamp();
+        assert_eq!(
+            boundary("start-of-day", noon, tz, Weekday::Mon).unwrap(),
+            one_am
+        );
+    }
+}
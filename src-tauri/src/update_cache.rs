@@ -0,0 +1,95 @@
+//! Installer files the updater plugin leaves behind.
+//!
+//! On Windows the plugin unpacks each download into a kept
+//! `{app}-{version}-updater-XXXXXX` directory under the temp dir and never
+//! removes it. Other platforms clean up after themselves, so nothing matches
+//! there.
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntry {
+    pub path: String,
+    pub version: String,
+    pub size_bytes: u64,
+    /// Holds the update installed this session and waiting for a restart;
+    /// `clear` leaves it alone.
+    pub pending: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheInfo {
+    /// Where the plugin writes its directories.
+    pub path: String,
+    pub size_bytes: u64,
+    pub entries: Vec<CacheEntry>,
+}
+
+/// The version in an updater directory name, if it is one of `app_name`'s.
+fn entry_version(name: &str, app_name: &str) -> Option<String> {
+    let rest = name.strip_prefix(app_name)?.strip_prefix('-')?;
+    let (version, _) = rest.rsplit_once("-updater-")?;
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+fn size_of(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| size_of(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Updater directories for `app_name` in `temp_dir`, oldest version name
+/// first. `pending_version` marks the one to keep.
+pub fn info(temp_dir: &Path, app_name: &str, pending_version: Option<&str>) -> CacheInfo {
+    let mut entries = fs::read_dir(temp_dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+                .filter_map(|entry| {
+                    let version = entry_version(&entry.file_name().to_string_lossy(), app_name)?;
+                    let path = entry.path();
+                    Some(CacheEntry {
+                        size_bytes: size_of(&path),
+                        pending: pending_version == Some(version.as_str()),
+                        path: path.display().to_string(),
+                        version,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    entries.sort_by(|a, b| a.version.cmp(&b.version).then(a.path.cmp(&b.path)));
+    CacheInfo {
+        path: temp_dir.display().to_string(),
+        size_bytes: entries.iter().map(|entry| entry.size_bytes).sum(),
+        entries,
+    }
+}
+
+/// Delete every entry of `info` except the pending one. Returns the bytes
+/// freed; the first failure stops the sweep.
+pub fn clear(info: &CacheInfo) -> Result<u64, String> {
+    let mut freed = 0;
+    for entry in info.entries.iter().filter(|entry| !entry.pending) {
+        fs::remove_dir_all(&entry.path)
+            .map_err(|e| format!("Failed to delete {}: {e}", entry.path))?;
+        freed += entry.size_bytes;
+    }
+    Ok(freed)
+}